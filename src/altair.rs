@@ -0,0 +1,369 @@
+use crate::io::{IoDevice, PortMap, UnmappedPorts};
+use crate::{I8080Core, LoadRomResult, StepInstructionResult};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+pub mod disk;
+pub mod front_panel;
+
+use disk::{DiskController, DCDD_BASE_PORT};
+use front_panel::FrontPanel;
+
+/*
+ * MITS Altair 8800
+ *
+ * A plain 64K RAM S-100 machine, everything else is a card on the bus. The serial cards are what
+ * the terminal hangs off:
+ *   88-SIO   one channel, status on the base port and data on base + 1 (usually 0x00/0x01), both
+ *            status bits are active low: bit 0 = 0 when a character is waiting, bit 7 = 0 when the
+ *            transmitter is free
+ *   88-2SIO  two MC6850 ACIAs, channel A status/control on 0x10 and data on 0x11 (channel B on
+ *            0x12/0x13), bit 0 (RDRF) = 1 when a character is waiting, bit 1 (TDRE) = 1 when the
+ *            transmitter is free
+ * IN 0xFF reads the front panel sense switches (A8-A15, see front_panel.rs) which BASIC and the
+ * monitors use to pick their console card. The 88-DCDD disk controller (see disk.rs) sits on 0x08-0x0A when fitted.
+ * Every card and the sense switches are devices on a PortMap in core.io, ports nothing answers read 0xFF.
+ */
+
+pub const SIO_DEFAULT_PORT: u8 = 0x00;
+pub const TWO_SIO_DEFAULT_PORT: u8 = 0x10;
+pub const SENSE_SWITCH_PORT: u8 = 0xFF;
+
+// 6850 control value that resets the ACIA
+const ACIA_MASTER_RESET: u8 = 0x03;
+
+/*
+ * SerialBackend - Where a serial channel's characters come from and go to
+ */
+pub trait SerialBackend {
+    // Returns the next received character without blocking, None if there isn't one yet
+    fn receive(&mut self) -> Option<u8>;
+    fn transmit(&mut self, byte: u8);
+}
+
+/*
+ * spawn_reader - Helper Function
+ * Expects: reader to block until it has data
+ * Does: Moves the blocking reads onto a thread feeding a channel so receive never blocks the core
+ * Returns: The receiving end of the channel
+ */
+fn spawn_reader<R: Read + Send + 'static>(mut reader: R) -> Receiver<u8> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut byte = [0u8; 1];
+        while let Ok(1) = reader.read(&mut byte) {
+            if sender.send(byte[0]).is_err() {
+                break;
+            }
+        }
+    });
+    return receiver;
+}
+
+/*
+ * StdioSerial - Connects a channel to the host's stdin and stdout
+ * Line feeds typed on the host are sent as carriage returns since that's what the Altair software
+ * waits for, output has the parity/high bit stripped.
+ */
+pub struct StdioSerial {
+    input: Receiver<u8>,
+}
+
+impl Default for StdioSerial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StdioSerial {
+    pub fn new() -> Self {
+        Self {
+            input: spawn_reader(io::stdin()),
+        }
+    }
+}
+
+impl SerialBackend for StdioSerial {
+    fn receive(&mut self) -> Option<u8> {
+        match self.input.try_recv() {
+            Ok(b'\n') => Some(b'\r'),
+            Ok(byte) => Some(byte),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(&[byte & 0x7F]);
+        let _ = stdout.flush();
+    }
+}
+
+/*
+ * DeviceSerial - Connects a channel to a character device such as a pty (e.g. one made by socat)
+ * or a real serial port, bytes go through unchanged
+ */
+pub struct DeviceSerial {
+    input: Receiver<u8>,
+    output: File,
+}
+
+impl DeviceSerial {
+    /*
+     * open - Function
+     * Expects: path to be a readable and writable character device
+     * Does: Opens the device once for the reader thread and once for writing
+     * Returns: The DeviceSerial or the error from opening the device
+     */
+    pub fn open(path: &str) -> io::Result<Self> {
+        let reader = File::open(path)?;
+        let output = OpenOptions::new().write(true).open(path)?;
+        Ok(Self {
+            input: spawn_reader(reader),
+            output,
+        })
+    }
+}
+
+impl SerialBackend for DeviceSerial {
+    fn receive(&mut self) -> Option<u8> {
+        return self.input.try_recv().ok();
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        let _ = self.output.write_all(&[byte]);
+        let _ = self.output.flush();
+    }
+}
+
+/*
+ * BufferSerial - An in memory channel, input is queued up front and output is collected, used for
+ * scripted sessions and tests
+ */
+#[derive(Default)]
+pub struct BufferSerial {
+    pub input: VecDeque<u8>,
+    pub output: Vec<u8>,
+}
+
+impl BufferSerial {
+    pub fn new(input: &[u8]) -> Self {
+        Self {
+            input: input.iter().copied().collect(),
+            output: Vec::new(),
+        }
+    }
+}
+
+impl SerialBackend for BufferSerial {
+    fn receive(&mut self) -> Option<u8> {
+        return self.input.pop_front();
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        self.output.push(byte);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SerialBoardKind {
+    Sio,
+    TwoSio,
+}
+
+/*
+ * SerialBoard - One serial channel of an 88-SIO or 88-2SIO, occupying base_port and base_port + 1
+ */
+pub struct SerialBoard {
+    pub kind: SerialBoardKind,
+    pub base_port: u8,
+    pub backend: Box<dyn SerialBackend>,
+    // character received from the backend but not yet read by the program
+    received: Option<u8>,
+}
+
+impl SerialBoard {
+    pub fn new(kind: SerialBoardKind, base_port: u8, backend: Box<dyn SerialBackend>) -> Self {
+        Self {
+            kind,
+            base_port,
+            backend,
+            received: None,
+        }
+    }
+
+    /*
+     * poll - Helper Function
+     * Expects: N/A
+     * Does: Pulls a character from the backend if the receive register is empty
+     * Returns: true if a character is waiting to be read
+     */
+    fn poll(&mut self) -> bool {
+        if self.received.is_none() {
+            self.received = self.backend.receive();
+        }
+        return self.received.is_some();
+    }
+
+    /*
+     * read - Function
+     * Expects: port to be base_port or base_port + 1, the two ports attach_serial maps
+     * Does: Reads the status register or takes the received character
+     * Returns: The byte read
+     */
+    pub fn read(&mut self, port: u8) -> u8 {
+        if port == self.base_port {
+            let ready = self.poll();
+            return match self.kind {
+                // active low, bit 7 (transmitter busy) stays clear
+                SerialBoardKind::Sio => if ready { 0x00 } else { 0x01 },
+                // TDRE always set, RDRF when a character is waiting
+                SerialBoardKind::TwoSio => if ready { 0x03 } else { 0x02 },
+            };
+        }
+        self.poll();
+        return self.received.take().unwrap_or(0);
+    }
+
+    /*
+     * write - Function
+     * Expects: port to be base_port or base_port + 1, the two ports attach_serial maps
+     * Does: Sends data writes to the backend, a master reset on the 2SIO control register drops any
+     * pending character and other control writes are accepted and ignored
+     * Returns: N/A
+     */
+    pub fn write(&mut self, port: u8, value: u8) {
+        if port == self.base_port {
+            if self.kind == SerialBoardKind::TwoSio && value & 0x03 == ACIA_MASTER_RESET {
+                self.received = None;
+            }
+            return;
+        }
+        self.backend.transmit(value);
+    }
+}
+
+impl IoDevice for SerialBoard {
+    fn input(&mut self, port: u8) -> u8 {
+        return self.read(port);
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.write(port, value);
+    }
+}
+
+/*
+ * SenseSwitches - IN 0xFF as a device, value is the front panel's A8-A15 as of the instruction
+ * being run (Altair::step copies them in)
+ */
+#[derive(Default)]
+pub struct SenseSwitches {
+    pub value: u8,
+}
+
+impl IoDevice for SenseSwitches {
+    fn input(&mut self, _port: u8) -> u8 {
+        return self.value;
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+}
+
+pub struct Altair {
+    pub core: I8080Core,
+    // The bus the cards are plugged into, also attached to core.io
+    pub ports: Rc<RefCell<PortMap>>,
+    pub serial_boards: Vec<Rc<RefCell<SerialBoard>>>,
+    pub disk_controller: Option<Rc<RefCell<DiskController>>>,
+    pub sense_switches: Rc<RefCell<SenseSwitches>>,
+    pub front_panel: FrontPanel,
+}
+
+impl Default for Altair {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Altair {
+    pub fn new() -> Self {
+        let mut core = I8080Core::new();
+        let sense_switches = Rc::new(RefCell::new(SenseSwitches::default()));
+        let ports = Rc::new(RefCell::new(PortMap::new(UnmappedPorts::Float)));
+        let id = ports.borrow_mut().add(Box::new(sense_switches.clone()));
+        ports.borrow_mut().map_input(SENSE_SWITCH_PORT..=SENSE_SWITCH_PORT, id);
+        core.io = Some(Box::new(ports.clone()));
+
+        Self {
+            core,
+            ports,
+            serial_boards: Vec::new(),
+            disk_controller: None,
+            sense_switches,
+            front_panel: FrontPanel::new(),
+        }
+    }
+
+    /*
+     * attach_serial - Function
+     * Expects: base_port not to overlap another card
+     * Does: Plugs a serial channel into the bus on base_port and base_port + 1
+     * Returns: N/A
+     */
+    pub fn attach_serial(&mut self, kind: SerialBoardKind, base_port: u8, backend: Box<dyn SerialBackend>) {
+        let board = Rc::new(RefCell::new(SerialBoard::new(kind, base_port, backend)));
+        self.ports.borrow_mut().map(base_port..=base_port.wrapping_add(1), Box::new(board.clone()));
+        self.serial_boards.push(board);
+    }
+
+    /*
+     * attach_disk_controller - Function
+     * Expects: N/A
+     * Does: Plugs the 88-DCDD into the bus on 0x08-0x0A, replacing any controller already there
+     * Returns: N/A
+     */
+    pub fn attach_disk_controller(&mut self, controller: DiskController) {
+        let controller = Rc::new(RefCell::new(controller));
+        self.ports.borrow_mut().map(DCDD_BASE_PORT..=DCDD_BASE_PORT + 2, Box::new(controller.clone()));
+        self.disk_controller = Some(controller);
+    }
+
+    /*
+     * load_program - Function
+     * Expects: path to be a binary image (ROM dump or tape image already converted to binary)
+     * Does: Loads it at address and points the PC at it
+     * Returns: A LoadRomResult that indicates what happened
+     */
+    pub fn load_program(&mut self, path: &str, address: u16) -> LoadRomResult {
+        return self.core.i8080_load_rom(path, address);
+    }
+
+    /*
+     * run_cycles - Function
+     * Expects: A program to have been loaded
+     * Does: Steps the machine until at least cycles clock cycles have passed, stopping early if an
+     * instruction halts or errors, nothing runs while the front panel is stopped
+     * Returns: The StepInstructionResult of the last instruction executed
+     */
+    pub fn run_cycles(&mut self, cycles: u64) -> StepInstructionResult {
+        if !self.front_panel.running {
+            return StepInstructionResult::Ok;
+        }
+        let target = self.core.cycles.wrapping_add(cycles);
+        let mut result = StepInstructionResult::Ok;
+
+        while self.core.cycles < target {
+            result = self.step();
+            if result == StepInstructionResult::Halt || result.is_failure() {
+                break;
+            }
+        }
+        return result;
+    }
+}
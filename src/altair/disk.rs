@@ -0,0 +1,284 @@
+use crate::disk_image::DiskGeometry;
+use crate::io::IoDevice;
+use std::io;
+
+pub use crate::disk_image::DiskImage;
+
+/*
+ * MITS 88-DCDD floppy disk controller
+ *
+ * Ports (every status bit is active low, 0 means true):
+ *   0x08 OUT  select drive: bits 0-3 drive number, bit 7 deselects
+ *   0x08 IN   status: bit 0 ready for write data (ENWD), bit 1 head can move, bit 2 head loaded,
+ *             bit 5 interrupts enabled, bit 6 on track 0, bit 7 read data available (NRDA)
+ *   0x09 OUT  control: bit 0 step in, bit 1 step out, bit 2 load head, bit 3 unload head,
+ *             bit 4/5 interrupt enable/disable, bit 6 head current switch, bit 7 write enable
+ *   0x09 IN   sector position: bit 0 sector true (0 at the start of a sector), bits 1-5 sector number
+ *   0x0A IN   read the next byte of the sector under the head
+ *   0x0A OUT  write the next byte of the sector under the head
+ *
+ * An 8" disk is 77 tracks of 32 sectors of 137 bytes and images are just those sectors in order.
+ * The disk spins under the head so every time the program polls the sector position while the
+ * sector true bit is clear the next sector comes round, which is enough for the MITS and CP/M
+ * boot loaders to find the sector they want.
+ */
+
+pub const GEOMETRY: DiskGeometry = DiskGeometry {
+    sector_size: 137,
+    sectors_per_track: 32,
+    tracks: 77,
+    first_sector: 0,
+};
+pub const SECTOR_SIZE: usize = GEOMETRY.sector_size;
+pub const SECTORS_PER_TRACK: usize = GEOMETRY.sectors_per_track;
+pub const TRACKS: usize = GEOMETRY.tracks;
+pub const DISK_SIZE: usize = GEOMETRY.disk_size();
+pub const MAX_DRIVES: usize = 16;
+
+pub const DCDD_BASE_PORT: u8 = 0x08;
+
+// Status bits (active low)
+const STATUS_ENWD: u8 = 0x01;
+const STATUS_MOVE_HEAD: u8 = 0x02;
+const STATUS_HEAD: u8 = 0x04;
+const STATUS_INTERRUPTS: u8 = 0x20;
+const STATUS_TRACK_0: u8 = 0x40;
+const STATUS_NRDA: u8 = 0x80;
+
+// Control bits
+const CONTROL_STEP_IN: u8 = 0x01;
+const CONTROL_STEP_OUT: u8 = 0x02;
+const CONTROL_HEAD_LOAD: u8 = 0x04;
+const CONTROL_HEAD_UNLOAD: u8 = 0x08;
+const CONTROL_INTERRUPT_ENABLE: u8 = 0x10;
+const CONTROL_INTERRUPT_DISABLE: u8 = 0x20;
+const CONTROL_WRITE_ENABLE: u8 = 0x80;
+
+/*
+ * DiskController - The 88-DCDD with up to 16 drives
+ */
+pub struct DiskController {
+    pub drives: Vec<Option<DiskImage>>,
+    pub selected: Option<usize>,
+    pub track: usize,
+    pub sector: usize,
+    pub head_loaded: bool,
+    pub interrupts_enabled: bool,
+    sector_true: bool,
+    byte_index: usize,
+    writing: bool,
+    write_buffer: Vec<u8>,
+    last_error: Option<io::Error>,
+}
+
+impl Default for DiskController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiskController {
+    pub fn new() -> Self {
+        Self {
+            drives: (0..MAX_DRIVES).map(|_| None).collect(),
+            selected: None,
+            track: 0,
+            sector: 0,
+            head_loaded: false,
+            interrupts_enabled: false,
+            sector_true: false,
+            byte_index: 0,
+            writing: false,
+            write_buffer: Vec::with_capacity(SECTOR_SIZE),
+            last_error: None,
+        }
+    }
+
+    /*
+     * insert - Function
+     * Expects: drive to be under MAX_DRIVES
+     * Does: Puts disk in drive, replacing whatever was there
+     * Returns: N/A
+     */
+    pub fn insert(&mut self, drive: usize, disk: DiskImage) {
+        self.drives[drive] = Some(disk);
+    }
+
+    /*
+     * selected_disk - Helper Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The disk in the selected drive if a drive is selected and has a disk in it
+     */
+    fn selected_disk(&mut self) -> Option<&mut DiskImage> {
+        match self.selected {
+            Some(drive) => self.drives[drive].as_mut(),
+            None => None,
+        }
+    }
+
+    /*
+     * take_error - Function
+     * Expects: N/A
+     * Does: Clears the recorded error
+     * Returns: The error from the last sector write that failed since the previous call, if any
+     */
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        return self.last_error.take();
+    }
+
+    /*
+     * read - Function
+     * Expects: port to be one of 0x08-0x0A, the ports attach_disk_controller maps
+     * Does: Reads the status, the sector position (moving the disk on) or the next data byte
+     * Returns: The byte read
+     */
+    pub fn read(&mut self, port: u8) -> u8 {
+        if self.selected_disk().is_none() {
+            return 0xFF;
+        }
+
+        match port - DCDD_BASE_PORT {
+            0 => {
+                // build the active high version and invert it
+                let mut status = STATUS_MOVE_HEAD;
+                if self.writing {
+                    status |= STATUS_ENWD;
+                }
+                if self.head_loaded {
+                    status |= STATUS_HEAD | STATUS_NRDA;
+                }
+                if self.interrupts_enabled {
+                    status |= STATUS_INTERRUPTS;
+                }
+                if self.track == 0 {
+                    status |= STATUS_TRACK_0;
+                }
+                return !status;
+            }
+            1 => {
+                if !self.head_loaded {
+                    return 0xFF;
+                }
+                if self.sector_true {
+                    self.sector_true = false;
+                } else {
+                    self.flush_write();
+                    self.sector = (self.sector + 1) % SECTORS_PER_TRACK;
+                    self.sector_true = true;
+                    self.byte_index = 0;
+                }
+                // sector true is active low
+                return 0xC0 | ((self.sector as u8) << 1) | if self.sector_true { 0 } else { 1 };
+            }
+            _ => {
+                let (track, sector, index) = (self.track, self.sector, self.byte_index);
+                self.byte_index = (self.byte_index + 1) % SECTOR_SIZE;
+                let disk = self.selected_disk().unwrap();
+                return disk.read_sector(track, sector).map_or(0xFF, |data| data[index]);
+            }
+        }
+    }
+
+    /*
+     * write - Function
+     * Expects: port to be one of 0x08-0x0A, the ports attach_disk_controller maps
+     * Does: Selects a drive, carries out the control bits or queues a data byte for the sector being
+     * written (the sector goes to the disk once all 137 bytes are in)
+     * Returns: N/A
+     */
+    pub fn write(&mut self, port: u8, value: u8) {
+        match port - DCDD_BASE_PORT {
+            0 => {
+                self.flush_write();
+                let drive = (value & 0x0F) as usize;
+                if value & 0x80 != 0 || self.drives[drive].is_none() {
+                    self.selected = None;
+                    self.head_loaded = false;
+                } else {
+                    self.selected = Some(drive);
+                }
+            }
+            1 => {
+                if self.selected_disk().is_none() {
+                    return;
+                }
+                if value & CONTROL_STEP_IN != 0 && self.track < TRACKS - 1 {
+                    self.flush_write();
+                    self.track += 1;
+                }
+                if value & CONTROL_STEP_OUT != 0 && self.track > 0 {
+                    self.flush_write();
+                    self.track -= 1;
+                }
+                if value & CONTROL_HEAD_LOAD != 0 {
+                    self.head_loaded = true;
+                }
+                if value & CONTROL_HEAD_UNLOAD != 0 {
+                    self.flush_write();
+                    self.head_loaded = false;
+                }
+                if value & CONTROL_INTERRUPT_ENABLE != 0 {
+                    self.interrupts_enabled = true;
+                }
+                if value & CONTROL_INTERRUPT_DISABLE != 0 {
+                    self.interrupts_enabled = false;
+                }
+                if value & CONTROL_WRITE_ENABLE != 0 {
+                    self.writing = true;
+                    self.write_buffer.clear();
+                }
+            }
+            _ => {
+                if !self.writing {
+                    return;
+                }
+                self.write_buffer.push(value);
+                if self.write_buffer.len() == SECTOR_SIZE {
+                    self.flush_write();
+                }
+            }
+        }
+    }
+
+    /*
+     * flush_write - Helper Function
+     * Expects: N/A
+     * Does: Ends a sector write, whatever has been written so far goes to the disk with the rest of
+     * the sector zero filled just like the controller writes zeros once the program stops feeding it,
+     * a failed write is kept for take_error
+     * Returns: N/A
+     */
+    fn flush_write(&mut self) {
+        if !self.writing {
+            return;
+        }
+        self.writing = false;
+        if self.write_buffer.is_empty() {
+            return;
+        }
+        let mut sector = std::mem::take(&mut self.write_buffer);
+        sector.resize(SECTOR_SIZE, 0);
+        let (track, index) = (self.track, self.sector);
+        if let Some(disk) = self.selected_disk() {
+            if let Err(e) = disk.write_sector(track, index, &sector) {
+                self.last_error = Some(io::Error::new(
+                    e.kind(),
+                    format!("track {} sector {}: {}", track, index, e),
+                ));
+            }
+        }
+        self.write_buffer = sector;
+        self.write_buffer.clear();
+    }
+}
+
+impl IoDevice for DiskController {
+    fn input(&mut self, port: u8) -> u8 {
+        return self.read(port);
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.write(port, value);
+    }
+}
@@ -0,0 +1,350 @@
+use super::Altair;
+use crate::instruction::{IndirectPair, Instruction, Reg};
+use crate::{I8080Core, StepInstructionResult};
+
+/*
+ * Altair 8800 front panel
+ *
+ * Sixteen address switches (A0-A15), the low eight double as the data switches for DEPOSIT and the
+ * high eight are the sense switches a program reads with IN 0xFF. The control switches work on the
+ * core's registers directly the way the panel did through the bus:
+ *   EXAMINE       jam the address switches into the PC and show that memory location
+ *   EXAMINE NEXT  advance the PC and show the next location
+ *   DEPOSIT       store the data switches at the PC
+ *   DEPOSIT NEXT  advance the PC and store the data switches there
+ *   RUN / STOP    start and stop the processor, SINGLE STEP runs one instruction while stopped
+ *   RESET         PC to 0, interrupts off and out of a HLT
+ * The panel only acts on EXAMINE/DEPOSIT/SINGLE STEP while the machine is stopped, like the real one.
+ *
+ * The LEDs follow the bus. While running they show the last memory or I/O access the instruction
+ * made (so the LDAX D in "kill the bit" lights the bit in D on A8-A15) and while stopped they sit on
+ * the next instruction fetch like the processor waiting in its M1 cycle.
+ */
+
+/*
+ * PanelLeds - What the panel lights show, one bool per status light
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct PanelLeds {
+    pub address: u16,
+    pub data: u8,
+    pub inte: bool,
+    pub wait: bool,
+    pub hlta: bool,
+    pub m1: bool,
+    pub memr: bool,
+    pub inp: bool,
+    pub out: bool,
+    pub stack: bool,
+    // lit when the cycle is a write, the panel's WO light is really active low
+    pub wo: bool,
+}
+
+impl PanelLeds {
+    /*
+     * render - Function
+     * Expects: N/A
+     * Does: Draws the lights as text, '*' for on and '.' for off, most significant bit first
+     * Returns: The status, address and data rows on one line
+     */
+    pub fn render(&self) -> String {
+        let light = |on: bool| if on { '*' } else { '.' };
+        let status = [
+            ("INTE", self.inte),
+            ("WAIT", self.wait),
+            ("HLTA", self.hlta),
+            ("M1", self.m1),
+            ("MEMR", self.memr),
+            ("INP", self.inp),
+            ("OUT", self.out),
+            ("STACK", self.stack),
+            ("WO", self.wo),
+        ];
+        let mut text = String::new();
+        for (name, on) in status {
+            text.push_str(name);
+            text.push(light(on));
+            text.push(' ');
+        }
+        text.push_str("A ");
+        text.extend((0..16).rev().map(|bit| light(self.address & (1 << bit) != 0)));
+        text.push_str(" D ");
+        text.extend((0..8).rev().map(|bit| light(self.data & (1 << bit) != 0)));
+        return text;
+    }
+}
+
+pub struct FrontPanel {
+    // A0-A15, see data_switches and sense_switches
+    pub switches: u16,
+    pub running: bool,
+    pub leds: PanelLeds,
+}
+
+impl Default for FrontPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrontPanel {
+    // Starts out running so a program loaded by the emulator goes straight away, STOP it to toggle
+    // something in by hand
+    pub fn new() -> Self {
+        Self {
+            switches: 0,
+            running: true,
+            leds: PanelLeds::default(),
+        }
+    }
+
+    pub fn data_switches(&self) -> u8 {
+        return self.switches as u8;
+    }
+
+    pub fn sense_switches(&self) -> u8 {
+        return (self.switches >> 8) as u8;
+    }
+}
+
+/*
+ * BusCycle - The memory or I/O access an instruction is about to make, worked out from the opcode
+ * and the registers before it runs
+ */
+enum BusCycle {
+    Fetch,
+    Read(u16),
+    Write(u16),
+    StackRead(u16),
+    StackWrite,
+    Input(u8),
+    Output(u8),
+}
+
+/*
+ * bus_cycle - Helper Function
+ * Expects: core not to have executed the instruction at the PC yet
+ * Does: Picks out the data access the instruction makes, a conditional CALL/RET that won't be taken
+ * is just a fetch
+ * Returns: The BusCycle
+ */
+fn bus_cycle(core: &I8080Core) -> BusCycle {
+    match core.decode_at(core.program_counter) {
+        Instruction::Mov(Reg::M, _) | Instruction::Mvi(Reg::M, _) | Instruction::Inr(Reg::M) | Instruction::Dcr(Reg::M) => {
+            BusCycle::Write(core.hl())
+        }
+        Instruction::Mov(_, Reg::M)
+        | Instruction::Add(Reg::M)
+        | Instruction::Adc(Reg::M)
+        | Instruction::Sub(Reg::M)
+        | Instruction::Sbb(Reg::M)
+        | Instruction::Ana(Reg::M)
+        | Instruction::Xra(Reg::M)
+        | Instruction::Ora(Reg::M)
+        | Instruction::Cmp(Reg::M) => BusCycle::Read(core.hl()),
+        Instruction::Ldax(IndirectPair::BC) => BusCycle::Read(core.bc()),
+        Instruction::Ldax(IndirectPair::DE) => BusCycle::Read(core.de()),
+        Instruction::Stax(IndirectPair::BC) => BusCycle::Write(core.bc()),
+        Instruction::Stax(IndirectPair::DE) => BusCycle::Write(core.de()),
+        Instruction::Lda(direct) | Instruction::Lhld(direct) => BusCycle::Read(direct),
+        Instruction::Sta(direct) | Instruction::Shld(direct) => BusCycle::Write(direct),
+        Instruction::In(port) => BusCycle::Input(port),
+        Instruction::Out(port) => BusCycle::Output(port),
+        Instruction::Xthl | Instruction::Pop(_) | Instruction::Ret => BusCycle::StackRead(core.stack_pointer),
+        Instruction::Push(_) | Instruction::Call(_) | Instruction::Rst(_) => BusCycle::StackWrite,
+        Instruction::Rcc(cond) if cond.met(core) => BusCycle::StackRead(core.stack_pointer),
+        Instruction::Ccc(cond, _) if cond.met(core) => BusCycle::StackWrite,
+        _ => BusCycle::Fetch,
+    }
+}
+
+impl Altair {
+    /*
+     * show_fetch - Helper Function
+     * Expects: N/A
+     * Does: Puts the LEDs on the next instruction fetch, where a stopped processor sits
+     * Returns: N/A
+     */
+    fn show_fetch(&mut self) {
+        let pc = self.core.program_counter;
+        self.front_panel.leds = PanelLeds {
+            address: pc,
+            data: self.core.read_memory(pc),
+            inte: self.core.interrupts_enabled,
+            wait: !self.front_panel.running,
+            hlta: self.core.halted,
+            m1: true,
+            memr: true,
+            ..PanelLeds::default()
+        };
+    }
+
+    /*
+     * step - Function
+     * Expects: A program to have been loaded
+     * Does: Executes one instruction with the sense switches as the panel has them and updates the
+     * front panel LEDs with the bus access it made
+     * Returns: The StepInstructionResult from the core
+     */
+    pub fn step(&mut self) -> StepInstructionResult {
+        let cycle = if self.core.halted { BusCycle::Fetch } else { bus_cycle(&self.core) };
+        self.sense_switches.borrow_mut().value = self.front_panel.sense_switches();
+        let result = self.core.i8080_step();
+
+        let mut leds = PanelLeds {
+            inte: self.core.interrupts_enabled,
+            wait: !self.front_panel.running,
+            hlta: self.core.halted,
+            ..PanelLeds::default()
+        };
+        match cycle {
+            BusCycle::Fetch => {
+                self.show_fetch();
+                return result;
+            }
+            BusCycle::Read(address) => {
+                leds.address = address;
+                leds.memr = true;
+            }
+            BusCycle::Write(address) => {
+                leds.address = address;
+                leds.wo = true;
+            }
+            BusCycle::StackRead(address) => {
+                leds.address = address;
+                leds.memr = true;
+                leds.stack = true;
+            }
+            BusCycle::StackWrite => {
+                leds.address = self.core.stack_pointer;
+                leds.wo = true;
+                leds.stack = true;
+            }
+            // the port number shows on both halves of the address bus
+            BusCycle::Input(port) => {
+                leds.address = u16::from_le_bytes([port, port]);
+                leds.data = self.core.a;
+                leds.inp = true;
+                self.front_panel.leds = leds;
+                return result;
+            }
+            BusCycle::Output(port) => {
+                leds.address = u16::from_le_bytes([port, port]);
+                leds.data = self.core.a;
+                leds.out = true;
+                leds.wo = true;
+                self.front_panel.leds = leds;
+                return result;
+            }
+        }
+        leds.data = self.core.read_memory(leds.address);
+        self.front_panel.leds = leds;
+        return result;
+    }
+
+    /*
+     * examine - Function
+     * Expects: The machine to be stopped
+     * Does: Loads the address switches into the PC and shows that location on the LEDs
+     * Returns: N/A
+     */
+    pub fn examine(&mut self) {
+        if self.front_panel.running {
+            return;
+        }
+        self.core.program_counter = self.front_panel.switches;
+        self.show_fetch();
+    }
+
+    /*
+     * examine_next - Function
+     * Expects: The machine to be stopped
+     * Does: Advances the PC and shows the next location
+     * Returns: N/A
+     */
+    pub fn examine_next(&mut self) {
+        if self.front_panel.running {
+            return;
+        }
+        self.core.program_counter = self.core.program_counter.wrapping_add(1);
+        self.show_fetch();
+    }
+
+    /*
+     * deposit - Function
+     * Expects: The machine to be stopped
+     * Does: Writes the data switches to the location the PC points at
+     * Returns: N/A
+     */
+    pub fn deposit(&mut self) {
+        if self.front_panel.running {
+            return;
+        }
+        let value = self.front_panel.data_switches();
+        self.core.write_memory(self.core.program_counter, value);
+        self.show_fetch();
+    }
+
+    /*
+     * deposit_next - Function
+     * Expects: The machine to be stopped
+     * Does: Advances the PC and writes the data switches there
+     * Returns: N/A
+     */
+    pub fn deposit_next(&mut self) {
+        if self.front_panel.running {
+            return;
+        }
+        self.core.program_counter = self.core.program_counter.wrapping_add(1);
+        self.deposit();
+    }
+
+    /*
+     * run - Function
+     * Expects: N/A
+     * Does: Lets run_cycles execute instructions again
+     * Returns: N/A
+     */
+    pub fn run(&mut self) {
+        self.front_panel.running = true;
+        self.front_panel.leds.wait = false;
+    }
+
+    /*
+     * stop - Function
+     * Expects: N/A
+     * Does: Stops the processor before its next instruction and shows where it stopped
+     * Returns: N/A
+     */
+    pub fn stop(&mut self) {
+        self.front_panel.running = false;
+        self.show_fetch();
+    }
+
+    /*
+     * single_step - Function
+     * Expects: The machine to be stopped
+     * Does: Executes one instruction and shows the next fetch
+     * Returns: The StepInstructionResult from the core, StepInstructionResult::Ok if running
+     */
+    pub fn single_step(&mut self) -> StepInstructionResult {
+        if self.front_panel.running {
+            return StepInstructionResult::Ok;
+        }
+        let result = self.step();
+        self.show_fetch();
+        return result;
+    }
+
+    /*
+     * reset - Function
+     * Expects: N/A
+     * Does: The RESET switch, pulls the processor's RESET pin (see I8080Core::reset) so memory and
+     * the other registers are left alone
+     * Returns: N/A
+     */
+    pub fn reset(&mut self) {
+        self.core.reset();
+        self.show_fetch();
+    }
+}
@@ -0,0 +1,122 @@
+use i8080_emulator::altair::disk::{DiskController, DiskImage, GEOMETRY, MAX_DRIVES};
+use i8080_emulator::altair::{
+    Altair, DeviceSerial, SerialBackend, SerialBoardKind, StdioSerial, SIO_DEFAULT_PORT, TWO_SIO_DEFAULT_PORT,
+};
+use i8080_emulator::{LoadRomResult, StepInstructionResult, Throttle, I8080_CLOCK_HZ};
+
+const USAGE: &str = "Usage: altair <PROGRAM> [--address HEX] [--sio | --2sio] [--tty PATH] [--sense HEX] [--mhz MHZ] [--disk IMAGE ...]";
+
+/*
+ * parse_hex - Helper Function
+ * Expects: N/A
+ * Does: Parses a hex number with or without a 0x prefix
+ * Returns: The value or None if it isn't valid hex
+ */
+fn parse_hex(text: &str) -> Option<u32> {
+    u32::from_str_radix(text.trim_start_matches("0x"), 16).ok()
+}
+
+/*
+ * Usage: altair <PROGRAM> [--address HEX] [--sio | --2sio] [--tty PATH] [--sense HEX] [--mhz MHZ]
+ * Loads a binary image (0x0000 by default) and runs it on an Altair with the console on an 88-2SIO
+ * at 0x10 (or an 88-SIO at 0x00 with --sio), talking to stdin/stdout or the device given by --tty
+ * Each --disk puts an image in the next drive of an 88-DCDD controller
+ */
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let mut address: u16 = 0;
+    let mut kind = SerialBoardKind::TwoSio;
+    let mut tty: Option<String> = None;
+    let mut sense_switches: u8 = 0;
+    let mut clock_hz = I8080_CLOCK_HZ;
+    let mut disks: Vec<String> = Vec::new();
+
+    let mut index = 2;
+    while index < args.len() {
+        let value = args.get(index + 1);
+        match (args[index].as_str(), value) {
+            ("--sio", _) => kind = SerialBoardKind::Sio,
+            ("--2sio", _) => kind = SerialBoardKind::TwoSio,
+            ("--address", Some(value)) => match parse_hex(value) {
+                Some(parsed) if parsed <= 0xFFFF => address = parsed as u16,
+                _ => return println!("Invalid address: {}", value),
+            },
+            ("--sense", Some(value)) => match parse_hex(value) {
+                Some(parsed) if parsed <= 0xFF => sense_switches = parsed as u8,
+                _ => return println!("Invalid sense switches: {}", value),
+            },
+            ("--tty", Some(value)) => tty = Some(value.clone()),
+            ("--disk", Some(value)) => disks.push(value.clone()),
+            ("--mhz", Some(value)) => match value.parse::<f64>() {
+                Ok(mhz) if mhz > 0.0 => clock_hz = (mhz * 1_000_000.0) as u64,
+                _ => return println!("Invalid clock speed: {}", value),
+            },
+            _ => return println!("{}", USAGE),
+        }
+        // flags other than --sio/--2sio take a value
+        index += if args[index] == "--sio" || args[index] == "--2sio" { 1 } else { 2 };
+    }
+
+    let backend: Box<dyn SerialBackend> = match tty {
+        Some(path) => match DeviceSerial::open(&path) {
+            Ok(device) => Box::new(device),
+            Err(e) => return println!("Failed to open {}: {}", path, e),
+        },
+        None => Box::new(StdioSerial::new()),
+    };
+    let base_port = match kind {
+        SerialBoardKind::Sio => SIO_DEFAULT_PORT,
+        SerialBoardKind::TwoSio => TWO_SIO_DEFAULT_PORT,
+    };
+
+    let mut altair = Altair::new();
+    altair.attach_serial(kind, base_port, backend);
+    altair.front_panel.switches = (sense_switches as u16) << 8;
+    if !disks.is_empty() {
+        if disks.len() > MAX_DRIVES {
+            return println!("The controller only has {} drives", MAX_DRIVES);
+        }
+        let mut controller = DiskController::new();
+        for (drive, path) in disks.iter().enumerate() {
+            match DiskImage::open(path, false, GEOMETRY) {
+                Ok(disk) => controller.insert(drive, disk),
+                Err(e) => return println!("Failed to open disk image {}: {}", path, e),
+            }
+        }
+        altair.attach_disk_controller(controller);
+    }
+    if altair.load_program(&args[1], address) != LoadRomResult::Ok {
+        return;
+    }
+
+    let mut throttle = Throttle::new(clock_hz);
+    throttle.restart(altair.core.cycles);
+    loop {
+        match altair.run_cycles(throttle.slice_cycles) {
+            StepInstructionResult::Halt => {
+                println!("\nEncountered a HALT STOPPING");
+                break;
+            }
+            StepInstructionResult::Error => {
+                println!("\nStep failed and returned an error so exiting");
+                break;
+            }
+            StepInstructionResult::IllegalOpcode { pc, opcode } => {
+                println!("\nIllegal opcode 0x{:02X} at 0x{:04X} so exiting", opcode, pc);
+                break;
+            }
+            _ => {}
+        }
+        if let Some(controller) = &altair.disk_controller {
+            if let Some(e) = controller.borrow_mut().take_error() {
+                println!("\nFailed to write to a disk image: {}", e);
+            }
+        }
+        throttle.pace(altair.core.cycles);
+    }
+}
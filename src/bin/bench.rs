@@ -0,0 +1,218 @@
+use i8080_emulator::{I8080Core, LoadRomResult, StepInstructionResult};
+use std::time::{Duration, Instant};
+
+// Clock speed of a stock 8080 used as the 1.0x reference for the emulated speed
+const REFERENCE_MHZ: f64 = 2.0;
+// Number of instructions each synthetic workload runs for
+const SYNTHETIC_INSTRUCTIONS: u64 = 50_000_000;
+// Upper bound on instructions for a ROM workload so a long exerciser still finishes
+const ROM_INSTRUCTION_LIMIT: u64 = 2_000_000_000;
+
+/*
+ * Workload - A named program that gets loaded at 0x0100 and looped over forever
+ */
+struct Workload {
+    name: &'static str,
+    program: &'static [u8],
+}
+
+/*
+ * All synthetic workloads are endless loops so the benchmark decides how long they run
+ */
+const WORKLOADS: [Workload; 4] = [
+    // DCR C / JNZ tight loop with an outer DCR B, mostly branch dispatch
+    Workload {
+        name: "branch loop",
+        program: &[
+            0x0D,             // 0100 DCR C
+            0xC2, 0x00, 0x01, // 0101 JNZ 0100
+            0x05,             // 0104 DCR B
+            0xC3, 0x00, 0x01, // 0105 JMP 0100
+        ],
+    },
+    // Mix of register ALU ops similar to what the exerciser hammers on
+    Workload {
+        name: "alu mix",
+        program: &[
+            0x80,             // 0100 ADD B
+            0x89,             // 0101 ADC C
+            0x92,             // 0102 SUB D
+            0x9B,             // 0103 SBB E
+            0xA4,             // 0104 ANA H
+            0xAD,             // 0105 XRA L
+            0xB0,             // 0106 ORA B
+            0xB9,             // 0107 CMP C
+            0x27,             // 0108 DAA
+            0x07,             // 0109 RLC
+            0x04,             // 010A INR B
+            0x0D,             // 010B DCR C
+            0x3C,             // 010C INR A
+            0xC6, 0x5A,       // 010D ADI 5A
+            0xC3, 0x00, 0x01, // 010F JMP 0100
+        ],
+    },
+    // 256 byte block copy from 0x2000 to 0x4000 through HL and DE
+    Workload {
+        name: "memory copy",
+        program: &[
+            0x21, 0x00, 0x20, // 0100 LXI H,2000
+            0x11, 0x00, 0x40, // 0103 LXI D,4000
+            0x0E, 0x00,       // 0106 MVI C,00
+            0x7E,             // 0108 MOV A,M
+            0x12,             // 0109 STAX D
+            0x23,             // 010A INX H
+            0x13,             // 010B INX D
+            0x0D,             // 010C DCR C
+            0xC2, 0x08, 0x01, // 010D JNZ 0108
+            0xC3, 0x00, 0x01, // 0110 JMP 0100
+        ],
+    },
+    // CALL/RET with every PUSH and POP in the subroutine
+    Workload {
+        name: "call and stack",
+        program: &[
+            0x31, 0x00, 0xF0, // 0100 LXI SP,F000
+            0xCD, 0x0A, 0x01, // 0103 CALL 010A
+            0xC3, 0x03, 0x01, // 0106 JMP 0103
+            0x00,             // 0109 NOP
+            0xC5,             // 010A PUSH B
+            0xD5,             // 010B PUSH D
+            0xE5,             // 010C PUSH H
+            0xF5,             // 010D PUSH PSW
+            0xF1,             // 010E POP PSW
+            0xE1,             // 010F POP H
+            0xD1,             // 0110 POP D
+            0xC1,             // 0111 POP B
+            0xC9,             // 0112 RET
+        ],
+    },
+];
+
+/*
+ * BenchResult - What a single workload run measured
+ */
+struct BenchResult {
+    instructions: u64,
+    cycles: u64,
+    elapsed: Duration,
+}
+
+/*
+ * run_core - Runs the core until it has executed limit instructions, halts, errors or jumps to 0x0000
+ * Expects: core to be loaded with the program and its PC set
+ * Does: Steps the core as fast as possible timing the whole run
+ * Returns: A BenchResult with the instructions, cycles and wall clock time of the run
+ */
+fn run_core(core: &mut I8080Core, limit: u64) -> BenchResult {
+    let start_cycles = core.cycles;
+    let start = Instant::now();
+    let mut instructions = 0;
+
+    while instructions < limit {
+        let step_result = core.i8080_step();
+        instructions += 1;
+        if step_result == StepInstructionResult::Halt || step_result.is_failure() {
+            break;
+        }
+        if core.program_counter == 0 {
+            break;
+        }
+    }
+
+    BenchResult {
+        instructions,
+        cycles: core.cycles - start_cycles,
+        elapsed: start.elapsed(),
+    }
+}
+
+/*
+ * print_result - Prints one line of the report
+ * Expects: N/A
+ * Does: Converts the raw counts into instructions per second and emulated MHz
+ * Returns: N/A
+ */
+fn print_result(name: &str, result: &BenchResult) {
+    let seconds = result.elapsed.as_secs_f64().max(f64::EPSILON);
+    let ips = result.instructions as f64 / seconds;
+    let mhz = result.cycles as f64 / seconds / 1_000_000.0;
+    println!(
+        "{:<24} {:>14} {:>8.3}s {:>10.2} MIPS {:>10.2} MHz {:>8.1}x",
+        name,
+        result.instructions,
+        seconds,
+        ips / 1_000_000.0,
+        mhz,
+        mhz / REFERENCE_MHZ
+    );
+}
+
+/*
+ * run_synthetic - Runs one of the built in loops
+ * Expects: N/A
+ * Does: Loads the workload at 0x0100 and runs it for SYNTHETIC_INSTRUCTIONS
+ * Returns: The measured BenchResult
+ */
+fn run_synthetic(workload: &Workload) -> BenchResult {
+    let mut core = I8080Core::new();
+    core.memory[0x0100..0x0100 + workload.program.len()].copy_from_slice(workload.program);
+    core.program_counter = 0x0100;
+    run_core(&mut core, SYNTHETIC_INSTRUCTIONS)
+}
+
+/*
+ * run_rom - Runs a CP/M .COM program such as 8080EXM with console output discarded
+ * Expects: path to point at a CP/M program
+ * Does: Sets up the same minimal CP/M zero page and BDOS stub the test binary uses and runs the
+ * program until it warm boots (PC == 0) or hits ROM_INSTRUCTION_LIMIT
+ * Returns: The measured BenchResult or None if the ROM couldn't be loaded
+ */
+fn run_rom(path: &str) -> Option<BenchResult> {
+    let mut core = I8080Core::new();
+    if core.i8080_load_rom(path, 0x0100) != LoadRomResult::Ok {
+        return None;
+    }
+
+    // JMP 0x0000 at the warm boot vector and JMP to the BDOS stub at 0x0005, the high byte
+    // at 0x0007 doubles as the top of memory for LHLD 6 / SPHL
+    core.memory[0x0000..0x0003].copy_from_slice(&[0xC3, 0x00, 0x00]);
+    core.memory[0x0005..0x0008].copy_from_slice(&[0xC3, 0x00, 0xF0]);
+
+    // BDOS stub handling function 2 (print char) and 9 (print string) through OUT 0
+    let bdos_handler: [u8; 32] = [
+        0x79, 0xFE, 0x02, 0xC2, 0x0A, 0xF0, 0x7B, 0xD3, 0x00, 0xC9, 0xFE, 0x09, 0xC2, 0x1F, 0xF0, 0x7B,
+        0x6F, 0x7A, 0x67, 0x7E, 0xFE, 0x24, 0xCA, 0x1F, 0xF0, 0xD3, 0x00, 0x23, 0xC3, 0x13, 0xF0, 0xC9,
+    ];
+    core.memory[0xF000..0xF000 + bdos_handler.len()].copy_from_slice(&bdos_handler);
+
+    Some(run_core(&mut core, ROM_INSTRUCTION_LIMIT))
+}
+
+/*
+ * Usage: bench [PROGRAM.COM ...]
+ * Runs every synthetic workload and then any CP/M programs given on the command line (8080EXM.COM
+ * is the intended one). Build with --release, debug builds are an order of magnitude slower.
+ */
+fn main() {
+    println!(
+        "{:<24} {:>14} {:>9} {:>15} {:>14} {:>9}",
+        "workload", "instructions", "time", "speed", "clock", "vs 2MHz"
+    );
+
+    for workload in WORKLOADS.iter() {
+        let result = run_synthetic(workload);
+        print_result(workload.name, &result);
+    }
+
+    for path in std::env::args().skip(1) {
+        match run_rom(&path) {
+            Some(result) => {
+                let name = path.rsplit('/').next().unwrap_or(&path);
+                print_result(name, &result);
+            }
+            None => {
+                println!("Skipping {} as it couldn't be loaded", path);
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use i8080_emulator::altair::{SerialBackend, StdioSerial};
+use i8080_emulator::cpm::disk::{DiskImage, GEOMETRY};
+use i8080_emulator::cpm::host::HostDrive;
+use i8080_emulator::cpm::{Cpm, MAX_DRIVES};
+use i8080_emulator::terminal::{TerminalBackend, TerminalType};
+use i8080_emulator::{LoadRomResult, StepInstructionResult, Throttle, I8080_CLOCK_HZ};
+
+const USAGE: &str = "Usage: cpm <DISK> [DISK ...] [--ccp HEX] [--mhz MHZ] [--terminal adm3a|vt52]\n       cpm --run <PROGRAM.COM> [--drive DIR[,ro] ...] [--tail ARGS] [--mhz MHZ] [--terminal adm3a|vt52]";
+
+/*
+ * Usage: cpm <DISK> [DISK ...] [--ccp HEX] [--mhz MHZ] [--terminal adm3a|vt52]
+ *        cpm --run <PROGRAM.COM> [--drive DIR[,ro] ...] [--tail ARGS] [--mhz MHZ] [--terminal adm3a|vt52]
+ * Boots CP/M 2.2 from the system tracks of the first 8" SSSD image, each image goes in the next
+ * drive (A:, B:, ...). --ccp gives where the system on the disk was built to run (0xE400 for 64K).
+ * With --run the program is started directly with the BDOS trapped, each --drive maps the next drive
+ * to a host directory (read only with ,ro) and --tail is its command line. --terminal translates the
+ * console output of software installed for that terminal to ANSI.
+ */
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let mut disks: Vec<String> = Vec::new();
+    let mut ccp_base: Option<u16> = None;
+    let mut terminal: Option<TerminalType> = None;
+    let mut clock_hz = I8080_CLOCK_HZ;
+    let mut program: Option<String> = None;
+    let mut host_drives: Vec<HostDrive> = Vec::new();
+    let mut tail = String::new();
+
+    let mut index = 1;
+    while index < args.len() {
+        let value = args.get(index + 1);
+        match (args[index].as_str(), value) {
+            ("--ccp", Some(value)) => match u16::from_str_radix(value.trim_start_matches("0x"), 16) {
+                Ok(address) => ccp_base = Some(address),
+                Err(_) => return println!("Invalid CCP address: {}", value),
+            },
+            ("--mhz", Some(value)) => match value.parse::<f64>() {
+                Ok(mhz) if mhz > 0.0 => clock_hz = (mhz * 1_000_000.0) as u64,
+                _ => return println!("Invalid clock speed: {}", value),
+            },
+            ("--terminal", Some(value)) => match TerminalType::from_name(value) {
+                Some(kind) => terminal = Some(kind),
+                None => return println!("Unknown terminal type: {}", value),
+            },
+            ("--run", Some(value)) => program = Some(value.clone()),
+            ("--tail", Some(value)) => tail = value.clone(),
+            ("--drive", Some(value)) => match value.strip_suffix(",ro") {
+                Some(directory) => host_drives.push(HostDrive::new(directory, true)),
+                None => host_drives.push(HostDrive::new(value, false)),
+            },
+            (flag, _) if flag.starts_with("--") => return println!("{}", USAGE),
+            (path, _) => {
+                disks.push(path.to_string());
+                index += 1;
+                continue;
+            }
+        }
+        index += 2;
+    }
+
+    let console: Box<dyn SerialBackend> = match terminal {
+        Some(kind) => Box::new(TerminalBackend::new(kind, Box::new(StdioSerial::new()))),
+        None => Box::new(StdioSerial::new()),
+    };
+    let mut cpm = Cpm::new(console);
+    if let Some(address) = ccp_base {
+        cpm.ccp_base = address;
+    }
+    if disks.len() > MAX_DRIVES || host_drives.len() > MAX_DRIVES {
+        return println!("Only {} drives are supported", MAX_DRIVES);
+    }
+    if let Some(program) = program {
+        cpm.host_drives.mount_sequence(host_drives);
+        if cpm.load_com(&program) != LoadRomResult::Ok {
+            return;
+        }
+        cpm.set_command_line(&tail);
+    } else {
+        if disks.is_empty() {
+            return println!("{}", USAGE);
+        }
+        for (drive, path) in disks.iter().enumerate() {
+            match DiskImage::open(path, false, GEOMETRY) {
+                Ok(disk) => cpm.insert(drive, disk),
+                Err(e) => return println!("Failed to open disk image {}: {}", path, e),
+            }
+        }
+        if cpm.boot() != LoadRomResult::Ok {
+            return;
+        }
+    }
+
+    let mut throttle = Throttle::new(clock_hz);
+    throttle.restart(cpm.core.cycles);
+    loop {
+        match cpm.run_cycles(throttle.slice_cycles) {
+            // a program started with --run warm boots when it's done
+            StepInstructionResult::Halt if cpm.bdos_trap => break,
+            StepInstructionResult::Halt => {
+                println!("\nEncountered a HALT STOPPING");
+                break;
+            }
+            StepInstructionResult::Error => {
+                println!("\nStep failed and returned an error so exiting");
+                break;
+            }
+            StepInstructionResult::IllegalOpcode { pc, opcode } => {
+                println!("\nIllegal opcode 0x{:02X} at 0x{:04X} so exiting", opcode, pc);
+                break;
+            }
+            _ => {}
+        }
+        throttle.pace(cpm.core.cycles);
+    }
+}
@@ -0,0 +1,33 @@
+use i8080_emulator::fuzz::fuzz_image;
+use std::time::Instant;
+
+// Instructions run from each random image
+const STEPS_PER_IMAGE: usize = 10_000;
+
+/*
+ * Usage: fuzz [IMAGES] [FIRST_SEED]
+ * Runs IMAGES (default 1000) random memory images from random core states, one seed each starting
+ * at FIRST_SEED. A panic in the core stops the run and the seed printed last reproduces it with
+ * fuzz 1 SEED. Build without --release to also catch arithmetic overflow.
+ */
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let images: u64 = match args.get(1).map(|value| value.parse()) {
+        Some(Ok(images)) => images,
+        Some(Err(_)) => return println!("Usage: fuzz [IMAGES] [FIRST_SEED]"),
+        None => 1000,
+    };
+    let first_seed: u64 = match args.get(2).map(|value| value.parse()) {
+        Some(Ok(seed)) => seed,
+        Some(Err(_)) => return println!("Usage: fuzz [IMAGES] [FIRST_SEED]"),
+        None => 1,
+    };
+
+    let start = Instant::now();
+    for seed in first_seed..first_seed + images {
+        eprint!("\rseed {}", seed);
+        fuzz_image(seed, STEPS_PER_IMAGE);
+    }
+    eprintln!();
+    println!("{} images of {} instructions ran without a panic in {:.1?}", images, STEPS_PER_IMAGE, start.elapsed());
+}
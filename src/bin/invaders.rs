@@ -0,0 +1,94 @@
+use i8080_emulator::invaders::sound::{encode_wav, SampleMixer};
+use i8080_emulator::invaders::video::Overlay;
+use i8080_emulator::invaders::SpaceInvaders;
+use i8080_emulator::{LoadRomResult, I8080_CLOCK_HZ};
+use std::fs;
+
+const USAGE: &str = "[FRAMES] [OUTPUT.png|OUTPUT.ppm] [OUTPUT.wav [SAMPLE_DIRECTORY]]";
+const SAMPLE_RATE: u32 = 44_100;
+
+/*
+ * Usage: invaders <ROM_DIRECTORY> [FRAMES] [OUTPUT.png|OUTPUT.ppm] [OUTPUT.wav [SAMPLE_DIRECTORY]]
+ * Runs the Space Invaders board headless for FRAMES frames (10 seconds worth by default) and prints
+ * where the core ended up, the ROM directory needs invaders.h, invaders.g, invaders.f and invaders.e
+ * If an image file is given the last frame is saved to it with the cabinet colour overlay
+ * If a WAV file is given the sounds of the whole run are mixed into it from the samples (0.wav -
+ * 9.wav) in SAMPLE_DIRECTORY, the ROM directory if there isn't one
+ */
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        println!("Usage: {} <ROM_DIRECTORY> {}", args[0], USAGE);
+        return;
+    }
+    let frames: u64 = match args.get(2) {
+        Some(frames) => match frames.parse() {
+            Ok(frames) => frames,
+            Err(_) => {
+                println!("Invalid frame count: {}", frames);
+                return;
+            }
+        },
+        None => 600,
+    };
+
+    let mut machine = SpaceInvaders::new();
+    if machine.load_rom_set(&args[1]) != LoadRomResult::Ok {
+        return;
+    }
+
+    for _ in 0..frames {
+        if machine.run_frame().is_failure() {
+            println!("Step failed and returned an error so exiting");
+            break;
+        }
+    }
+
+    println!("Ran {} frames ({} cycles)", machine.board.frame_number, machine.board.core.cycles);
+    machine.board.core.print_state();
+
+    let outputs = args.get(3..).unwrap_or(&[]);
+    let wav = outputs.iter().position(|output| output.ends_with(".wav"));
+    if let Some(index) = wav {
+        let samples = outputs.get(index + 1).unwrap_or(&args[1]);
+        save_wav(&mut machine, &outputs[index], samples);
+    }
+
+    if let Some(output) = outputs.first().filter(|_| wav != Some(0)) {
+        let frame = machine.render(Some(&Overlay::cabinet()));
+        let saved = if output.ends_with(".ppm") {
+            frame.save_ppm(output)
+        } else {
+            frame.save_png(output)
+        };
+        match saved {
+            Ok(()) => println!("Saved frame to {}", output),
+            Err(e) => println!("Failed to save frame to {}: {}", output, e),
+        }
+    }
+}
+
+/*
+ * save_wav - Function
+ * Expects: machine to have finished its run
+ * Does: Mixes every sound event of the run from the samples in sample_directory and writes the
+ * result to output, reporting what happened
+ * Returns: N/A
+ */
+fn save_wav(machine: &mut SpaceInvaders, output: &str, sample_directory: &str) {
+    let mut mixer = match SampleMixer::new(SAMPLE_RATE, I8080_CLOCK_HZ) {
+        Ok(mixer) => mixer,
+        Err(e) => return println!("Failed to set up the mixer: {}", e),
+    };
+    match mixer.load_samples(sample_directory) {
+        Ok(loaded) => println!("Loaded {} samples from {}", loaded, sample_directory),
+        Err(e) => return println!("Failed to load samples from {}: {}", sample_directory, e),
+    }
+
+    let events = machine.sound.borrow_mut().take_events();
+    let audio = mixer.mix(&events, machine.board.core.cycles);
+    match fs::write(output, encode_wav(&audio, SAMPLE_RATE)) {
+        Ok(()) => println!("Saved {} sound events to {}", events.len(), output),
+        Err(e) => println!("Failed to save sound to {}: {}", output, e),
+    }
+}
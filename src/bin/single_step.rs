@@ -0,0 +1,67 @@
+use i8080_emulator::single_step::run_file;
+use i8080_emulator::CpuModel;
+use std::fs;
+use std::path::Path;
+
+/*
+ * Usage: single_step [--8085] <FILE|DIRECTORY>...
+ * Runs SingleStepTests style JSON files (every *.json in a directory, in name order) and prints the
+ * pass rate of each file, the first few failures of any that don't pass and the totals
+ */
+fn main() {
+    let mut model = CpuModel::I8080;
+    let mut files = Vec::new();
+    for arg in std::env::args().skip(1) {
+        if arg == "--8085" {
+            model = CpuModel::I8085;
+            continue;
+        }
+        let path = Path::new(&arg);
+        if !path.is_dir() {
+            files.push(arg);
+            continue;
+        }
+        let mut found: Vec<String> = match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect(),
+            Err(error) => return println!("Can't read {}: {}", arg, error),
+        };
+        found.sort();
+        files.extend(found);
+    }
+    if files.is_empty() {
+        return println!("Usage: single_step [--8085] <FILE|DIRECTORY>...");
+    }
+
+    let (mut passed, mut failed, mut clean_files) = (0, 0, 0);
+    for file in files.iter() {
+        let report = match run_file(file, model) {
+            Ok(report) => report,
+            Err(error) => {
+                println!("{}: {}", file, error);
+                continue;
+            }
+        };
+        println!("{:<12} {:>6}/{:<6} {:>6.2}%", report.name, report.passed, report.passed + report.failed, report.pass_rate());
+        for failure in report.failures.iter() {
+            println!("    {}", failure);
+        }
+        passed += report.passed;
+        failed += report.failed;
+        if report.failed == 0 {
+            clean_files += 1;
+        }
+    }
+    let total = passed + failed;
+    println!(
+        "{} of {} cases passed ({:.2}%), {} of {} files clean",
+        passed,
+        total,
+        if total == 0 { 100.0 } else { passed as f64 * 100.0 / total as f64 },
+        clean_files,
+        files.len()
+    );
+}
@@ -0,0 +1,583 @@
+use crate::altair::SerialBackend;
+use crate::{I8080Core, LoadRomResult, StepInstructionResult};
+
+pub mod disk;
+pub mod host;
+
+use disk::{DiskImage, DISK_PARAMETER_BLOCK, SECTORS_PER_TRACK, SECTOR_SIZE, SKEW_TABLE};
+use host::HostDrives;
+
+/*
+ * CP/M 2.2 machine
+ *
+ * The BIOS is implemented here in Rust, everything above it (CCP and BDOS) is the real thing loaded
+ * from the system tracks of drive A so unmodified distribution disks boot. The BIOS jump table sits
+ * at its usual place (CCP + 0x1600) with every entry jumping to itself, step spots the PC landing on
+ * an entry, does the work and returns to the caller as if the routine had run. SELDSK hands the
+ * BDOS disk parameter headers built in the BIOS page after the jump table.
+ *
+ * Memory map for the default 64K system
+ *   0x0000-0x00FF  page zero (warm boot and BDOS jumps, IOBYTE, current drive, default DMA)
+ *   0x0100-0xE3FF  TPA
+ *   0xE400-0xEBFF  CCP
+ *   0xEC00-0xF9FF  BDOS (entered at 0xEC06)
+ *   0xFA00-0xFFFF  BIOS jump table and disk tables
+ *
+ * A .COM file can also be run without a system disk (load_com), then calls to the BDOS are trapped
+ * too, the console functions are handled here and the file functions go to host directories (see
+ * host.rs).
+ */
+
+pub const TPA_START: u16 = 0x0100;
+pub const DEFAULT_CCP_BASE: u16 = 0xE400;
+pub const DEFAULT_DMA: u16 = 0x0080;
+// Longest command tail the CCP leaves in the buffer at 0x0080, leading space included
+pub const COMMAND_TAIL_MAX: usize = 126;
+pub const MAX_DRIVES: usize = 16;
+
+const BDOS_ENTRY_OFFSET: u16 = 0x0806;
+const BIOS_OFFSET: u16 = 0x1600;
+// The CCP and BDOS take up 44 sectors starting at track 0 sector 2
+const SYSTEM_SECTORS: usize = 44;
+
+const PAGE_ZERO_IOBYTE: u16 = 0x0003;
+const PAGE_ZERO_DRIVE: u16 = 0x0004;
+
+// Layout of the BIOS page after the 17 entry jump table, each drive gets a disk parameter header
+// followed by its checksum vector (CKS bytes) and allocation vector (DSM / 8 + 1 bytes)
+const BIOS_ENTRIES: u16 = 17;
+const DPB_OFFSET: u16 = 0x40;
+const XLT_OFFSET: u16 = DPB_OFFSET + DISK_PARAMETER_BLOCK.len() as u16;
+const DIRBUF_OFFSET: u16 = XLT_OFFSET + SKEW_TABLE.len() as u16;
+const DPH_OFFSET: u16 = DIRBUF_OFFSET + SECTOR_SIZE as u16;
+const DPH_SIZE: u16 = 16;
+const CSV_SIZE: u16 = 16;
+const ALV_SIZE: u16 = 242 / 8 + 1;
+
+// Cycles charged for a trapped call, the RET that ends it
+const TRAP_CYCLES: u64 = 10;
+
+/*
+ * BiosCall - The BIOS jump table entries in order
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BiosCall {
+    Boot,
+    WarmBoot,
+    ConsoleStatus,
+    ConsoleIn,
+    ConsoleOut,
+    List,
+    Punch,
+    Reader,
+    Home,
+    SelectDisk,
+    SetTrack,
+    SetSector,
+    SetDma,
+    Read,
+    Write,
+    ListStatus,
+    SectorTranslate,
+}
+
+const BIOS_CALLS: [BiosCall; BIOS_ENTRIES as usize] = [
+    BiosCall::Boot,
+    BiosCall::WarmBoot,
+    BiosCall::ConsoleStatus,
+    BiosCall::ConsoleIn,
+    BiosCall::ConsoleOut,
+    BiosCall::List,
+    BiosCall::Punch,
+    BiosCall::Reader,
+    BiosCall::Home,
+    BiosCall::SelectDisk,
+    BiosCall::SetTrack,
+    BiosCall::SetSector,
+    BiosCall::SetDma,
+    BiosCall::Read,
+    BiosCall::Write,
+    BiosCall::ListStatus,
+    BiosCall::SectorTranslate,
+];
+
+/*
+ * parse_fcb - Function
+ * Expects: argument to be upper case
+ * Does: Parses [D:]NAME[.TYP] the way the CCP fills in an FCB, '*' fills the rest of the name or
+ * type with '?'
+ * Returns: The drive byte (0 for the current drive), name and type and a zeroed extent
+ */
+pub fn parse_fcb(argument: &str) -> [u8; 16] {
+    let mut fcb = [0u8; 16];
+    fcb[1..12].fill(b' ');
+    let mut argument = argument.as_bytes();
+    if argument.len() >= 2 && argument[1] == b':' && argument[0].is_ascii_uppercase() {
+        fcb[0] = argument[0] - b'A' + 1;
+        argument = &argument[2..];
+    }
+    let (name, extension) = match argument.iter().position(|byte| *byte == b'.') {
+        Some(dot) => (&argument[..dot], &argument[dot + 1..]),
+        None => (argument, &argument[argument.len()..]),
+    };
+    for (field, start, length) in [(name, 1, 8), (extension, 9, 3)] {
+        for (index, byte) in field.iter().take(length).enumerate() {
+            if *byte == b'*' {
+                fcb[start + index..start + length].fill(b'?');
+                break;
+            }
+            fcb[start + index] = *byte;
+        }
+    }
+    return fcb;
+}
+
+pub struct Cpm {
+    pub core: I8080Core,
+    pub console: Box<dyn SerialBackend>,
+    pub drives: Vec<Option<DiskImage>>,
+    // Where the CCP is loaded, the BDOS and BIOS follow it
+    pub ccp_base: u16,
+    // Set by load_com, BDOS calls are handled here instead of by a loaded BDOS
+    pub bdos_trap: bool,
+    // Drives the trapped BDOS file functions work on
+    pub host_drives: HostDrives,
+
+    // character taken from the console by CONST and not yet read by CONIN
+    console_pending: Option<u8>,
+    disk: usize,
+    track: usize,
+    sector: usize,
+    dma: u16,
+}
+
+impl Cpm {
+    pub fn new(console: Box<dyn SerialBackend>) -> Self {
+        Self {
+            core: I8080Core::new(),
+            console,
+            drives: (0..MAX_DRIVES).map(|_| None).collect(),
+            ccp_base: DEFAULT_CCP_BASE,
+            bdos_trap: false,
+            host_drives: HostDrives::new(),
+            console_pending: None,
+            disk: 0,
+            track: 0,
+            sector: 1,
+            dma: DEFAULT_DMA,
+        }
+    }
+
+    pub fn bdos_entry(&self) -> u16 {
+        return self.ccp_base.wrapping_add(BDOS_ENTRY_OFFSET);
+    }
+
+    pub fn bios_base(&self) -> u16 {
+        return self.ccp_base.wrapping_add(BIOS_OFFSET);
+    }
+
+    /*
+     * insert - Function
+     * Expects: drive to be under MAX_DRIVES
+     * Does: Puts disk in drive, replacing whatever was there
+     * Returns: N/A
+     */
+    pub fn insert(&mut self, drive: usize, disk: DiskImage) {
+        self.drives[drive] = Some(disk);
+    }
+
+    /*
+     * disk_parameter_header - Helper Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The address of drive's disk parameter header
+     */
+    fn disk_parameter_header(&self, drive: usize) -> u16 {
+        let size = DPH_SIZE + CSV_SIZE + ALV_SIZE;
+        return self.bios_base().wrapping_add(DPH_OFFSET).wrapping_add((drive as u16).wrapping_mul(size));
+    }
+
+    /*
+     * write_word - Helper Function
+     * Expects: N/A
+     * Does: Stores value little endian at address
+     * Returns: N/A
+     */
+    fn write_word(&mut self, address: u16, value: u16) {
+        self.core.write_memory(address, value as u8);
+        self.core.write_memory(address.wrapping_add(1), (value >> 8) as u8);
+    }
+
+    /*
+     * install_bios - Helper Function
+     * Expects: N/A
+     * Does: Writes the jump table, the disk tables and the page zero jumps, addresses wrap at 0xFFFF
+     * so a ccp_base too high for the BIOS area puts the end of it in low memory rather than overflowing
+     * Returns: N/A
+     */
+    fn install_bios(&mut self) {
+        let bios = self.bios_base();
+        for entry in 0..BIOS_ENTRIES {
+            let address = bios.wrapping_add(entry * 3);
+            self.core.write_memory(address, 0xC3);
+            self.write_word(address.wrapping_add(1), address);
+        }
+        for (index, byte) in DISK_PARAMETER_BLOCK.iter().enumerate() {
+            self.core.write_memory(bios.wrapping_add(DPB_OFFSET + index as u16), *byte);
+        }
+        for (index, byte) in SKEW_TABLE.iter().enumerate() {
+            self.core.write_memory(bios.wrapping_add(XLT_OFFSET + index as u16), *byte);
+        }
+        for drive in 0..MAX_DRIVES {
+            // XLT, three BDOS scratch words, DIRBUF, DPB, CSV, ALV
+            let dph = self.disk_parameter_header(drive);
+            let words = [
+                bios.wrapping_add(XLT_OFFSET),
+                0,
+                0,
+                0,
+                bios.wrapping_add(DIRBUF_OFFSET),
+                bios.wrapping_add(DPB_OFFSET),
+                dph.wrapping_add(DPH_SIZE),
+                dph.wrapping_add(DPH_SIZE + CSV_SIZE),
+            ];
+            for (index, word) in words.iter().enumerate() {
+                self.write_word(dph.wrapping_add(index as u16 * 2), *word);
+            }
+        }
+
+        self.core.write_memory(0x0000, 0xC3);
+        self.write_word(0x0001, bios.wrapping_add(3));
+        self.core.write_memory(0x0005, 0xC3);
+        self.write_word(0x0006, self.bdos_entry());
+    }
+
+    /*
+     * load_system - Helper Function
+     * Expects: N/A
+     * Does: Reads the CCP and BDOS from the system tracks of drive A to ccp_base
+     * Returns: false if there's no disk in drive A
+     */
+    fn load_system(&mut self) -> bool {
+        let ccp_base = self.ccp_base;
+        let disk = match &self.drives[0] {
+            Some(disk) => disk,
+            None => return false,
+        };
+        let mut address = ccp_base;
+        for index in 1..=SYSTEM_SECTORS {
+            let sector = disk.read_sector(index / SECTORS_PER_TRACK, index % SECTORS_PER_TRACK + 1).unwrap();
+            for byte in sector {
+                self.core.memory[address as usize] = *byte;
+                address = address.wrapping_add(1);
+            }
+        }
+        return true;
+    }
+
+    /*
+     * enter_ccp - Helper Function
+     * Expects: The CCP and BDOS to be in memory
+     * Does: What the BIOS does at the end of a boot, resets the DMA address and jumps to the CCP with
+     * the current drive and user in C
+     * Returns: N/A
+     */
+    fn enter_ccp(&mut self) {
+        self.dma = DEFAULT_DMA;
+        self.core.stack_pointer = DEFAULT_DMA;
+        self.core.c = self.core.read_memory(PAGE_ZERO_DRIVE);
+        self.core.program_counter = self.ccp_base;
+    }
+
+    /*
+     * boot - Function
+     * Expects: drive A to hold a CP/M system disk built for ccp_base
+     * Does: Cold boots, installs the BIOS, loads the CCP and BDOS and starts the CCP on drive A user 0
+     * Returns: LoadRomResult::NotFound if drive A is empty otherwise LoadRomResult::Ok
+     */
+    pub fn boot(&mut self) -> LoadRomResult {
+        self.bdos_trap = false;
+        self.install_bios();
+        if !self.load_system() {
+            println!("No system disk in drive A");
+            return LoadRomResult::NotFound;
+        }
+        self.core.write_memory(PAGE_ZERO_IOBYTE, 0);
+        self.core.write_memory(PAGE_ZERO_DRIVE, 0);
+        self.enter_ccp();
+        return LoadRomResult::Ok;
+    }
+
+    /*
+     * load_com - Function
+     * Expects: path to point at a CP/M .COM program
+     * Does: Sets up page zero and the BIOS without a system disk, loads the program at 0x0100 and
+     * points the PC at it with 0x0000 on the stack so returning from it warm boots
+     * Returns: A LoadRomResult that indicates what happened
+     */
+    pub fn load_com(&mut self, path: &str) -> LoadRomResult {
+        self.bdos_trap = true;
+        self.install_bios();
+        let bdos_entry = self.bdos_entry();
+        // a RET in case anything looks at the BDOS entry point
+        self.core.write_memory(bdos_entry, 0xC9);
+        self.core.write_memory(DEFAULT_DMA, 0);
+
+        let result = self.core.i8080_load_rom(path, TPA_START);
+        self.core.stack_pointer = self.ccp_base.wrapping_sub(2);
+        self.write_word(self.core.stack_pointer, 0x0000);
+        return result;
+    }
+
+    /*
+     * set_command_line - Function
+     * Expects: load_com to have been called, tail to be what followed the command name
+     * Does: What the CCP does before starting a program, the upper cased tail goes in the buffer at
+     * 0x0080 and the first two arguments are parsed into the FCBs at 0x005C and 0x006C
+     * Returns: N/A
+     */
+    pub fn set_command_line(&mut self, tail: &str) {
+        let tail = tail.trim().to_ascii_uppercase();
+        let mut buffer: Vec<u8> = Vec::new();
+        if !tail.is_empty() {
+            buffer.push(b' ');
+            buffer.extend(tail.bytes());
+            // the NUL after it has to land at 0x00FF at the latest, not on the program at 0x0100
+            buffer.truncate(COMMAND_TAIL_MAX);
+        }
+        self.core.write_memory(DEFAULT_DMA, buffer.len() as u8);
+        for (index, byte) in buffer.iter().enumerate() {
+            self.core.write_memory(DEFAULT_DMA + 1 + index as u16, *byte);
+        }
+        self.core.write_memory(DEFAULT_DMA + 1 + buffer.len() as u16, 0);
+
+        let mut arguments = tail.split_whitespace();
+        for fcb in [0x005C, 0x006C] {
+            let fcb_bytes = parse_fcb(arguments.next().unwrap_or(""));
+            for (index, byte) in fcb_bytes.iter().enumerate() {
+                self.core.write_memory(fcb + index as u16, *byte);
+            }
+        }
+    }
+
+    /*
+     * console_ready - Helper Function
+     * Expects: N/A
+     * Does: Pulls a character from the console if none is pending
+     * Returns: true if a character is waiting to be read
+     */
+    fn console_ready(&mut self) -> bool {
+        if self.console_pending.is_none() {
+            self.console_pending = self.console.receive();
+        }
+        return self.console_pending.is_some();
+    }
+
+    /*
+     * return_from_trap - Helper Function
+     * Expects: The call's return address to be on top of the stack
+     * Does: Pops it into the PC as the RET at the end of the routine would
+     * Returns: StepInstructionResult::Ok
+     */
+    fn return_from_trap(&mut self) -> StepInstructionResult {
+        let sp = self.core.stack_pointer;
+        self.core.program_counter = u16::from_le_bytes([self.core.read_memory(sp), self.core.read_memory(sp.wrapping_add(1))]);
+        self.core.stack_pointer = sp.wrapping_add(2);
+        self.core.cycles += TRAP_CYCLES;
+        return StepInstructionResult::Ok;
+    }
+
+    /*
+     * warm_boot - Helper Function
+     * Expects: N/A
+     * Does: Reloads the CCP and BDOS and restarts the CCP, a program run with load_com has nothing to
+     * go back to so the core halts instead
+     * Returns: StepInstructionResult::Halt if there's no system to go back to otherwise StepInstructionResult::Ok
+     */
+    fn warm_boot(&mut self) -> StepInstructionResult {
+        if self.bdos_trap || !self.load_system() {
+            self.core.halted = true;
+            return StepInstructionResult::Halt;
+        }
+        self.install_bios();
+        self.enter_ccp();
+        self.core.cycles += TRAP_CYCLES;
+        return StepInstructionResult::Ok;
+    }
+
+    /*
+     * bios_call - Helper Function
+     * Expects: The PC to be on the jump table entry for call
+     * Does: Carries out the BIOS routine, registers are passed and returned as in the CP/M 2.2
+     * alteration guide. CONIN with nothing typed leaves the PC on the entry so it's retried next step.
+     * Returns: The StepInstructionResult of the call
+     */
+    fn bios_call(&mut self, call: BiosCall) -> StepInstructionResult {
+        let bc = self.core.bc();
+        match call {
+            BiosCall::Boot => {
+                self.core.write_memory(PAGE_ZERO_IOBYTE, 0);
+                self.core.write_memory(PAGE_ZERO_DRIVE, 0);
+                return self.warm_boot();
+            }
+            BiosCall::WarmBoot => return self.warm_boot(),
+            BiosCall::ConsoleStatus => {
+                self.core.a = if self.console_ready() { 0xFF } else { 0x00 };
+            }
+            BiosCall::ConsoleIn => {
+                if !self.console_ready() {
+                    self.core.cycles += TRAP_CYCLES;
+                    return StepInstructionResult::Ok;
+                }
+                self.core.a = self.console_pending.take().unwrap() & 0x7F;
+            }
+            BiosCall::ConsoleOut => self.console.transmit(self.core.c),
+            // nothing is attached to the list and punch devices
+            BiosCall::List | BiosCall::Punch => {}
+            BiosCall::Reader => self.core.a = 0x1A,
+            BiosCall::ListStatus => self.core.a = 0xFF,
+            BiosCall::Home => self.track = 0,
+            BiosCall::SelectDisk => {
+                let drive = self.core.c as usize;
+                let dph = if drive < MAX_DRIVES && self.drives[drive].is_some() {
+                    self.disk = drive;
+                    self.disk_parameter_header(drive)
+                } else {
+                    0
+                };
+                self.core.set_hl(dph);
+            }
+            BiosCall::SetTrack => self.track = bc as usize,
+            BiosCall::SetSector => self.sector = bc as usize,
+            BiosCall::SetDma => self.dma = bc,
+            BiosCall::Read => {
+                let (track, sector, dma) = (self.track, self.sector, self.dma);
+                let data = self.drives[self.disk].as_ref().and_then(|disk| disk.read_sector(track, sector)).map(|data| data.to_vec());
+                self.core.a = match data {
+                    Some(data) => {
+                        for (index, byte) in data.iter().enumerate() {
+                            self.core.write_memory(dma.wrapping_add(index as u16), *byte);
+                        }
+                        0
+                    }
+                    None => 1,
+                };
+            }
+            BiosCall::Write => {
+                let data: Vec<u8> = (0..SECTOR_SIZE as u16).map(|index| self.core.read_memory(self.dma.wrapping_add(index))).collect();
+                let (track, sector) = (self.track, self.sector);
+                self.core.a = match self.drives[self.disk].as_mut().map(|disk| disk.write_sector(track, sector, &data)) {
+                    Some(Ok(())) => 0,
+                    _ => 1,
+                };
+            }
+            BiosCall::SectorTranslate => {
+                let table = self.core.de();
+                let sector = if table == 0 { bc.wrapping_add(1) } else { self.core.read_memory(table.wrapping_add(bc)) as u16 };
+                self.core.set_hl(sector);
+            }
+        }
+        return self.return_from_trap();
+    }
+
+    /*
+     * bdos_call - Helper Function
+     * Expects: bdos_trap to be set and the PC to be on the BDOS entry point
+     * Does: Handles the BDOS function in C, the console ones here and the disk and file ones against
+     * the host drives, the result goes in A and HL (with B = H) as the BDOS returns it. Console input
+     * with nothing typed retries like CONIN.
+     * Returns: The StepInstructionResult of the call
+     */
+    fn bdos_call(&mut self) -> StepInstructionResult {
+        let de = self.core.de();
+        let result: u16 = match self.core.c {
+            0 => return self.warm_boot(),
+            // console input with echo
+            1 => {
+                if !self.console_ready() {
+                    self.core.cycles += TRAP_CYCLES;
+                    return StepInstructionResult::Ok;
+                }
+                let character = self.console_pending.take().unwrap() & 0x7F;
+                self.console.transmit(character);
+                character as u16
+            }
+            2 => {
+                self.console.transmit(self.core.e);
+                0
+            }
+            // direct console I/O, E = 0xFF reads without waiting
+            6 => {
+                if self.core.e != 0xFF {
+                    self.console.transmit(self.core.e);
+                    0
+                } else if self.console_ready() {
+                    self.console_pending.take().unwrap() as u16
+                } else {
+                    0
+                }
+            }
+            // print the string at DE up to the '$'
+            // a string without its '$' stops at the top of memory rather than wrapping round forever
+            9 => {
+                for address in de..=0xFFFF {
+                    let character = self.core.read_memory(address);
+                    if character == b'$' {
+                        break;
+                    }
+                    self.console.transmit(character);
+                }
+                0
+            }
+            11 => if self.console_ready() { 0xFF } else { 0x00 },
+            // CP/M 2.2
+            12 => 0x0022,
+            _ => self.file_function(self.core.c, de),
+        };
+        self.core.set_hl(result);
+        self.core.a = self.core.l;
+        self.core.b = self.core.h;
+        return self.return_from_trap();
+    }
+
+    /*
+     * step - Function
+     * Expects: boot or load_com to have been called
+     * Does: Executes one instruction, or the BIOS/BDOS routine if the PC is on a trapped entry point
+     * Returns: The StepInstructionResult from the core or the trapped routine
+     */
+    pub fn step(&mut self) -> StepInstructionResult {
+        if self.core.halted {
+            return self.core.i8080_step();
+        }
+        let pc = self.core.program_counter;
+        let offset = pc.wrapping_sub(self.bios_base());
+        if offset < BIOS_ENTRIES * 3 && offset.is_multiple_of(3) {
+            return self.bios_call(BIOS_CALLS[(offset / 3) as usize]);
+        }
+        if self.bdos_trap && pc == self.bdos_entry() {
+            return self.bdos_call();
+        }
+        return self.core.i8080_step();
+    }
+
+    /*
+     * run_cycles - Function
+     * Expects: boot or load_com to have been called
+     * Does: Steps the machine until at least cycles clock cycles have passed, stopping early if an
+     * instruction halts or errors
+     * Returns: The StepInstructionResult of the last instruction executed
+     */
+    pub fn run_cycles(&mut self, cycles: u64) -> StepInstructionResult {
+        let target = self.core.cycles.wrapping_add(cycles);
+        let mut result = StepInstructionResult::Ok;
+
+        while self.core.cycles < target {
+            result = self.step();
+            if result == StepInstructionResult::Halt || result.is_failure() {
+                break;
+            }
+        }
+        return result;
+    }
+}
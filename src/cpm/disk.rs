@@ -0,0 +1,33 @@
+use crate::disk_image::DiskGeometry;
+
+pub use crate::disk_image::DiskImage;
+
+/*
+ * 8" single sided single density disks in the IBM 3740 format
+ *
+ * 77 tracks of 26 sectors of 128 bytes, sectors are numbered from 1 and images store them in
+ * physical order. Tracks 0 and 1 hold the system (cold boot loader in track 0 sector 1 followed by
+ * the CCP and BDOS) and the CP/M file system starts on track 2 with the standard 6 sector skew. This
+ * is the distribution format every CP/M 2.2 machine could read.
+ */
+
+pub const GEOMETRY: DiskGeometry = DiskGeometry {
+    sector_size: 128,
+    sectors_per_track: 26,
+    tracks: 77,
+    first_sector: 1,
+};
+pub const SECTOR_SIZE: usize = GEOMETRY.sector_size;
+pub const SECTORS_PER_TRACK: usize = GEOMETRY.sectors_per_track;
+pub const TRACKS: usize = GEOMETRY.tracks;
+pub const DISK_SIZE: usize = GEOMETRY.disk_size();
+pub const RESERVED_TRACKS: usize = 2;
+
+// Logical to physical sector translation for the standard 6 sector skew
+pub const SKEW_TABLE: [u8; SECTORS_PER_TRACK] = [
+    1, 7, 13, 19, 25, 5, 11, 17, 23, 3, 9, 15, 21, 2, 8, 14, 20, 26, 6, 12, 18, 24, 4, 10, 16, 22,
+];
+
+// Disk parameter block: SPT 26, BSH 3 / BLM 7 (1K blocks), EXM 0, DSM 242, DRM 63, AL0/AL1 with two
+// directory blocks, CKS 16 and OFF 2
+pub const DISK_PARAMETER_BLOCK: [u8; 15] = [26, 0, 3, 7, 0, 242, 0, 63, 0, 0xC0, 0x00, 16, 0, 2, 0];
@@ -0,0 +1,666 @@
+use super::{Cpm, DEFAULT_DMA, MAX_DRIVES};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/*
+ * CP/M drives backed by host directories
+ *
+ * When BDOS calls are trapped (see Cpm::load_com) the file functions work on ordinary host files
+ * instead of a disk image, which is what batch builds want: put the sources in a directory, run the
+ * assembler on it and pick the output up afterwards. User 0 is the directory itself and users 1-15
+ * are subdirectories named 1 to 15. Host file names are matched without regard to case and only
+ * names that fit 8.3 in the CP/M character set are visible, files the BDOS creates get lower case
+ * host names. A drive, or a single file without write permission, is read only.
+ *
+ * There are no allocation blocks behind the files so directory entries from a search carry the
+ * name, the extent and the record count but an empty allocation map.
+ */
+
+pub const RECORD_SIZE: usize = 128;
+const RECORDS_PER_EXTENT: usize = 128;
+const EXTENTS_PER_MODULE: usize = 32;
+const MAX_USER: u8 = 15;
+// Padding for the last record of a file that doesn't fill it
+const CPM_EOF: u8 = 0x1A;
+
+// FCB layout
+const FCB_NAME: u16 = 1;
+const FCB_EXTENT: u16 = 12;
+const FCB_MODULE: u16 = 14;
+const FCB_RECORD_COUNT: u16 = 15;
+const FCB_NEW_NAME: u16 = 17;
+const FCB_CURRENT_RECORD: u16 = 32;
+const FCB_RANDOM_RECORD: u16 = 33;
+
+/*
+ * CpmName - The 11 character name and type from an FCB or directory entry, space padded, upper case
+ * and with the attribute bits stripped
+ */
+pub type CpmName = [u8; 11];
+
+/*
+ * to_cpm_name - Function
+ * Expects: N/A
+ * Does: Translates a host file name to 8.3, splitting the type off at the last dot
+ * Returns: The CpmName or None if the name can't be represented
+ */
+pub fn to_cpm_name(host: &str) -> Option<CpmName> {
+    let (name, extension) = match host.rfind('.') {
+        Some(dot) => (&host[..dot], &host[dot + 1..]),
+        None => (host, ""),
+    };
+    if name.is_empty() || name.len() > 8 || extension.len() > 3 {
+        return None;
+    }
+    let valid = |byte: &u8| byte.is_ascii_graphic() && !b"<>.,;:=?*[]_%|()/\\".contains(byte);
+    if !name.bytes().all(|byte| valid(&byte)) || !extension.bytes().all(|byte| valid(&byte)) {
+        return None;
+    }
+
+    let mut cpm_name = [b' '; 11];
+    for (index, byte) in name.bytes().enumerate() {
+        cpm_name[index] = byte.to_ascii_uppercase();
+    }
+    for (index, byte) in extension.bytes().enumerate() {
+        cpm_name[8 + index] = byte.to_ascii_uppercase();
+    }
+    return Some(cpm_name);
+}
+
+/*
+ * to_host_name - Function
+ * Expects: name not to contain wildcards
+ * Does: Translates an 8.3 name to the lower case host name new files are given
+ * Returns: The host file name, without a dot if the type is blank
+ */
+pub fn to_host_name(name: &CpmName) -> String {
+    let base = String::from_utf8_lossy(&name[..8]).trim_end().to_ascii_lowercase();
+    let extension = String::from_utf8_lossy(&name[8..]).trim_end().to_ascii_lowercase();
+    if extension.is_empty() {
+        return base;
+    }
+    return format!("{}.{}", base, extension);
+}
+
+/*
+ * name_matches - Function
+ * Expects: N/A
+ * Does: Compares name against pattern where '?' in the pattern matches any character
+ * Returns: true if they match
+ */
+pub fn name_matches(pattern: &CpmName, name: &CpmName) -> bool {
+    return pattern.iter().zip(name.iter()).all(|(want, have)| *want == b'?' || want == have);
+}
+
+/*
+ * HostDrive - A host directory standing in for one CP/M drive
+ */
+pub struct HostDrive {
+    pub root: PathBuf,
+    pub read_only: bool,
+}
+
+impl HostDrive {
+    pub fn new<P: Into<PathBuf>>(root: P, read_only: bool) -> Self {
+        Self {
+            root: root.into(),
+            read_only,
+        }
+    }
+
+    /*
+     * user_directory - Function
+     * Expects: user to be 0 to 15
+     * Does: N/A
+     * Returns: The host directory holding user's files
+     */
+    pub fn user_directory(&self, user: u8) -> PathBuf {
+        if user == 0 {
+            return self.root.clone();
+        }
+        return self.root.join(user.to_string());
+    }
+
+    /*
+     * files - Function
+     * Expects: N/A
+     * Does: Lists the regular files in user's directory that have a CP/M name
+     * Returns: The names and host paths sorted by name, empty if the directory doesn't exist
+     */
+    pub fn files(&self, user: u8) -> Vec<(CpmName, PathBuf)> {
+        let mut files: Vec<(CpmName, PathBuf)> = match fs::read_dir(self.user_directory(user)) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().map(|kind| kind.is_file()).unwrap_or(false))
+                .filter_map(|entry| Some((to_cpm_name(entry.file_name().to_str()?)?, entry.path())))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        files.sort();
+        return files;
+    }
+
+    /*
+     * find - Function
+     * Expects: N/A
+     * Does: Looks for the first file of user matching pattern
+     * Returns: Its name and host path or None if nothing matches
+     */
+    pub fn find(&self, user: u8, pattern: &CpmName) -> Option<(CpmName, PathBuf)> {
+        return self.files(user).into_iter().find(|(name, _)| name_matches(pattern, name));
+    }
+
+    /*
+     * file_read_only - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: true if path can't be written, either because of the drive or the file's permissions
+     */
+    pub fn file_read_only(&self, path: &Path) -> bool {
+        if self.read_only {
+            return true;
+        }
+        return fs::metadata(path).map(|metadata| metadata.permissions().readonly()).unwrap_or(false);
+    }
+}
+
+/*
+ * HostDrives - The BDOS state for the host drives: the drive sequence and the current drive, user,
+ * drives set read only by the program and the rest of an in progress directory search
+ */
+pub struct HostDrives {
+    pub drives: Vec<Option<HostDrive>>,
+    pub current_drive: usize,
+    pub user: u8,
+    read_only_vector: u16,
+    search_results: VecDeque<[u8; 32]>,
+}
+
+impl Default for HostDrives {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HostDrives {
+    pub fn new() -> Self {
+        Self {
+            drives: (0..MAX_DRIVES).map(|_| None).collect(),
+            current_drive: 0,
+            user: 0,
+            read_only_vector: 0,
+            search_results: VecDeque::new(),
+        }
+    }
+
+    /*
+     * mount - Function
+     * Expects: drive to be under MAX_DRIVES (0 is A:)
+     * Does: Maps drive to the host directory, replacing whatever was there
+     * Returns: N/A
+     */
+    pub fn mount(&mut self, drive: usize, host_drive: HostDrive) {
+        self.drives[drive] = Some(host_drive);
+    }
+
+    /*
+     * mount_sequence - Function
+     * Expects: At most MAX_DRIVES drives
+     * Does: Maps the drives to A:, B:, ... in order
+     * Returns: N/A
+     */
+    pub fn mount_sequence(&mut self, drives: Vec<HostDrive>) {
+        for (drive, host_drive) in drives.into_iter().enumerate() {
+            self.mount(drive, host_drive);
+        }
+    }
+
+    fn drive(&self, drive: usize) -> Option<&HostDrive> {
+        return self.drives.get(drive).and_then(|drive| drive.as_ref());
+    }
+
+    fn drive_read_only(&self, drive: usize) -> bool {
+        return self.read_only_vector & (1 << drive) != 0 || self.drive(drive).map(|drive| drive.read_only).unwrap_or(true);
+    }
+}
+
+/*
+ * records_in - Helper Function
+ * Expects: N/A
+ * Does: N/A
+ * Returns: How many 128 byte records a file of length bytes takes
+ */
+fn records_in(length: u64) -> usize {
+    return length.div_ceil(RECORD_SIZE as u64) as usize;
+}
+
+/*
+ * directory_entry - Helper Function
+ * Expects: N/A
+ * Does: Builds the 32 byte directory entry search returns for one extent of a file
+ * Returns: The entry
+ */
+fn directory_entry(user: u8, name: &CpmName, read_only: bool, extent: usize, records: usize) -> [u8; 32] {
+    let mut entry = [0u8; 32];
+    entry[0] = user;
+    entry[1..12].copy_from_slice(name);
+    if read_only {
+        entry[9] |= 0x80;
+    }
+    entry[12] = (extent % EXTENTS_PER_MODULE) as u8;
+    entry[14] = (extent / EXTENTS_PER_MODULE) as u8;
+    entry[15] = records.saturating_sub(extent * RECORDS_PER_EXTENT).min(RECORDS_PER_EXTENT) as u8;
+    return entry;
+}
+
+impl Cpm {
+    /*
+     * fcb_name - Helper Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The name in the FCB at fcb + offset with the attribute bits stripped
+     */
+    fn fcb_name(&self, fcb: u16, offset: u16) -> CpmName {
+        let mut name = [0u8; 11];
+        for (index, byte) in name.iter_mut().enumerate() {
+            *byte = self.core.read_memory(fcb.wrapping_add(offset + index as u16)) & 0x7F;
+        }
+        return name;
+    }
+
+    /*
+     * fcb_drive - Helper Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The drive the FCB refers to, 0 (and '?' for a search) means the current drive
+     */
+    fn fcb_drive(&self, fcb: u16) -> usize {
+        return match self.core.read_memory(fcb) {
+            0 | b'?' => self.host_drives.current_drive,
+            drive => (drive as usize - 1) % MAX_DRIVES,
+        };
+    }
+
+    fn fcb_byte(&self, fcb: u16, offset: u16) -> usize {
+        return self.core.read_memory(fcb.wrapping_add(offset)) as usize;
+    }
+
+    /*
+     * fcb_record - Helper Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The sequential record number from the FCB's module, extent and current record
+     */
+    fn fcb_record(&self, fcb: u16) -> usize {
+        let extent = (self.fcb_byte(fcb, FCB_MODULE) & 0x3F) * EXTENTS_PER_MODULE + (self.fcb_byte(fcb, FCB_EXTENT) & 0x1F);
+        return extent * RECORDS_PER_EXTENT + (self.fcb_byte(fcb, FCB_CURRENT_RECORD) & 0x7F);
+    }
+
+    /*
+     * set_fcb_record - Helper Function
+     * Expects: N/A
+     * Does: Points the FCB's module, extent and current record at record and sets the record count
+     * for that extent from the file's size in records
+     * Returns: N/A
+     */
+    fn set_fcb_record(&mut self, fcb: u16, record: usize, file_records: usize) {
+        let extent = record / RECORDS_PER_EXTENT;
+        self.core.write_memory(fcb.wrapping_add(FCB_EXTENT), (extent % EXTENTS_PER_MODULE) as u8);
+        self.core.write_memory(fcb.wrapping_add(FCB_MODULE), (extent / EXTENTS_PER_MODULE) as u8);
+        self.core.write_memory(fcb.wrapping_add(FCB_CURRENT_RECORD), (record % RECORDS_PER_EXTENT) as u8);
+        let count = file_records.saturating_sub(extent * RECORDS_PER_EXTENT).min(RECORDS_PER_EXTENT);
+        self.core.write_memory(fcb.wrapping_add(FCB_RECORD_COUNT), count as u8);
+    }
+
+    /*
+     * random_record - Helper Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The record number in r0/r1 or None if r2 is set (past the end of the largest file)
+     */
+    fn random_record(&self, fcb: u16) -> Option<usize> {
+        if self.fcb_byte(fcb, FCB_RANDOM_RECORD + 2) != 0 {
+            return None;
+        }
+        return Some(self.fcb_byte(fcb, FCB_RANDOM_RECORD) | (self.fcb_byte(fcb, FCB_RANDOM_RECORD + 1) << 8));
+    }
+
+    fn set_random_record(&mut self, fcb: u16, record: usize) {
+        for index in 0..3 {
+            self.core.write_memory(fcb.wrapping_add(FCB_RANDOM_RECORD + index), (record >> (8 * index)) as u8);
+        }
+    }
+
+    /*
+     * find_file - Helper Function
+     * Expects: N/A
+     * Does: Looks up the FCB's file on its drive in the current user area
+     * Returns: The drive, the file's name and its host path or None if there's no such drive or file
+     */
+    fn find_file(&self, fcb: u16) -> Option<(usize, CpmName, PathBuf)> {
+        let drive = self.fcb_drive(fcb);
+        let (name, path) = self.host_drives.drive(drive)?.find(self.host_drives.user, &self.fcb_name(fcb, FCB_NAME))?;
+        return Some((drive, name, path));
+    }
+
+    /*
+     * writable_file - Helper Function
+     * Expects: N/A
+     * Does: Looks up the FCB's file and checks neither it nor the drive is read only
+     * Returns: The host path or None if the file doesn't exist or can't be written
+     */
+    fn writable_file(&self, fcb: u16) -> Option<PathBuf> {
+        let (drive, _, path) = self.find_file(fcb)?;
+        if self.host_drives.drive_read_only(drive) || self.host_drives.drive(drive)?.file_read_only(&path) {
+            return None;
+        }
+        return Some(path);
+    }
+
+    /*
+     * read_record - Helper Function
+     * Expects: N/A
+     * Does: Copies record of the FCB's file to the DMA address, a short last record is padded with ^Z,
+     * and leaves the FCB on the record (advancing past it when sequential)
+     * Returns: 0 on success, 1 reading past the end of the file, 0xFF if the file doesn't exist
+     */
+    fn read_record(&mut self, fcb: u16, record: usize, sequential: bool) -> u16 {
+        let path = match self.find_file(fcb) {
+            Some((_, _, path)) => path,
+            None => return 0xFF,
+        };
+        let mut file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(_) => return 0xFF,
+        };
+        let length = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        let file_records = records_in(length);
+        if record >= file_records {
+            self.set_fcb_record(fcb, record, file_records);
+            return 1;
+        }
+
+        let mut data = [CPM_EOF; RECORD_SIZE];
+        let offset = (record * RECORD_SIZE) as u64;
+        let available = (length - offset).min(RECORD_SIZE as u64) as usize;
+        if file.seek(SeekFrom::Start(offset)).is_err() || file.read_exact(&mut data[..available]).is_err() {
+            return 0xFF;
+        }
+        for (index, byte) in data.iter().enumerate() {
+            self.core.write_memory(self.dma.wrapping_add(index as u16), *byte);
+        }
+        self.set_fcb_record(fcb, if sequential { record + 1 } else { record }, file_records);
+        return 0;
+    }
+
+    /*
+     * write_record - Helper Function
+     * Expects: N/A
+     * Does: Writes the 128 bytes at the DMA address to record of the FCB's file, writing past the end
+     * leaves a zero filled gap, and leaves the FCB on the record (advancing past it when sequential)
+     * Returns: 0 on success, 0xFF if the file doesn't exist or is read only
+     */
+    fn write_record(&mut self, fcb: u16, record: usize, sequential: bool) -> u16 {
+        let path = match self.writable_file(fcb) {
+            Some(path) => path,
+            None => return 0xFF,
+        };
+        let data: Vec<u8> = (0..RECORD_SIZE as u16).map(|index| self.core.read_memory(self.dma.wrapping_add(index))).collect();
+        let written = OpenOptions::new().write(true).open(&path).and_then(|mut file| {
+            file.seek(SeekFrom::Start((record * RECORD_SIZE) as u64))?;
+            file.write_all(&data)?;
+            file.metadata()
+        });
+        match written {
+            Ok(metadata) => {
+                self.set_fcb_record(fcb, if sequential { record + 1 } else { record }, records_in(metadata.len()));
+                return 0;
+            }
+            Err(_) => return 0xFF,
+        }
+    }
+
+    /*
+     * search - Helper Function
+     * Expects: N/A
+     * Does: Collects the directory entries matching the FCB, '?' in the drive byte or the extent
+     * returns every extent otherwise only the first
+     * Returns: N/A
+     */
+    fn search(&mut self, fcb: u16) {
+        self.host_drives.search_results.clear();
+        let drive = self.fcb_drive(fcb);
+        let user = self.host_drives.user;
+        let pattern = self.fcb_name(fcb, FCB_NAME);
+        let all_extents = self.core.read_memory(fcb) == b'?' || self.core.read_memory(fcb.wrapping_add(FCB_EXTENT)) == b'?';
+        let host_drive = match self.host_drives.drive(drive) {
+            Some(host_drive) => host_drive,
+            None => return,
+        };
+
+        let mut results = VecDeque::new();
+        for (name, path) in host_drive.files(user) {
+            if !name_matches(&pattern, &name) {
+                continue;
+            }
+            let records = records_in(fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0));
+            let read_only = host_drive.file_read_only(&path);
+            let extents = if all_extents { records.div_ceil(RECORDS_PER_EXTENT).max(1) } else { 1 };
+            for extent in 0..extents {
+                results.push_back(directory_entry(user, &name, read_only, extent, records));
+            }
+        }
+        self.host_drives.search_results = results;
+    }
+
+    /*
+     * next_search_result - Helper Function
+     * Expects: search to have been called
+     * Does: Puts the next directory entry at the start of the DMA buffer
+     * Returns: 0 (its index in the buffer) or 0xFF when there are no more
+     */
+    fn next_search_result(&mut self) -> u16 {
+        match self.host_drives.search_results.pop_front() {
+            Some(entry) => {
+                for (index, byte) in entry.iter().enumerate() {
+                    self.core.write_memory(self.dma.wrapping_add(index as u16), *byte);
+                }
+                return 0;
+            }
+            None => return 0xFF,
+        }
+    }
+
+    /*
+     * make_file - Helper Function
+     * Expects: The FCB's name not to contain wildcards
+     * Does: Creates (or empties) the file in the current user area, making the user's directory if
+     * needed, and points the FCB at its first record
+     * Returns: 0 on success, 0xFF if the drive is read only or the file couldn't be created
+     */
+    fn make_file(&mut self, fcb: u16) -> u16 {
+        let drive = self.fcb_drive(fcb);
+        let name = self.fcb_name(fcb, FCB_NAME);
+        if self.host_drives.drive_read_only(drive) || name.contains(&b'?') {
+            return 0xFF;
+        }
+        let host_drive = self.host_drives.drive(drive).unwrap();
+        let directory = host_drive.user_directory(self.host_drives.user);
+        let path = match host_drive.find(self.host_drives.user, &name) {
+            Some((_, path)) => path,
+            None => directory.join(to_host_name(&name)),
+        };
+        if fs::create_dir_all(&directory).and_then(|_| fs::File::create(&path)).is_err() {
+            return 0xFF;
+        }
+        self.set_fcb_record(fcb, 0, 0);
+        return 0;
+    }
+
+    /*
+     * delete_files - Helper Function
+     * Expects: N/A
+     * Does: Deletes every file matching the FCB unless the drive or one of the files is read only
+     * Returns: 0 if anything was deleted otherwise 0xFF
+     */
+    fn delete_files(&mut self, fcb: u16) -> u16 {
+        let drive = self.fcb_drive(fcb);
+        let pattern = self.fcb_name(fcb, FCB_NAME);
+        let host_drive = match self.host_drives.drive(drive) {
+            Some(host_drive) if !self.host_drives.drive_read_only(drive) => host_drive,
+            _ => return 0xFF,
+        };
+        let matching: Vec<PathBuf> = host_drive
+            .files(self.host_drives.user)
+            .into_iter()
+            .filter(|(name, _)| name_matches(&pattern, name))
+            .map(|(_, path)| path)
+            .collect();
+        if matching.is_empty() || matching.iter().any(|path| host_drive.file_read_only(path)) {
+            return 0xFF;
+        }
+        for path in matching {
+            let _ = fs::remove_file(path);
+        }
+        return 0;
+    }
+
+    /*
+     * rename_file - Helper Function
+     * Expects: The new name to be in the FCB at offset 17
+     * Does: Renames the file unless the new name is taken
+     * Returns: 0 on success otherwise 0xFF
+     */
+    fn rename_file(&mut self, fcb: u16) -> u16 {
+        let path = match self.writable_file(fcb) {
+            Some(path) => path,
+            None => return 0xFF,
+        };
+        let new_name = self.fcb_name(fcb, FCB_NEW_NAME);
+        let host_drive = self.host_drives.drive(self.fcb_drive(fcb)).unwrap();
+        if new_name.contains(&b'?') || host_drive.find(self.host_drives.user, &new_name).is_some() {
+            return 0xFF;
+        }
+        let new_path = path.with_file_name(to_host_name(&new_name));
+        return if fs::rename(&path, new_path).is_ok() { 0 } else { 0xFF };
+    }
+
+    /*
+     * file_function - Helper Function
+     * Expects: bdos_trap to be set
+     * Does: Carries out the BDOS disk and file functions against the host drives, de is the FCB
+     * address (or the parameter) as passed in DE
+     * Returns: The value the BDOS returns in HL (A gets L)
+     */
+    pub(super) fn file_function(&mut self, function: u8, de: u16) -> u16 {
+        let fcb = de;
+        match function {
+            // reset disk system
+            13 => {
+                self.dma = DEFAULT_DMA;
+                self.host_drives.current_drive = 0;
+                self.host_drives.read_only_vector = 0;
+                return 0;
+            }
+            // select disk
+            14 => {
+                let drive = (self.core.e & 0x0F) as usize;
+                if self.host_drives.drive(drive).is_none() {
+                    return 0xFF;
+                }
+                self.host_drives.current_drive = drive;
+                return 0;
+            }
+            // open file
+            15 => match self.find_file(fcb) {
+                Some((_, name, path)) => {
+                    for (index, byte) in name.iter().enumerate() {
+                        self.core.write_memory(fcb.wrapping_add(FCB_NAME + index as u16), *byte);
+                    }
+                    let records = records_in(fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0));
+                    let record = self.fcb_record(fcb);
+                    self.set_fcb_record(fcb, record, records);
+                    return 0;
+                }
+                None => return 0xFF,
+            },
+            // close file, writes have already gone to the host file
+            16 => return if self.find_file(fcb).is_some() { 0 } else { 0xFF },
+            // search for first
+            17 => {
+                self.search(fcb);
+                return self.next_search_result();
+            }
+            // search for next
+            18 => return self.next_search_result(),
+            19 => return self.delete_files(fcb),
+            // read and write sequential
+            20 => {
+                let record = self.fcb_record(fcb);
+                return self.read_record(fcb, record, true);
+            }
+            21 => {
+                let record = self.fcb_record(fcb);
+                return self.write_record(fcb, record, true);
+            }
+            22 => return self.make_file(fcb),
+            23 => return self.rename_file(fcb),
+            // login vector
+            24 => {
+                return (0..MAX_DRIVES).filter(|drive| self.host_drives.drive(*drive).is_some()).map(|drive| 1 << drive).sum();
+            }
+            25 => return self.host_drives.current_drive as u16,
+            26 => {
+                self.dma = de;
+                return 0;
+            }
+            // write protect disk
+            28 => {
+                self.host_drives.read_only_vector |= 1 << self.host_drives.current_drive;
+                return 0;
+            }
+            // read only vector
+            29 => {
+                return (0..MAX_DRIVES)
+                    .filter(|drive| self.host_drives.drive(*drive).is_some() && self.host_drives.drive_read_only(*drive))
+                    .map(|drive| 1 << drive)
+                    .sum();
+            }
+            // get/set user code
+            32 => {
+                if self.core.e == 0xFF {
+                    return self.host_drives.user as u16;
+                }
+                self.host_drives.user = self.core.e.min(MAX_USER);
+                return 0;
+            }
+            // read and write random (40 is write random with zero fill which the host does anyway)
+            33 => match self.random_record(fcb) {
+                Some(record) => return self.read_record(fcb, record, false),
+                None => return 6,
+            },
+            34 | 40 => match self.random_record(fcb) {
+                Some(record) => return self.write_record(fcb, record, false),
+                None => return 6,
+            },
+            // compute file size
+            35 => match self.find_file(fcb) {
+                Some((_, _, path)) => {
+                    let records = records_in(fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0));
+                    self.set_random_record(fcb, records);
+                    return 0;
+                }
+                None => return 0xFF,
+            },
+            // set random record
+            36 => {
+                let record = self.fcb_record(fcb);
+                self.set_random_record(fcb, record);
+                return 0;
+            }
+            _ => return 0x00FF,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/*
+ * Raw floppy disk images
+ *
+ * Both the Altair 88-DCDD and the CP/M BIOS keep a whole disk in memory and write each sector back
+ * to the image file as it's written. An image is just the sectors in order, track by track, so all
+ * that differs between the two is the geometry: how big a sector is, how many there are on a track,
+ * how many tracks and whether sectors are numbered from 0 or 1.
+ */
+
+/*
+ * DiskGeometry - The shape of a disk and how its sectors are numbered
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiskGeometry {
+    pub sector_size: usize,
+    pub sectors_per_track: usize,
+    pub tracks: usize,
+    pub first_sector: usize,
+}
+
+impl DiskGeometry {
+    /*
+     * disk_size - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The size of a full image in bytes
+     */
+    pub const fn disk_size(&self) -> usize {
+        return self.sector_size * self.sectors_per_track * self.tracks;
+    }
+
+    /*
+     * sector_offset - Function
+     * Expects: sector to be numbered from first_sector
+     * Does: N/A
+     * Returns: Where sector of track starts in the image, None if it's off the disk
+     */
+    pub fn sector_offset(&self, track: usize, sector: usize) -> Option<usize> {
+        if track >= self.tracks || sector < self.first_sector || sector - self.first_sector >= self.sectors_per_track {
+            return None;
+        }
+        return Some((track * self.sectors_per_track + sector - self.first_sector) * self.sector_size);
+    }
+}
+
+/*
+ * DiskImage - The sectors of one disk, kept in memory and written back to the file sector by sector
+ */
+pub struct DiskImage {
+    pub geometry: DiskGeometry,
+    pub data: Vec<u8>,
+    pub read_only: bool,
+    file: Option<File>,
+}
+
+impl DiskImage {
+    /*
+     * open - Function
+     * Expects: path to be a raw image laid out as geometry, shorter images are padded out with 0xE5
+     * Does: Reads the image, it's opened for writing back unless read_only is set or the file
+     * itself is read only
+     * Returns: The DiskImage or the error from reading the file
+     */
+    pub fn open(path: &str, read_only: bool, geometry: DiskGeometry) -> io::Result<Self> {
+        let writable = if read_only { None } else { OpenOptions::new().read(true).write(true).open(path).ok() };
+        let mut data = Vec::new();
+        match &writable {
+            Some(file) => {
+                let mut file = file;
+                file.read_to_end(&mut data)?;
+            }
+            None => {
+                File::open(path)?.read_to_end(&mut data)?;
+            }
+        }
+        if data.len() < geometry.disk_size() {
+            data.resize(geometry.disk_size(), 0xE5);
+        }
+        Ok(Self {
+            geometry,
+            data,
+            read_only: writable.is_none(),
+            file: writable,
+        })
+    }
+
+    /*
+     * from_bytes - Function
+     * Expects: N/A
+     * Does: Makes a disk that only lives in memory, padded out to a full disk with 0xE5
+     * Returns: The DiskImage
+     */
+    pub fn from_bytes(data: &[u8], geometry: DiskGeometry) -> Self {
+        let mut data = data.to_vec();
+        if data.len() < geometry.disk_size() {
+            data.resize(geometry.disk_size(), 0xE5);
+        }
+        Self {
+            geometry,
+            data,
+            read_only: false,
+            file: None,
+        }
+    }
+
+    /*
+     * read_sector - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The sector or None if it's off the disk
+     */
+    pub fn read_sector(&self, track: usize, sector: usize) -> Option<&[u8]> {
+        let offset = self.geometry.sector_offset(track, sector)?;
+        return Some(&self.data[offset..offset + self.geometry.sector_size]);
+    }
+
+    /*
+     * write_sector - Function
+     * Expects: data to be at least a sector long
+     * Does: Stores the sector and writes it through to the image file if there is one
+     * Returns: An error if the disk is read only, the sector is off the disk or writing the file failed
+     */
+    pub fn write_sector(&mut self, track: usize, sector: usize, data: &[u8]) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "disk is read only"));
+        }
+        let offset = match self.geometry.sector_offset(track, sector) {
+            Some(offset) => offset,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "sector is off the disk")),
+        };
+        let sector_size = self.geometry.sector_size;
+        self.data[offset..offset + sector_size].copy_from_slice(&data[..sector_size]);
+        if let Some(file) = &mut self.file {
+            file.seek(SeekFrom::Start(offset as u64))?;
+            file.write_all(&data[..sector_size])?;
+        }
+        return Ok(());
+    }
+}
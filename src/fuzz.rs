@@ -0,0 +1,156 @@
+use crate::io::IoDevice;
+use crate::{CpuModel, I8080Core, UndocumentedOpcodes};
+
+/*
+ * XorShift - Small deterministic random number generator (xorshift64*)
+ * Good enough to scramble memory and registers reproducibly from a seed, not for anything else
+ */
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    /*
+     * new - Function
+     * Expects: N/A
+     * Does: Creates the generator, a zero seed is swapped for a fixed non zero one as xorshift
+     * would otherwise only ever return 0
+     * Returns: The XorShift
+     */
+    pub fn new(seed: u64) -> Self {
+        let state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+        Self { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        return self.state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+    pub fn next_u8(&mut self) -> u8 {
+        return (self.next_u64() >> 56) as u8;
+    }
+
+    pub fn next_u16(&mut self) -> u16 {
+        return (self.next_u64() >> 48) as u16;
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        return self.next_u64() >> 63 == 1;
+    }
+
+    pub fn fill(&mut self, bytes: &mut [u8]) {
+        for byte in bytes.iter_mut() {
+            *byte = self.next_u8();
+        }
+    }
+}
+
+// Answers IN with the last byte written to OUT mixed with the port
+struct FuzzDevice {
+    latch: u8,
+}
+
+impl IoDevice for FuzzDevice {
+    fn input(&mut self, port: u8) -> u8 {
+        return self.latch ^ port;
+    }
+
+    fn output(&mut self, _port: u8, value: u8) {
+        self.latch = value;
+    }
+}
+
+/*
+ * random_core - Function
+ * Expects: N/A
+ * Does: Builds a core with random memory, registers, flags, memory map, model and undocumented
+ * opcode policy, PC and SP anywhere in the 64K including right at the top where operands wrap
+ * Returns: The I8080Core
+ */
+pub fn random_core(random: &mut XorShift) -> I8080Core {
+    let mut core = I8080Core::new();
+    random.fill(&mut core.memory);
+    core.a = random.next_u8();
+    core.b = random.next_u8();
+    core.c = random.next_u8();
+    core.d = random.next_u8();
+    core.e = random.next_u8();
+    core.h = random.next_u8();
+    core.l = random.next_u8();
+    core.sign = random.next_bool();
+    core.zero = random.next_bool();
+    core.auxiliary_carry = random.next_bool();
+    core.parity = random.next_bool();
+    core.carry = random.next_bool();
+    core.interrupts_enabled = random.next_bool();
+
+    // every other image starts near the top of memory so the wrap around gets exercised
+    if random.next_bool() {
+        core.program_counter = 0xFFF0 | random.next_u16() & 0x000F;
+        core.stack_pointer = random.next_u16() & 0x0003;
+    } else {
+        core.program_counter = random.next_u16();
+        core.stack_pointer = random.next_u16();
+    }
+    if random.next_u8() < 32 {
+        core.address_mask = 0x3FFF;
+        core.rom_end = random.next_u16() & 0x3FFF;
+    }
+
+    core.model = match random.next_u8() % 3 {
+        0 => CpuModel::I8080,
+        1 => CpuModel::I8085,
+        _ => CpuModel::Z80,
+    };
+    if random.next_bool() {
+        core.undocumented_opcodes = UndocumentedOpcodes::Trap;
+    }
+    core.z80.ix = random.next_u16();
+    core.z80.iy = random.next_u16();
+    core.z80.i = random.next_u8();
+    core.z80.interrupt_mode = random.next_u8() % 3;
+    core.i8085.masks = random.next_u8() & 0x07;
+    if random.next_bool() {
+        core.io = Some(Box::new(FuzzDevice { latch: random.next_u8() }));
+    }
+    return core;
+}
+
+/*
+ * fuzz_image - Function
+ * Expects: N/A
+ * Does: Runs steps instructions of a random_core built from seed, throwing in random interrupts
+ * along the way. Anything that goes wrong in the core shows up as a panic
+ * Returns: The core after the run
+ */
+pub fn fuzz_image(seed: u64, steps: usize) -> I8080Core {
+    let mut random = XorShift::new(seed);
+    let mut core = random_core(&mut random);
+
+    for _ in 0..steps {
+        match random.next_u8() {
+            0 => {
+                core.i8080_interrupt(random.next_u8());
+            }
+            1 if core.model == CpuModel::I8085 => match random.next_u8() % 4 {
+                0 => core.i8085_trap(),
+                1 => core.i8085_rst75(),
+                2 => core.i8085.rst55 = random.next_bool(),
+                _ => core.i8085.rst65 = random.next_bool(),
+            },
+            1 if core.model == CpuModel::Z80 => core.z80_nmi(),
+            _ => {}
+        }
+        // a trapped opcode leaves the PC on it so move on like a debugger would
+        if core.i8080_step().is_failure() {
+            core.program_counter = core.program_counter.wrapping_add(1);
+        }
+        if core.halted && random.next_u8() < 16 {
+            core.halted = false;
+        }
+    }
+    return core;
+}
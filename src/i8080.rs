@@ -1,6 +1,24 @@
-use std::{fs, usize};
+#![allow(clippy::needless_return)]
+
+use std::fs;
 use std::{thread, time};
 
+pub mod altair;
+pub mod cpm;
+pub mod disk_image;
+pub mod fuzz;
+pub mod i8085;
+pub mod instruction;
+pub mod io;
+pub mod json;
+pub mod invaders;
+pub mod midway;
+pub mod observer;
+pub mod registers;
+pub mod single_step;
+pub mod terminal;
+pub mod z80;
+
 /*
  * Todo
  * Keep reformating and documenting code
@@ -20,10 +38,95 @@ pub enum StepInstructionResult {
     Error,
     NoOperation,
     Halt,
+    // An undocumented opcode was hit with UndocumentedOpcodes::Trap, nothing was executed and the
+    // PC is left on it
+    IllegalOpcode { pc: u16, opcode: u8 },
+    // The I/O device refused an IN or OUT on port (see io::UnmappedPorts::Trap), the instruction ran
+    // to completion and pc is where it started
+    UnmappedPort { pc: u16, port: u8 },
+}
+
+impl StepInstructionResult {
+    /*
+     * is_failure - Function
+     * Expects: N/A
+     * Does: Tells the results a run loop has to stop on apart from HLT
+     * Returns: true for Error, IllegalOpcode and UnmappedPort
+     */
+    pub fn is_failure(&self) -> bool {
+        return matches!(
+            self,
+            StepInstructionResult::Error | StepInstructionResult::IllegalOpcode { .. } | StepInstructionResult::UnmappedPort { .. }
+        );
+    }
+}
+
+/*
+ * UndocumentedOpcodes - What the core does with the 12 opcodes Intel left undocumented
+ * Execute does what the silicon does: 0x08/0x10/0x18/0x20/0x28/0x30/0x38 are NOPs, 0xCB is JMP, 0xD9
+ * is RET and 0xDD/0xED/0xFD are CALL. Trap stops with StepInstructionResult::IllegalOpcode instead so
+ * a stray jump into data fails loudly. In 8085 mode 0x20/0x30 are RIM/SIM and the rest stay NOPs
+ * when executed, in Z80 mode they're all real instructions and the policy doesn't apply.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UndocumentedOpcodes {
+    Execute,
+    Trap,
+}
+
+/*
+ * PowerOnFill - What RAM and the registers hold when I8080Core::power_on switches the machine on
+ * Zero is what new() gives, Pattern fills every byte with the one value and Random fills them from
+ * the seed so a run can be repeated
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PowerOnFill {
+    Zero,
+    Pattern(u8),
+    Random(u64),
 }
 
 const MEMORY_SIZE: usize = 65536;
 
+/*
+ * CpuModel - Which chip the core behaves as
+ * I8085 adds RIM/SIM, the RST 5.5/6.5/7.5 and TRAP inputs, SID/SOD, the 8085 cycle timings and
+ * ANA/ANI always setting the auxiliary carry (see i8085.rs). Z80 decodes the whole Z80 instruction
+ * set with its flags and timings, the CB/DD/ED/FD prefixes, IX/IY, the alternate registers, relative
+ * jumps and IM 0/1/2 (see z80.rs)
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CpuModel {
+    I8080,
+    I8085,
+    Z80,
+}
+
+/*
+ * CYCLE_TABLE - Base number of clock cycles (states) each opcode takes on a real 8080
+ * Conditional CALLs and RETs list their not taken cost, taking the branch costs 6 more cycles
+ * (see conditional_cycles). Undocumented opcodes cost the same as the instruction they alias.
+ */
+pub const CYCLE_TABLE: [u8; 256] = [
+    //0  1   2   3   4   5   6   7   8   9   A   B   C   D   E   F
+    4,  10, 7,  5,  5,  5,  7,  4,  4,  10, 7,  5,  5,  5,  7,  4,  // 0x00
+    4,  10, 7,  5,  5,  5,  7,  4,  4,  10, 7,  5,  5,  5,  7,  4,  // 0x10
+    4,  10, 16, 5,  5,  5,  7,  4,  4,  10, 16, 5,  5,  5,  7,  4,  // 0x20
+    4,  10, 13, 5,  10, 10, 10, 4,  4,  10, 13, 5,  5,  5,  7,  4,  // 0x30
+    5,  5,  5,  5,  5,  5,  7,  5,  5,  5,  5,  5,  5,  5,  7,  5,  // 0x40
+    5,  5,  5,  5,  5,  5,  7,  5,  5,  5,  5,  5,  5,  5,  7,  5,  // 0x50
+    5,  5,  5,  5,  5,  5,  7,  5,  5,  5,  5,  5,  5,  5,  7,  5,  // 0x60
+    7,  7,  7,  7,  7,  7,  7,  7,  5,  5,  5,  5,  5,  5,  7,  5,  // 0x70
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x80
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x90
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0xA0
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0xB0
+    5,  10, 10, 10, 11, 11, 7,  11, 5,  10, 10, 10, 11, 17, 7,  11, // 0xC0
+    5,  10, 10, 10, 11, 11, 7,  11, 5,  10, 10, 10, 11, 17, 7,  11, // 0xD0
+    5,  10, 10, 18, 11, 11, 7,  11, 5,  5,  10, 4,  11, 17, 7,  11, // 0xE0
+    5,  10, 10, 4,  11, 11, 7,  11, 5,  5,  10, 4,  11, 17, 7,  11, // 0xF0
+];
+
 /*
  * get_twos_compliment - Helper Function
  * Expects: N/A
@@ -34,6 +137,101 @@ pub fn get_twos_compliment(value: u8) -> u8 {
     (!value).wrapping_add(1)
 }
 
+/*
+ * crc32 - Helper Function
+ * Expects: N/A
+ * Does: Computes the CRC-32 (IEEE, reflected 0xEDB88320) used by PNG chunks, ZIP files and ROM set listings
+ * Returns: The CRC
+ */
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    return !crc;
+}
+
+// Clock of a stock 8080 system and of a typical 3.125 MHz 8085 system (6.25 MHz crystal)
+pub const I8080_CLOCK_HZ: u64 = 2_000_000;
+pub const I8085_CLOCK_HZ: u64 = 3_125_000;
+
+// Default number of cycles run between pacing checks (1ms worth at 2 MHz)
+const DEFAULT_SLICE_CYCLES: u64 = 2_000;
+// How far behind real time execution may fall before the schedule is reset instead of caught up
+const MAX_THROTTLE_LAG: time::Duration = time::Duration::from_millis(100);
+
+/*
+ * Throttle - Paces a core to a target clock speed
+ * The schedule is kept relative to when pacing started (not per slice) so the small amounts every
+ * sleep oversleeps don't accumulate into drift. If the host stalls for longer than MAX_THROTTLE_LAG
+ * the schedule is re-anchored to now rather than running flat out to catch up.
+ */
+pub struct Throttle {
+    pub clock_hz: u64,
+    pub slice_cycles: u64,
+    start: Option<time::Instant>,
+    start_cycles: u64,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new(I8080_CLOCK_HZ)
+    }
+}
+
+impl Throttle {
+    pub fn new(clock_hz: u64) -> Self {
+        Self {
+            clock_hz,
+            slice_cycles: DEFAULT_SLICE_CYCLES,
+            start: None,
+            start_cycles: 0,
+        }
+    }
+
+    /*
+     * pace - Function
+     * Expects: cycles to be the cores running cycle count (I8080Core::cycles)
+     * Does: Sleeps until real time catches up with the time those cycles take at clock_hz, the first
+     * call only records where the schedule starts
+     * Returns: N/A
+     */
+    pub fn pace(&mut self, cycles: u64) {
+        let start = match self.start {
+            Some(start) => start,
+            None => {
+                self.restart(cycles);
+                return;
+            }
+        };
+
+        let emulated_nanos = (cycles.wrapping_sub(self.start_cycles) as u128 * 1_000_000_000) / self.clock_hz.max(1) as u128;
+        let target = start + time::Duration::from_nanos(emulated_nanos as u64);
+        let now = time::Instant::now();
+
+        if target > now {
+            thread::sleep(target - now);
+        } else if now - target > MAX_THROTTLE_LAG {
+            self.restart(cycles);
+        }
+    }
+
+    /*
+     * restart - Function
+     * Expects: cycles to be the cores running cycle count
+     * Does: Anchors the schedule so that cycles corresponds to the current instant
+     * Returns: N/A
+     */
+    pub fn restart(&mut self, cycles: u64) {
+        self.start = Some(time::Instant::now());
+        self.start_cycles = cycles;
+    }
+}
+
 pub struct I8080Core {
     pub memory: [u8; MEMORY_SIZE],
 
@@ -53,8 +251,40 @@ pub struct I8080Core {
     pub auxiliary_carry: bool,
     pub parity: bool,
     pub carry: bool,
-    pub on_out: Option<fn(&mut I8080Core, u8, u8)>,
+    // Device IN and OUT talk to (see io.rs)
+    pub io: Option<Box<dyn io::IoDevice>>,
+    // Port the device refused during the current instruction
+    io_trapped: Option<u8>,
+    // Hooks for tracers, profilers and the like (see observer.rs)
+    pub observer: Option<Box<dyn observer::Observer>>,
     pub instruction_number: usize,
+    pub cycles: u64,
+
+    // INTE flip flop, set by EI and cleared by DI or accepting an interrupt
+    pub interrupts_enabled: bool,
+    // EI only takes effect after the instruction following it so the delay is tracked here
+    pub interrupt_delay: bool,
+    pub halted: bool,
+
+    // Simple memory map: addresses are ANDed with address_mask (for mirroring) and writes below
+    // rom_end are ignored (write protected ROM), defaults leave the full 64K as RAM. Boards with a
+    // second ROM above their RAM put its start and (exclusive) end after masking in upper_rom
+    pub address_mask: u16,
+    pub rom_end: u16,
+    pub upper_rom: Option<(u16, u16)>,
+
+    pub model: CpuModel,
+    pub undocumented_opcodes: UndocumentedOpcodes,
+    // Interrupt inputs and serial lines only an 8085 has
+    pub i8085: i8085::I8085Pins,
+    // IX, IY, I, R, the alternate registers and interrupt state only a Z80 has
+    pub z80: z80::Z80Registers,
+}
+
+impl Default for I8080Core {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl I8080Core {
@@ -76,11 +306,86 @@ impl I8080Core {
             auxiliary_carry: false,
             parity: false,
             carry: false,
-            on_out: None,
+            io: None,
+            io_trapped: None,
+            observer: None,
             instruction_number: 0,
+            cycles: 0,
+            interrupts_enabled: false,
+            interrupt_delay: false,
+            halted: false,
+            address_mask: 0xFFFF,
+            rom_end: 0,
+            upper_rom: None,
+            model: CpuModel::I8080,
+            undocumented_opcodes: UndocumentedOpcodes::Execute,
+            i8085: i8085::I8085Pins::new(),
+            z80: z80::Z80Registers::new(),
         }
     }
 
+    /*
+     * reset - Function
+     * Expects: N/A
+     * Does: The RESET pin, clears the PC and INTE and takes the processor out of HLT. The other
+     * registers, the flags and memory are left as they were, like the real chip. The 8085 also sets
+     * its RST masks and the Z80 clears I, R and goes to IM 0 (see I8085Pins::reset and Z80Registers::reset)
+     * Returns: N/A
+     */
+    pub fn reset(&mut self) {
+        self.program_counter = 0;
+        self.interrupts_enabled = false;
+        self.interrupt_delay = false;
+        self.halted = false;
+        self.i8085.reset();
+        self.z80.reset();
+    }
+
+    /*
+     * power_on - Function
+     * Expects: A ROM, if there is one, to already be loaded below rom_end
+     * Does: Models switching the machine on, the registers, flags and RAM come up holding fill rather
+     * than the zeros new() gives, then the core is reset. ROM (below rom_end or in upper_rom) is left alone.
+     * Software that reads memory or registers before setting them behaves differently from run to run
+     * with PowerOnFill::Random which is how such bugs show up on real hardware
+     * Returns: N/A
+     */
+    pub fn power_on(&mut self, fill: PowerOnFill) {
+        let mut random = fuzz::XorShift::new(match fill {
+            PowerOnFill::Random(seed) => seed,
+            _ => 0,
+        });
+        let mut next = || match fill {
+            PowerOnFill::Zero => 0,
+            PowerOnFill::Pattern(byte) => byte,
+            PowerOnFill::Random(_) => random.next_u8(),
+        };
+
+        // with everything mirrored or write protected there may be no RAM at all
+        if self.rom_end <= self.address_mask {
+            for address in self.rom_end..=self.address_mask {
+                if !self.is_rom(address) {
+                    self.memory[address as usize] = next();
+                }
+            }
+        }
+        for register in [&mut self.a, &mut self.b, &mut self.c, &mut self.d, &mut self.e, &mut self.h, &mut self.l] {
+            *register = next();
+        }
+        self.stack_pointer = u16::from_le_bytes([next(), next()]);
+        let flags = next();
+        self.set_flags_byte(flags);
+        if self.model == CpuModel::Z80 {
+            self.z80.ix = u16::from_le_bytes([next(), next()]);
+            self.z80.iy = u16::from_le_bytes([next(), next()]);
+            self.z80.af_alternate = u16::from_le_bytes([next(), next()]);
+            self.z80.bc_alternate = u16::from_le_bytes([next(), next()]);
+            self.z80.de_alternate = u16::from_le_bytes([next(), next()]);
+            self.z80.hl_alternate = u16::from_le_bytes([next(), next()]);
+        }
+        self.reset();
+    }
+
     /* i8080_load_rom - loads the ROM into the cores memory
      * Expects: N/A
      * Does: Takes the ROM places it into memory and if it fails provides print feedback aswell as a LoadRomResult return
@@ -189,6 +494,70 @@ impl I8080Core {
         self.carry = first < second;
     }
 
+    /*
+     * read_memory - Function
+     * Expects: N/A
+     * Does: Reads the byte at address through the memory map (so mirrors resolve)
+     * Returns: The byte at address
+     */
+    pub fn read_memory(&self, address: u16) -> u8 {
+        return self.memory[(address & self.address_mask) as usize];
+    }
+
+    /*
+     * write_memory - Function
+     * Expects: N/A
+     * Does: Writes value at address through the memory map, writes landing in ROM are dropped
+     * Returns: N/A
+     */
+    pub fn write_memory(&mut self, address: u16, value: u8) {
+        let address = address & self.address_mask;
+        if !self.is_rom(address) {
+            self.memory[address as usize] = value;
+        }
+    }
+
+    /*
+     * is_rom - Function
+     * Expects: address to already be masked with address_mask
+     * Does: N/A
+     * Returns: true if address is write protected, below rom_end or inside upper_rom
+     */
+    pub fn is_rom(&self, address: u16) -> bool {
+        if address < self.rom_end {
+            return true;
+        }
+        return matches!(self.upper_rom, Some((start, end)) if (start..end).contains(&address));
+    }
+
+    /*
+     * i8080_interrupt - Function
+     * Expects: opcode to be the RST instruction the interrupting device puts on the data bus (0xC7-0xFF)
+     * Does: If interrupts are enabled it clears INTE, leaves HLT and performs the RST pushing the
+     * current PC, just like the real chip does when it samples INT between instructions
+     * Returns: true if the interrupt was accepted otherwise false (disabled, EI delay or not an RST)
+     * In Z80 mode the interrupt mode decides what happens instead (see z80_interrupt)
+     */
+    pub fn i8080_interrupt(&mut self, opcode: u8) -> bool {
+        if self.model == CpuModel::Z80 {
+            return self.z80_interrupt(opcode);
+        }
+        if !self.interrupts_enabled || self.interrupt_delay || opcode & 0xC7 != 0xC7 {
+            return false;
+        }
+        self.interrupts_enabled = false;
+        self.halted = false;
+
+        let return_address = self.program_counter;
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        self.bus_write(self.stack_pointer, (return_address >> 8) as u8);
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        self.bus_write(self.stack_pointer, return_address as u8);
+        self.program_counter = (opcode & 0x38) as u16;
+        self.cycles = self.cycles.wrapping_add(self.cycle_table()[opcode as usize] as u64);
+        return true;
+    }
+
     /*
      * print_state - Debug tool that prints the entire state of the core besides all the memory
      * Expects: N/A
@@ -202,29 +571,200 @@ impl I8080Core {
             self.zero as u8, self.sign as u8, self.parity as u8, self.carry as u8, self.auxiliary_carry as u8);
     }
 
+    /*
+     * condition_met - Function
+     * Expects: opcode to be a conditional JMP, CALL or RET (the condition lives in bits 3-5)
+     * Does: Checks the flag the condition encodes (NZ, Z, NC, C, PO, PE, P, M)
+     * Returns: true if the branch would be taken
+     */
+    pub fn condition_met(&self, opcode: u8) -> bool {
+        match (opcode >> 3) & 0x07 {
+            0 => !self.zero,
+            1 => self.zero,
+            2 => !self.carry,
+            3 => self.carry,
+            4 => !self.parity,
+            5 => self.parity,
+            6 => !self.sign,
+            _ => self.sign,
+        }
+    }
+
+    /*
+     * conditional_cycles - Function
+     * Expects: opcode to be the instruction about to be executed (flags not yet changed by it)
+     * Does: Works out the extra cycles a conditional CALL/RET costs when its branch is taken
+     * Returns: 6 for a taken conditional CALL or RET otherwise 0
+     */
+    fn conditional_cycles(&self, opcode: u8) -> u64 {
+        // RET cc is 11xxx000 and CALL cc is 11xxx100
+        let is_conditional = opcode & 0xC7 == 0xC0 || opcode & 0xC7 == 0xC4;
+        if is_conditional && self.condition_met(opcode) {
+            return 6;
+        }
+        return 0;
+    }
+
+    /*
+     * cycle_table - Function
+     * Expects: N/A
+     * Does: Picks the cycle table of the chip the core is behaving as
+     * Returns: CYCLE_TABLE, I8085_CYCLE_TABLE or Z80_CYCLE_TABLE
+     */
+    pub fn cycle_table(&self) -> &'static [u8; 256] {
+        match self.model {
+            CpuModel::I8080 => return &CYCLE_TABLE,
+            CpuModel::I8085 => return &i8085::I8085_CYCLE_TABLE,
+            CpuModel::Z80 => return &z80::Z80_CYCLE_TABLE,
+        }
+    }
+
     /*
      * i8080_step - Function
      * Epxects: self to be initialized
-     * Does: Performs one instruction (the one pointed at by the program counter)
+     * Does: Performs one instruction (the one pointed at by the program counter) and adds the clock
+     * cycles it took to self.cycles
      * Returns: A StepInstructionResult indicating how things went in the execution of this instruction
      */
     pub fn i8080_step(&mut self) -> StepInstructionResult {
+        // the 8085 samples its own interrupt inputs between instructions, accepting one is a step
+        if self.i8085_service_interrupts() {
+            return StepInstructionResult::Ok;
+        }
+        // a halted core just idles until an interrupt or reset, the HLT state still burns clocks
+        if self.halted {
+            self.cycles = self.cycles.wrapping_add(4);
+            return StepInstructionResult::Halt;
+        }
+        self.observe(|observer, core| observer.before_instruction(core));
+        let result = self.i8080_step_instruction();
+        self.observe(|observer, core| observer.after_instruction(core, &result));
+        return result;
+    }
+
+    /*
+     * i8080_step_instruction - Function
+     * Expects: self not to be halted
+     * Does: The instruction part of i8080_step, executes the instruction at the program counter for
+     * the current model and adds its cycles
+     * Returns: A StepInstructionResult indicating how things went in the execution of this instruction
+     */
+    fn i8080_step_instruction(&mut self) -> StepInstructionResult {
+        self.interrupt_delay = false;
+        let pc = self.program_counter;
+        // the Z80 decoder works out its own cycles as prefixes and operands are fetched
+        if self.model == CpuModel::Z80 {
+            let result = self.z80_execute();
+            return self.io_trap(pc, result);
+        }
+
+        let opcode = self.read_memory(self.program_counter);
+        let cycles = match self.model {
+            CpuModel::I8085 => i8085::I8085_CYCLE_TABLE[opcode as usize] as u64 + self.i8085_conditional_cycles(opcode),
+            _ => CYCLE_TABLE[opcode as usize] as u64 + self.conditional_cycles(opcode),
+        };
+
+        let result = self.i8080_execute();
+        if let StepInstructionResult::IllegalOpcode { .. } = result {
+            return result;
+        }
+        self.cycles = self.cycles.wrapping_add(cycles);
+        return self.io_trap(pc, result);
+    }
+
+    /*
+     * i8080_run_cycles - Function
+     * Expects: self to be initialized
+     * Does: Steps the core until at least cycles clock cycles have passed, stopping early if an
+     * instruction halts or errors
+     * Returns: The StepInstructionResult of the last instruction executed
+     */
+    pub fn i8080_run_cycles(&mut self, cycles: u64) -> StepInstructionResult {
+        let target = self.cycles.wrapping_add(cycles);
+        let mut result = StepInstructionResult::Ok;
+
+        while self.cycles < target {
+            result = self.i8080_step();
+            if result == StepInstructionResult::Halt || result.is_failure() {
+                break;
+            }
+        }
+        return result;
+    }
+
+    /*
+     * i8080_run_throttled - Function
+     * Expects: self to be initialized and throttle to be set to the wanted clock speed
+     * Does: Runs one slice of throttle.slice_cycles and then sleeps so that execution keeps pace with
+     * throttle.clock_hz, hosts call this in a loop instead of i8080_step
+     * Returns: The StepInstructionResult of the last instruction executed
+     */
+    pub fn i8080_run_throttled(&mut self, throttle: &mut Throttle) -> StepInstructionResult {
+        if throttle.start.is_none() {
+            throttle.restart(self.cycles);
+        }
+        let result = self.i8080_run_cycles(throttle.slice_cycles);
+        throttle.pace(self.cycles);
+        return result;
+    }
+
+    /*
+     * i8080_execute - Function
+     * Epxects: self to be initialized
+     * Does: Decodes and executes the instruction pointed at by the program counter
+     * Returns: A StepInstructionResult indicating how things went in the execution of this instruction
+     */
+    fn i8080_execute(&mut self) -> StepInstructionResult {
         let debug = false;
-        let mut instruction: u8;
-        let mut temp1_8: u8;
-        let mut temp2_8: u8;
-        let mut temp3_16: u16;
 
         self.instruction_number = self.instruction_number.wrapping_add(1);
-        instruction = self.memory[self.program_counter as usize];
+        let instruction: u8 = self.bus_read(self.program_counter);
 
 
         if debug {
-            println!("Core state before instruction number #{} and instruction: {:02X}", self.instruction_number, instruction);
+            println!(
+                "Core state before instruction number #{} and instruction: {:02X} {}",
+                self.instruction_number,
+                instruction,
+                self.decode_at(self.program_counter)
+            );
             self.print_state();
             
         }
 
+        return self.i8080_execute_opcode(instruction);
+    }
+
+    /*
+     * undocumented_opcode - Function
+     * Expects: opcode to be one of the undocumented opcodes at the PC, alias the instruction the
+     * silicon performs for it
+     * Does: Applies the UndocumentedOpcodes policy, executing alias or trapping
+     * Returns: The StepInstructionResult of the alias or IllegalOpcode
+     */
+    fn undocumented_opcode(&mut self, opcode: u8, alias: u8) -> StepInstructionResult {
+        if self.undocumented_opcodes == UndocumentedOpcodes::Trap {
+            return StepInstructionResult::IllegalOpcode { pc: self.program_counter, opcode };
+        }
+        // the 8085 has its own undocumented instructions here which the core runs as NOPs
+        if self.model == CpuModel::I8085 {
+            self.program_counter = self.program_counter.wrapping_add(1);
+            return StepInstructionResult::NoOperation;
+        }
+        return self.i8080_execute_opcode(alias);
+    }
+
+    /*
+     * i8080_execute_opcode - Function
+     * Expects: instruction to be the opcode at the program counter
+     * Does: Executes it, the operands are read from after the program counter
+     * Returns: A StepInstructionResult indicating how things went in the execution of this instruction
+     */
+    fn i8080_execute_opcode(&mut self, instruction: u8) -> StepInstructionResult {
+        let debug = false;
+        let temp1_8: u8;
+        let temp2_8: u8;
+        let mut temp3_16: u16;
 
         match instruction {
             0x00 => {
@@ -232,21 +772,20 @@ impl I8080Core {
                 return StepInstructionResult::NoOperation;
             }
             0x01 => {
-                self.b = self.memory[(self.program_counter as usize) + 2];
-                self.c = self.memory[(self.program_counter as usize) + 1];
+                self.b = self.bus_read(self.program_counter.wrapping_add(2));
+                self.c = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(3);
                 return StepInstructionResult::Ok;
             }
             0x02 => {
-                self.memory[((self.b as u16) << 8 | self.c as u16) as usize] = self.a;
+                self.bus_write(self.bc(), self.a);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x03 => {
-                temp3_16 = (self.b as u16) << 8 | (self.c as u16);
+                temp3_16 = self.bc();
                 temp3_16 = temp3_16.wrapping_add(1);
-                self.b = (temp3_16 >> 8) as u8;
-                self.c = (temp3_16 & 0x00FF) as u8;
+                self.set_bc(temp3_16);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -274,7 +813,7 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x06 => {
-                self.b = self.memory[(self.program_counter as usize) + 1];
+                self.b = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
@@ -284,30 +823,25 @@ impl I8080Core {
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
-            0x08 => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
-            }
+            0x08 => return self.undocumented_opcode(0x08, 0x00),
             0x09 => {
-                let mut hl = (self.h as u16) << 8 | self.l as u16;
-                let bc = (self.b as u16) << 8 | self.c as u16;
+                let hl = self.hl();
+                let bc = self.bc();
                 let sum = hl.wrapping_add(bc);
                 self.carry = sum < hl;
-                self.h = (sum >> 8) as u8;
-                self.l = sum as u8;
+                self.set_hl(sum);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x0A => {
-                self.a = self.memory[((self.b as u16) << 8 | self.c as u16) as usize];
+                self.a = self.bus_read(self.bc());
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x0B => {
-                temp3_16 = (self.b as u16) << 8 | self.c as u16;
+                temp3_16 = self.bc();
                 temp3_16 = temp3_16.wrapping_sub(1);
-                self.b = (temp3_16 >> 8) as u8;
-                self.c = (temp3_16 & 0x00FF) as u8;
+                self.set_bc(temp3_16);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -332,7 +866,7 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x0E => {
-                self.c = self.memory[(self.program_counter as usize) + 1];
+                self.c = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
@@ -344,26 +878,22 @@ impl I8080Core {
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
-            0x10 => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
-            }
+            0x10 => return self.undocumented_opcode(0x10, 0x00),
             0x11 => {
-                self.d = self.memory[(self.program_counter as usize) + 2];
-                self.e = self.memory[(self.program_counter as usize) + 1];
+                self.d = self.bus_read(self.program_counter.wrapping_add(2));
+                self.e = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(3);
                 return StepInstructionResult::Ok;
             }
             0x12 => {
-                self.memory[((self.d as u16) << 8 | self.e as u16) as usize] = self.a;
+                self.bus_write(self.de(), self.a);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x13 => {
-                temp3_16 = (self.d as u16) << 8 | (self.e as u16);
+                temp3_16 = self.de();
                 temp3_16 = temp3_16.wrapping_add(1);
-                self.d = (temp3_16 >> 8) as u8;
-                self.e = (temp3_16 & 0x00FF) as u8;
+                self.set_de(temp3_16);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -389,7 +919,7 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x16 => {
-                self.d = self.memory[(self.program_counter as usize) + 1];
+                self.d = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
@@ -401,30 +931,25 @@ impl I8080Core {
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
-            0x18 => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
-            }
+            0x18 => return self.undocumented_opcode(0x18, 0x00),
             0x19 => {
-                let mut hl = (self.h as u16) << 8 | self.l as u16;
-                let de = (self.d as u16) << 8 | self.e as u16;
+                let hl = self.hl();
+                let de = self.de();
                 let sum = hl.wrapping_add(de);
                 self.carry = sum < hl;
-                self.h = (sum >> 8) as u8;
-                self.l = sum as u8;
+                self.set_hl(sum);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x1A => {
-                self.a = self.memory[((self.d as u16) << 8 | (self.e as u16)) as usize];
+                self.a = self.bus_read(self.de());
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x1B => {
-                temp3_16 = (self.d as u16) << 8 | self.e as u16;
+                temp3_16 = self.de();
                 temp3_16 = temp3_16.wrapping_sub(1);
-                self.d = (temp3_16 >> 8) as u8;
-                self.e = (temp3_16 & 0x00FF) as u8;
+                self.set_de(temp3_16);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -449,7 +974,7 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x1E => {
-                self.e = self.memory[(self.program_counter as usize) + 1];
+                self.e = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
@@ -462,28 +987,32 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x20 => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
+                // RIM on the 8085, an undocumented NOP on the 8080
+                if self.model == CpuModel::I8085 {
+                    self.i8085_rim();
+                    self.program_counter = self.program_counter.wrapping_add(1);
+                    return StepInstructionResult::Ok;
+                }
+                return self.undocumented_opcode(0x20, 0x00);
             }
             0x21 => {
-                self.h = self.memory[(self.program_counter as usize) + 2];
-                self.l = self.memory[(self.program_counter as usize) + 1];
+                self.h = self.bus_read(self.program_counter.wrapping_add(2));
+                self.l = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(3);
                 return StepInstructionResult::Ok;
             }
             0x22 => {
-                let addr = (self.memory[(self.program_counter as usize) + 2] as u16) << 8
-                    | (self.memory[(self.program_counter as usize) + 1] as u16);
-                self.memory[addr as usize] = self.l;
-                self.memory[addr as usize + 1] = self.h;
+                let addr = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                    | (self.bus_read(self.program_counter.wrapping_add(1)) as u16);
+                self.bus_write(addr, self.l);
+                self.bus_write(addr.wrapping_add(1), self.h);
                 self.program_counter = self.program_counter.wrapping_add(3);
                 return StepInstructionResult::Ok;
             }
             0x23 => {
-                temp3_16 = (self.h as u16) << 8 | (self.l as u16);
+                temp3_16 = self.hl();
                 temp3_16 = temp3_16.wrapping_add(1);
-                self.h = (temp3_16 >> 8) as u8;
-                self.l = (temp3_16 & 0x00FF) as u8;
+                self.set_hl(temp3_16);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -509,7 +1038,7 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x26 => {
-                self.h = self.memory[(self.program_counter as usize) + 1];
+                self.h = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
@@ -541,32 +1070,27 @@ impl I8080Core {
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
-            0x28 => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
-            }
+            0x28 => return self.undocumented_opcode(0x28, 0x00),
             0x29 => {
-                let mut hl = (self.h as u16) << 8 | self.l as u16;
+                let hl = self.hl();
                 let sum = hl.wrapping_add(hl);
                 self.carry = sum < hl;
-                self.h = (sum >> 8) as u8;
-                self.l = sum as u8;
+                self.set_hl(sum);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x2A => {
-                let addr = (self.memory[(self.program_counter as usize) + 2] as u16) << 8
-                    | (self.memory[(self.program_counter as usize) + 1] as u16);
-                self.l = self.memory[addr as usize];
-                self.h = self.memory[addr as usize + 1];
+                let addr = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                    | (self.bus_read(self.program_counter.wrapping_add(1)) as u16);
+                self.l = self.bus_read(addr);
+                self.h = self.bus_read(addr.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(3);
                 return StepInstructionResult::Ok;
             }
             0x2B => {
-                temp3_16 = (self.h as u16) << 8 | self.l as u16;
+                temp3_16 = self.hl();
                 temp3_16 = temp3_16.wrapping_sub(1);
-                self.h = (temp3_16 >> 8) as u8;
-                self.l = (temp3_16 & 0x00FF) as u8;
+                self.set_hl(temp3_16);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -591,7 +1115,7 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x2E => {
-                self.l = self.memory[(self.program_counter as usize) + 1];
+                self.l = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
@@ -601,21 +1125,26 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x30 => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
+                // SIM on the 8085, an undocumented NOP on the 8080
+                if self.model == CpuModel::I8085 {
+                    self.i8085_sim();
+                    self.program_counter = self.program_counter.wrapping_add(1);
+                    return StepInstructionResult::Ok;
+                }
+                return self.undocumented_opcode(0x30, 0x00);
             }
             0x31 => {
-                self.stack_pointer = (self.memory[(self.program_counter as usize) + 2] as u16) << 8
-                    | self.memory[(self.program_counter as usize) + 1] as u16;
+                self.stack_pointer = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                    | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
 
 
                 self.program_counter = self.program_counter.wrapping_add(3);
                 return StepInstructionResult::Ok;
             }
             0x32 => {
-                let addr = (self.memory[(self.program_counter as usize) + 2] as u16) << 8
-                    | (self.memory[(self.program_counter as usize) + 1] as u16);
-                self.memory[addr as usize] = self.a;
+                let addr = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                    | (self.bus_read(self.program_counter.wrapping_add(1)) as u16);
+                self.bus_write(addr, self.a);
                 self.program_counter = self.program_counter.wrapping_add(3);
                 return StepInstructionResult::Ok;
             }
@@ -625,10 +1154,10 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x34 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                let original = self.memory[addr as usize];
+                let addr = self.hl();
+                let original = self.bus_read(addr);
                 let result = original.wrapping_add(1);
-                self.memory[addr as usize] = result;
+                self.bus_write(addr, result);
 
                 self.set_sign_flag(result);
                 self.set_zero_flag(result);
@@ -638,10 +1167,10 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x35 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                let original = self.memory[addr as usize];
+                let addr = self.hl();
+                let original = self.bus_read(addr);
                 let result = original.wrapping_sub(1);  
-                self.memory[addr as usize] = result;    
+                self.bus_write(addr, result);    
 
                 self.auxiliary_carry = (original & 0x0F) != 0;
                 self.set_sign_flag(result);
@@ -652,9 +1181,10 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x36 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
+                let addr = self.hl();
 
-                self.memory[addr as usize] = self.memory[(self.program_counter as usize) + 1];
+                let value = self.bus_read(self.program_counter.wrapping_add(1));
+                self.bus_write(addr, value);
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
@@ -663,25 +1193,21 @@ impl I8080Core {
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
-            0x38 => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
-            }
+            0x38 => return self.undocumented_opcode(0x38, 0x00),
             0x39 => {
-                let mut hl = (self.h as u16) << 8 | self.l as u16;
+                let hl = self.hl();
                 let sum = hl.wrapping_add(self.stack_pointer);
                 self.carry = sum < hl;
-                self.h = (sum >> 8) as u8;
-                self.l = sum as u8;
+                self.set_hl(sum);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x3A => {
-                let addr = (self.memory[(self.program_counter as usize) + 2] as u16) << 8
-                    | (self.memory[(self.program_counter as usize) + 1] as u16);
+                let addr = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                    | (self.bus_read(self.program_counter.wrapping_add(1)) as u16);
 
 
-                self.a = self.memory[addr as usize];
+                self.a = self.bus_read(addr);
                 self.program_counter = self.program_counter.wrapping_add(3);
                 return StepInstructionResult::Ok;
             }
@@ -711,7 +1237,7 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x3E => {
-                self.a = self.memory[(self.program_counter as usize) + 1];
+                self.a = self.bus_read(self.program_counter.wrapping_add(1));
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
@@ -750,12 +1276,12 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x46 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
+                let addr = self.hl();
                 if debug {
-                    let value = self.memory[addr as usize];
+                    let value = self.read_memory(addr);
                     println!("MOV B,M: reading 0x{:02X} from address 0x{:04X}", value, addr);
                 }
-                self.b = self.memory[addr as usize];
+                self.b = self.bus_read(addr);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -794,8 +1320,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x4E => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.c = self.memory[addr as usize];
+                let addr = self.hl();
+                self.c = self.bus_read(addr);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -834,8 +1360,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x56 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.d = self.memory[addr as usize];
+                let addr = self.hl();
+                self.d = self.bus_read(addr);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -874,8 +1400,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x5E => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.e = self.memory[addr as usize];
+                let addr = self.hl();
+                self.e = self.bus_read(addr);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -914,8 +1440,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x66 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.h = self.memory[addr as usize];
+                let addr = self.hl();
+                self.h = self.bus_read(addr);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -954,8 +1480,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x6E => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.l = self.memory[addr as usize];
+                let addr = self.hl();
+                self.l = self.bus_read(addr);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -965,51 +1491,52 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x70 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
+                let addr = self.hl();
                 if debug {
                     println!("MOV M,B: writing 0x{:02X} to address 0x{:04X}", self.b, addr);
                 }
-                self.memory[addr as usize] = self.b;
+                self.bus_write(addr, self.b);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x71 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.memory[addr as usize] = self.c;
+                let addr = self.hl();
+                self.bus_write(addr, self.c);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x72 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.memory[addr as usize] = self.d;
+                let addr = self.hl();
+                self.bus_write(addr, self.d);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x73 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.memory[addr as usize] = self.e;
+                let addr = self.hl();
+                self.bus_write(addr, self.e);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x74 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.memory[addr as usize] = self.h;
+                let addr = self.hl();
+                self.bus_write(addr, self.h);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x75 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.memory[addr as usize] = self.l;
+                let addr = self.hl();
+                self.bus_write(addr, self.l);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0x76 => {
+                self.halted = true;
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Halt;
             }
             0x77 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.memory[addr as usize] = self.a;
+                let addr = self.hl();
+                self.bus_write(addr, self.a);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -1045,8 +1572,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x7E => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                self.a = self.memory[addr as usize];
+                let addr = self.hl();
+                self.a = self.bus_read(addr);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -1121,13 +1648,10 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x86 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                let sum = self.a as u16 + self.memory[addr as usize] as u16;
-                self.set_auxiliary_carry_addition_flag(
-                    self.a,
-                    self.memory[addr as usize],
-                    sum as u8,
-                );
+                let addr = self.hl();
+                let value = self.bus_read(addr);
+                let sum = self.a as u16 + value as u16;
+                self.set_auxiliary_carry_addition_flag(self.a, value, sum as u8);
                 self.a = sum as u8;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
@@ -1238,14 +1762,15 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x8E => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
+                let addr = self.hl();
+                let value = self.bus_read(addr);
                 let carry_in = if self.carry { 1 } else { 0 } as u8;
                 let sum = self.a as u16
-                    + self.memory[addr as usize] as u16
+                    + value as u16
                     + carry_in as u16;
 
                 // Auxiliary carry from bits 3-4 including the carry in
-                self.auxiliary_carry = ((self.a & 0x0F) + (self.memory[addr as usize] & 0x0F) + carry_in) > 0x0F;
+                self.auxiliary_carry = ((self.a & 0x0F) + (value & 0x0F) + carry_in) > 0x0F;
                 self.a = sum as u8;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
@@ -1336,8 +1861,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x96 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                let value_from_memory = self.memory[addr as usize] as u8;
+                let addr = self.hl();
+                let value_from_memory = self.bus_read(addr);
                 let dif = (self.a as u16).wrapping_sub(value_from_memory as u16);
                 self.set_auxiliary_carry_subtraction_flag(self.a, value_from_memory);
                 self.set_carry_flag_arithmetic_subtraction(self.a, value_from_memory);
@@ -1481,8 +2006,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0x9E => {  // SBB M
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                let mem = self.memory[addr as usize];
+                let addr = self.hl();
+                let mem = self.bus_read(addr);
                 let a = self.a;
                 let carry_in = if self.carry { 1u8 } else { 0u8 };
 
@@ -1520,9 +2045,9 @@ impl I8080Core {
                 let first_bit3 = (self.a >> 3) & 1;
                 let second_bit3 = (self.b >> 3) & 1;
                 
-                // Set auxiliary carry based on OR of bit 3 from both operands
-                self.auxiliary_carry = (first_bit3 | second_bit3) == 1;
-                self.a = self.a & self.b;
+                // Set auxiliary carry based on OR of bit 3 from both operands, the 8085 always sets it
+                self.auxiliary_carry = (first_bit3 | second_bit3) == 1 || self.model == CpuModel::I8085;
+                self.a &= self.b;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1535,9 +2060,9 @@ impl I8080Core {
                 let first_bit3 = (self.a >> 3) & 1;
                 let second_bit3 = (self.c >> 3) & 1;
                 
-                // Set auxiliary carry based on OR of bit 3 from both operands
-                self.auxiliary_carry = (first_bit3 | second_bit3) == 1;
-                self.a = self.a & self.c;
+                // Set auxiliary carry based on OR of bit 3 from both operands, the 8085 always sets it
+                self.auxiliary_carry = (first_bit3 | second_bit3) == 1 || self.model == CpuModel::I8085;
+                self.a &= self.c;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1550,9 +2075,9 @@ impl I8080Core {
                 let first_bit3 = (self.a >> 3) & 1;
                 let second_bit3 = (self.d >> 3) & 1;
                 
-                // Set auxiliary carry based on OR of bit 3 from both operands
-                self.auxiliary_carry = (first_bit3 | second_bit3) == 1;
-                self.a = self.a & self.d;
+                // Set auxiliary carry based on OR of bit 3 from both operands, the 8085 always sets it
+                self.auxiliary_carry = (first_bit3 | second_bit3) == 1 || self.model == CpuModel::I8085;
+                self.a &= self.d;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1565,9 +2090,9 @@ impl I8080Core {
                 let first_bit3 = (self.a >> 3) & 1;
                 let second_bit3 = (self.e >> 3) & 1;
                 
-                // Set auxiliary carry based on OR of bit 3 from both operands
-                self.auxiliary_carry = (first_bit3 | second_bit3) == 1;
-                self.a = self.a & self.e;
+                // Set auxiliary carry based on OR of bit 3 from both operands, the 8085 always sets it
+                self.auxiliary_carry = (first_bit3 | second_bit3) == 1 || self.model == CpuModel::I8085;
+                self.a &= self.e;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1580,9 +2105,9 @@ impl I8080Core {
                 let first_bit3 = (self.a >> 3) & 1;
                 let second_bit3 = (self.h >> 3) & 1;
                 
-                // Set auxiliary carry based on OR of bit 3 from both operands
-                self.auxiliary_carry = (first_bit3 | second_bit3) == 1;
-                self.a = self.a & self.h;
+                // Set auxiliary carry based on OR of bit 3 from both operands, the 8085 always sets it
+                self.auxiliary_carry = (first_bit3 | second_bit3) == 1 || self.model == CpuModel::I8085;
+                self.a &= self.h;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1595,9 +2120,9 @@ impl I8080Core {
                 let first_bit3 = (self.a >> 3) & 1;
                 let second_bit3 = (self.l >> 3) & 1;
                 
-                // Set auxiliary carry based on OR of bit 3 from both operands
-                self.auxiliary_carry = (first_bit3 | second_bit3) == 1;
-                self.a = self.a & self.l;
+                // Set auxiliary carry based on OR of bit 3 from both operands, the 8085 always sets it
+                self.auxiliary_carry = (first_bit3 | second_bit3) == 1 || self.model == CpuModel::I8085;
+                self.a &= self.l;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1606,15 +2131,15 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xA6 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                let value_from_memory = self.memory[addr as usize] as u8;
+                let addr = self.hl();
+                let value_from_memory = self.bus_read(addr);
                 // Save original values for auxiliary carry calculation
                 let first_bit3 = (self.a >> 3) & 1;
                 let second_bit3 = (value_from_memory >> 3) & 1;
                 
-                // Set auxiliary carry based on OR of bit 3 from both operands
-                self.auxiliary_carry = (first_bit3 | second_bit3) == 1;
-                self.a = self.a & value_from_memory;
+                // Set auxiliary carry based on OR of bit 3 from both operands, the 8085 always sets it
+                self.auxiliary_carry = (first_bit3 | second_bit3) == 1 || self.model == CpuModel::I8085;
+                self.a &= value_from_memory;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1627,9 +2152,9 @@ impl I8080Core {
                 let first_bit3 = (self.a >> 3) & 1;
                 let second_bit3 = (self.a >> 3) & 1;
                 
-                // Set auxiliary carry based on OR of bit 3 from both operands
-                self.auxiliary_carry = (first_bit3 | second_bit3) == 1;
-                self.a = self.a & self.a;
+                // Set auxiliary carry based on OR of bit 3 from both operands, the 8085 always sets it
+                self.auxiliary_carry = (first_bit3 | second_bit3) == 1 || self.model == CpuModel::I8085;
+                // ANA A leaves the accumulator unchanged, only the flags are affected
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1639,7 +2164,7 @@ impl I8080Core {
             }
             0xA8 => {
                 self.auxiliary_carry = false;
-                self.a = self.a ^ self.b;
+                self.a ^= self.b;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1649,7 +2174,7 @@ impl I8080Core {
             }
             0xA9 => {
                 self.auxiliary_carry = false;
-                self.a = self.a ^ self.c;
+                self.a ^= self.c;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1659,7 +2184,7 @@ impl I8080Core {
             }
             0xAA => {
                 self.auxiliary_carry = false;
-                self.a = self.a ^ self.d;
+                self.a ^= self.d;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1669,7 +2194,7 @@ impl I8080Core {
             }
             0xAB => {
                 self.auxiliary_carry = false;
-                self.a = self.a ^ self.e;
+                self.a ^= self.e;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1679,7 +2204,7 @@ impl I8080Core {
             }
             0xAC => {
                 self.auxiliary_carry = false;
-                self.a = self.a ^ self.h;
+                self.a ^= self.h;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1689,7 +2214,7 @@ impl I8080Core {
             }
             0xAD => {
                 self.auxiliary_carry = false;
-                self.a = self.a ^ self.l;
+                self.a ^= self.l;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1698,10 +2223,10 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xAE => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                let value_from_memory = self.memory[addr as usize] as u8;
+                let addr = self.hl();
+                let value_from_memory = self.bus_read(addr);
                 self.auxiliary_carry = false;
-                self.a = self.a ^ value_from_memory;
+                self.a ^= value_from_memory;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1711,7 +2236,8 @@ impl I8080Core {
             }
             0xAF => {
                 self.auxiliary_carry = false;
-                self.a = self.a ^ self.a;
+                // XRA A always clears the accumulator
+                self.a = 0;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1721,7 +2247,7 @@ impl I8080Core {
             }
             0xB0 => {
                 self.auxiliary_carry = false;
-                self.a = self.a | self.b;
+                self.a |= self.b;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1731,7 +2257,7 @@ impl I8080Core {
             }
             0xB1 => {
                 self.auxiliary_carry = false;
-                self.a = self.a | self.c;
+                self.a |= self.c;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1741,7 +2267,7 @@ impl I8080Core {
             }
             0xB2 => {
                 self.auxiliary_carry = false;
-                self.a = self.a | self.d;
+                self.a |= self.d;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1751,7 +2277,7 @@ impl I8080Core {
             }
             0xB3 => {
                 self.auxiliary_carry = false;
-                self.a = self.a | self.e;
+                self.a |= self.e;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1761,7 +2287,7 @@ impl I8080Core {
             }
             0xB4 => {
                 self.auxiliary_carry = false;
-                self.a = self.a | self.h;
+                self.a |= self.h;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1771,7 +2297,7 @@ impl I8080Core {
             }
             0xB5 => {
                 self.auxiliary_carry = false;
-                self.a = self.a | self.l;
+                self.a |= self.l;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1780,10 +2306,10 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xB6 => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                let value_from_memory = self.memory[addr as usize] as u8;
+                let addr = self.hl();
+                let value_from_memory = self.bus_read(addr);
                 self.auxiliary_carry = false;
-                self.a = self.a | value_from_memory;
+                self.a |= value_from_memory;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1793,7 +2319,7 @@ impl I8080Core {
             }
             0xB7 => {
                 self.auxiliary_carry = false;
-                self.a = self.a | self.a;
+                // ORA A leaves the accumulator unchanged, only the flags are affected
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -1807,7 +2333,7 @@ impl I8080Core {
                 self.set_carry_flag_arithmetic_subtraction(self.a, self.b);
                 self.set_sign_flag(dif as u8);
                 self.set_zero_flag(dif as u8);
-                self.set_parity_flag(dif as u16);
+                self.set_parity_flag(dif);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -1817,7 +2343,7 @@ impl I8080Core {
                 self.set_carry_flag_arithmetic_subtraction(self.a, self.c);
                 self.set_sign_flag(dif as u8);
                 self.set_zero_flag(dif as u8);
-                self.set_parity_flag(dif as u16);
+                self.set_parity_flag(dif);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -1827,7 +2353,7 @@ impl I8080Core {
                 self.set_carry_flag_arithmetic_subtraction(self.a, self.d);
                 self.set_sign_flag(dif as u8);
                 self.set_zero_flag(dif as u8);
-                self.set_parity_flag(dif as u16);
+                self.set_parity_flag(dif);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -1837,7 +2363,7 @@ impl I8080Core {
                 self.set_carry_flag_arithmetic_subtraction(self.a, self.e);
                 self.set_sign_flag(dif as u8);
                 self.set_zero_flag(dif as u8);
-                self.set_parity_flag(dif as u16);
+                self.set_parity_flag(dif);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -1847,7 +2373,7 @@ impl I8080Core {
                 self.set_carry_flag_arithmetic_subtraction(self.a, self.h);
                 self.set_sign_flag(dif as u8);
                 self.set_zero_flag(dif as u8);
-                self.set_parity_flag(dif as u16);
+                self.set_parity_flag(dif);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
@@ -1857,36 +2383,36 @@ impl I8080Core {
                 self.set_carry_flag_arithmetic_subtraction(self.a, self.l);
                 self.set_sign_flag(dif as u8);
                 self.set_zero_flag(dif as u8);
-                self.set_parity_flag(dif as u16);
+                self.set_parity_flag(dif);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0xBE => {
-                let addr = (self.h as u16) << 8 | (self.l as u16);
-                let value_from_memory = self.memory[addr as usize] as u8;
+                let addr = self.hl();
+                let value_from_memory = self.bus_read(addr);
                 let dif = (self.a as u16).wrapping_sub(value_from_memory as u16);
                 self.set_auxiliary_carry_subtraction_flag(self.a, value_from_memory);
                 self.set_carry_flag_arithmetic_subtraction(self.a, value_from_memory);
                 self.set_sign_flag(dif as u8);
                 self.set_zero_flag(dif as u8);
-                self.set_parity_flag(dif as u16);
+                self.set_parity_flag(dif);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0xBF => {
-                let dif = (self.a as u16).wrapping_sub(self.a as u16);;
+                let dif = (self.a as u16).wrapping_sub(self.a as u16);
                 self.set_auxiliary_carry_subtraction_flag(self.a, self.a);
                 self.set_carry_flag_arithmetic_subtraction(self.a, self.a);
                 self.set_sign_flag(dif as u8);
                 self.set_zero_flag(dif as u8);
-                self.set_parity_flag(dif as u16);
+                self.set_parity_flag(dif);
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0xC0 => {
                 if !self.zero {
-                    temp1_8 = self.memory[self.stack_pointer as usize];
-                    temp2_8 = self.memory[self.stack_pointer as usize + 1];
+                    temp1_8 = self.bus_read(self.stack_pointer);
+                    temp2_8 = self.bus_read(self.stack_pointer.wrapping_add(1));
                     self.stack_pointer = self.stack_pointer.wrapping_add(2);
 
                     self.program_counter = (temp2_8 as u16) << 8 | temp1_8 as u16;
@@ -1896,8 +2422,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xC1 => {
-                self.b = self.memory[self.stack_pointer as usize + 1];
-                self.c = self.memory[self.stack_pointer as usize];
+                self.b = self.bus_read(self.stack_pointer.wrapping_add(1));
+                self.c = self.bus_read(self.stack_pointer);
 
                 self.program_counter = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_add(2);
@@ -1906,9 +2432,9 @@ impl I8080Core {
             }
             0xC2 => {
                 if !self.zero {
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
+                    self.program_counter = (self.bus_read(self.program_counter.wrapping_add(2)) as u16)
                         << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -1917,8 +2443,8 @@ impl I8080Core {
             }
             0xC3 => {
                 
-                self.program_counter = (self.memory[self.program_counter as usize + 2] as u16) << 8
-                    | self.memory[self.program_counter as usize + 1] as u16;
+                self.program_counter = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                    | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
 
 
                 return StepInstructionResult::Ok;
@@ -1926,14 +2452,13 @@ impl I8080Core {
             0xC4 => {
                 temp3_16 = self.program_counter.wrapping_add(3);
                 if !self.zero {
+                    let target = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                    self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = temp3_16 as u8;
-
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
-                        << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                    self.bus_write(self.stack_pointer, temp3_16 as u8);
+                    self.program_counter = target;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -1941,19 +2466,20 @@ impl I8080Core {
             }
             0xC5 => {
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = self.b;
+                self.bus_write(self.stack_pointer, self.b);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = self.c;
+                self.bus_write(self.stack_pointer, self.c);
 
                 self.program_counter = self.program_counter.wrapping_add(1);
 
                 return StepInstructionResult::Ok;
             }
             0xC6 => {
-                let sum = self.a as u16 + self.memory[self.program_counter as usize + 1] as u16;
+                let imm = self.bus_read(self.program_counter.wrapping_add(1));
+                let sum = self.a as u16 + imm as u16;
 
-                self.auxiliary_carry = ((self.a & 0x0F) + (self.memory[self.program_counter as usize + 1] & 0x0F)) > 0x0F;
-                self.carry = (self.a as u16 + self.memory[self.program_counter as usize + 1] as u16) > 0xFF;
+                self.auxiliary_carry = ((self.a & 0x0F) + (imm & 0x0F)) > 0x0F;
+                self.carry = sum > 0xFF;
 
                 self.a = sum as u8;
                 self.set_sign_flag(self.a);
@@ -1966,16 +2492,16 @@ impl I8080Core {
             0xC7 => {
                 temp3_16 = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = temp3_16 as u8;
+                self.bus_write(self.stack_pointer, temp3_16 as u8);
                 self.program_counter = 0x0000;
                 return StepInstructionResult::Ok;
             }
             0xC8 => {
                 if self.zero {
-                    temp1_8 = self.memory[self.stack_pointer as usize];
-                    temp2_8 = self.memory[self.stack_pointer as usize + 1];
+                    temp1_8 = self.bus_read(self.stack_pointer);
+                    temp2_8 = self.bus_read(self.stack_pointer.wrapping_add(1));
                     self.stack_pointer = self.stack_pointer.wrapping_add(2);
 
                     self.program_counter = (temp2_8 as u16) << 8 | temp1_8 as u16;
@@ -1985,8 +2511,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xC9 => {
-                self.program_counter = (self.memory[self.stack_pointer as usize + 1] as u16) << 8
-                    | self.memory[self.stack_pointer as usize] as u16;
+                self.program_counter = (self.bus_read(self.stack_pointer.wrapping_add(1)) as u16) << 8
+                    | self.bus_read(self.stack_pointer) as u16;
 
                 self.stack_pointer = self.stack_pointer.wrapping_add(2);
                 return StepInstructionResult::Ok;
@@ -1996,30 +2522,26 @@ impl I8080Core {
                     println!("zero flag before 0xCA: {}", self.zero);
                 }
                 if self.zero {
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
+                    self.program_counter = (self.bus_read(self.program_counter.wrapping_add(2)) as u16)
                         << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
 
                 return StepInstructionResult::Ok;
             }
-            0xCB => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
-            }
+            0xCB => return self.undocumented_opcode(0xCB, 0xC3),
             0xCC => {
                 temp3_16 = self.program_counter.wrapping_add(3);
                 if self.zero {
+                    let target = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                    self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = temp3_16 as u8;
-
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
-                        << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                    self.bus_write(self.stack_pointer, temp3_16 as u8);
+                    self.program_counter = target;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -2029,17 +2551,19 @@ impl I8080Core {
             0xCD => {
 
                 temp3_16 = self.program_counter.wrapping_add(3);
+                // the target is fetched before the push, which may overwrite it when SP wraps onto the operand
+                let target = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                    | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = temp3_16 as u8;
-                self.program_counter = (self.memory[self.program_counter as usize + 2] as u16) << 8
-                    | self.memory[self.program_counter as usize + 1] as u16;
+                self.bus_write(self.stack_pointer, temp3_16 as u8);
+                self.program_counter = target;
                 return StepInstructionResult::Ok;
             }
             0xCE => {
                 let carry_in = if self.carry { 1u8 } else { 0u8 };
-                let imm = self.memory[self.program_counter as usize + 1];
+                let imm = self.bus_read(self.program_counter.wrapping_add(1));
                 let sum = self.a as u16 + imm as u16 + carry_in as u16;
                 self.auxiliary_carry = ((self.a & 0x0F) + (imm & 0x0F) + carry_in) > 0x0F;
                 self.carry = sum > 0xFF;
@@ -2053,16 +2577,16 @@ impl I8080Core {
             0xCF => {
                 temp3_16 = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = temp3_16 as u8;
+                self.bus_write(self.stack_pointer, temp3_16 as u8);
                 self.program_counter = 0x0008;
                 return StepInstructionResult::Ok;
             }
             0xD0 => {
                 if !self.carry {
-                    temp1_8 = self.memory[self.stack_pointer as usize];
-                    temp2_8 = self.memory[self.stack_pointer as usize + 1];
+                    temp1_8 = self.bus_read(self.stack_pointer);
+                    temp2_8 = self.bus_read(self.stack_pointer.wrapping_add(1));
                     self.stack_pointer = self.stack_pointer.wrapping_add(2);
 
                     self.program_counter = (temp2_8 as u16) << 8 | temp1_8 as u16;
@@ -2072,8 +2596,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xD1 => {
-                self.d = self.memory[self.stack_pointer as usize + 1];
-                self.e = self.memory[self.stack_pointer as usize];
+                self.d = self.bus_read(self.stack_pointer.wrapping_add(1));
+                self.e = self.bus_read(self.stack_pointer);
 
                 self.program_counter = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_add(2);
@@ -2082,9 +2606,9 @@ impl I8080Core {
             }
             0xD2 => {
                 if !self.carry {
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
+                    self.program_counter = (self.bus_read(self.program_counter.wrapping_add(2)) as u16)
                         << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -2093,24 +2617,21 @@ impl I8080Core {
             }
             0xD3 => {
 
-                let port = self.memory[self.program_counter as usize + 1];
-                if let Some(callback) = self.on_out {
-                    callback(self, port, self.a);
-                }
+                let port = self.bus_read(self.program_counter.wrapping_add(1));
+                self.io_output(port, self.a);
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
             0xD4 => {
                 temp3_16 = self.program_counter.wrapping_add(3);
                 if !self.carry {
+                    let target = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                    self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = temp3_16 as u8;
-
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
-                        << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                    self.bus_write(self.stack_pointer, temp3_16 as u8);
+                    self.program_counter = target;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -2118,16 +2639,16 @@ impl I8080Core {
             }
             0xD5 => {
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = self.d;
+                self.bus_write(self.stack_pointer, self.d);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = self.e;
+                self.bus_write(self.stack_pointer, self.e);
 
                 self.program_counter = self.program_counter.wrapping_add(1);
 
                 return StepInstructionResult::Ok;
             }
             0xD6 => {
-                let imm = self.memory[self.program_counter as usize + 1] as u16;
+                let imm = self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 let dif = (self.a as u16).wrapping_sub(imm);
                 
                 self.auxiliary_carry = (self.a & 0x0F) >= (imm as u8 & 0x0F);
@@ -2145,16 +2666,16 @@ impl I8080Core {
             0xD7 => {
                 temp3_16 = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = temp3_16 as u8;
+                self.bus_write(self.stack_pointer, temp3_16 as u8);
                 self.program_counter = 0x0010;
                 return StepInstructionResult::Ok;
             }
             0xD8 => {
                 if self.carry {
-                    temp1_8 = self.memory[self.stack_pointer as usize];
-                    temp2_8 = self.memory[self.stack_pointer as usize + 1];
+                    temp1_8 = self.bus_read(self.stack_pointer);
+                    temp2_8 = self.bus_read(self.stack_pointer.wrapping_add(1));
                     self.stack_pointer = self.stack_pointer.wrapping_add(2);
 
                     self.program_counter = (temp2_8 as u16) << 8 | temp1_8 as u16;
@@ -2163,15 +2684,12 @@ impl I8080Core {
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
-            0xD9 => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
-            }
+            0xD9 => return self.undocumented_opcode(0xD9, 0xC9),
             0xDA => {
                 if self.carry {
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
+                    self.program_counter = (self.bus_read(self.program_counter.wrapping_add(2)) as u16)
                         << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -2179,32 +2697,30 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xDB => {
-                // TODO ADD SPECIFIC IN BEHAVIOR (AS A GENERIC)
+                let port = self.bus_read(self.program_counter.wrapping_add(1));
+                // with nothing attached A is left as it was
+                self.a = self.io_input(port, self.a);
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
             }
             0xDC => {
                 temp3_16 = self.program_counter.wrapping_add(3);
                 if self.carry {
+                    let target = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                    self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = temp3_16 as u8;
-
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
-                        << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                    self.bus_write(self.stack_pointer, temp3_16 as u8);
+                    self.program_counter = target;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
                 return StepInstructionResult::Ok;
             }
-            0xDD => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::NoOperation;
-            }
+            0xDD => return self.undocumented_opcode(0xDD, 0xCD),
             0xDE => {
-                let imm = self.memory[self.program_counter as usize + 1];
+                let imm = self.bus_read(self.program_counter.wrapping_add(1));
                 let carry_in = if self.carry { 1u8 } else { 0u8 };
                 let full_borrow = (imm as u16) + (carry_in as u16);
                 let dif = (self.a as u16).wrapping_sub(full_borrow);
@@ -2222,16 +2738,16 @@ impl I8080Core {
             0xDF => {
                 temp3_16 = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = temp3_16 as u8;
+                self.bus_write(self.stack_pointer, temp3_16 as u8);
                 self.program_counter = 0x0018;
                 return StepInstructionResult::Ok;
             }
             0xE0 => {
                 if !self.parity {
-                    temp1_8 = self.memory[self.stack_pointer as usize];
-                    temp2_8 = self.memory[self.stack_pointer as usize + 1];
+                    temp1_8 = self.bus_read(self.stack_pointer);
+                    temp2_8 = self.bus_read(self.stack_pointer.wrapping_add(1));
                     self.stack_pointer = self.stack_pointer.wrapping_add(2);
 
                     self.program_counter = (temp2_8 as u16) << 8 | temp1_8 as u16;
@@ -2241,8 +2757,8 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xE1 => {
-                self.h = self.memory[self.stack_pointer as usize + 1];
-                self.l = self.memory[self.stack_pointer as usize];
+                self.h = self.bus_read(self.stack_pointer.wrapping_add(1));
+                self.l = self.bus_read(self.stack_pointer);
 
                 self.program_counter = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_add(2);
@@ -2251,9 +2767,9 @@ impl I8080Core {
             }
             0xE2 => {
                 if !self.parity {
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
+                    self.program_counter = (self.bus_read(self.program_counter.wrapping_add(2)) as u16)
                         << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -2264,10 +2780,10 @@ impl I8080Core {
                 temp1_8 = self.h;
                 temp2_8 = self.l;
 
-                self.l = self.memory[self.stack_pointer as usize];
-                self.h = self.memory[self.stack_pointer as usize + 1];
-                self.memory[self.stack_pointer as usize] = temp2_8;
-                self.memory[self.stack_pointer as usize + 1] = temp1_8;
+                self.l = self.bus_read(self.stack_pointer);
+                self.h = self.bus_read(self.stack_pointer.wrapping_add(1));
+                self.bus_write(self.stack_pointer, temp2_8);
+                self.bus_write(self.stack_pointer.wrapping_add(1), temp1_8);
 
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
@@ -2278,14 +2794,13 @@ impl I8080Core {
                     println!("parity flag before 0xE4: {}", self.parity);
                 }
                 if !self.parity {
+                    let target = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                    self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = temp3_16 as u8;
-
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
-                        << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                    self.bus_write(self.stack_pointer, temp3_16 as u8);
+                    self.program_counter = target;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -2293,22 +2808,19 @@ impl I8080Core {
             }
             0xE5 => {
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = self.h;
+                self.bus_write(self.stack_pointer, self.h);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = self.l;
+                self.bus_write(self.stack_pointer, self.l);
 
                 self.program_counter = self.program_counter.wrapping_add(1);
 
                 return StepInstructionResult::Ok;
             }
             0xE6 => {
-                let imm = self.memory[self.program_counter as usize + 1];
-                let a_bit3 = (self.a >> 3) & 1;
-                let imm_bit3 = (imm >> 3) & 1;
+                let imm = self.bus_read(self.program_counter.wrapping_add(1));
+                self.auxiliary_carry = ((self.a | imm) & 0x08) != 0 || self.model == CpuModel::I8085;
                 
-                self.auxiliary_carry = ((self.a | imm) & 0x08) != 0;
-                
-                self.a = self.a & imm;
+                self.a &= imm;
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -2320,17 +2832,17 @@ impl I8080Core {
             0xE7 => {
                 temp3_16 = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = temp3_16 as u8;
+                self.bus_write(self.stack_pointer, temp3_16 as u8);
                 self.program_counter = 0x0020;
                 return StepInstructionResult::Ok;
             }
             0xE8 => {
 
                 if self.parity {
-                    temp1_8 = self.memory[self.stack_pointer as usize];
-                    temp2_8 = self.memory[self.stack_pointer as usize + 1];
+                    temp1_8 = self.bus_read(self.stack_pointer);
+                    temp2_8 = self.bus_read(self.stack_pointer.wrapping_add(1));
                     self.stack_pointer = self.stack_pointer.wrapping_add(2);
 
                     self.program_counter = (temp2_8 as u16) << 8 | temp1_8 as u16;
@@ -2340,14 +2852,14 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xE9 => {
-                self.program_counter = (self.h as u16) << 8 | (self.l as u16);
+                self.program_counter = self.hl();
                 return StepInstructionResult::Ok;
             }
             0xEA => {
                 if self.parity {
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
+                    self.program_counter = (self.bus_read(self.program_counter.wrapping_add(2)) as u16)
                         << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -2372,26 +2884,22 @@ impl I8080Core {
                 temp3_16 = self.program_counter.wrapping_add(3);
                 if self.parity {
 
+                    let target = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                    self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = temp3_16 as u8;
-
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
-                        << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                    self.bus_write(self.stack_pointer, temp3_16 as u8);
+                    self.program_counter = target;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
                 return StepInstructionResult::Ok;
             }
-            0xED => {
-                self.program_counter = self.program_counter.wrapping_add(1);
-                return StepInstructionResult::Ok;
-            }
+            0xED => return self.undocumented_opcode(0xED, 0xCD),
             0xEE => {
                 self.auxiliary_carry = false;
-                self.a = self.a ^ self.memory[self.program_counter as usize + 1] as u8;
+                self.a ^= self.bus_read(self.program_counter.wrapping_add(1));
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -2403,16 +2911,16 @@ impl I8080Core {
             0xEF => {
                 temp3_16 = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = temp3_16 as u8;
+                self.bus_write(self.stack_pointer, temp3_16 as u8);
                 self.program_counter = 0x0028;
                 return StepInstructionResult::Ok;
             }
             0xF0 => {
                 if !self.sign {
-                    temp1_8 = self.memory[self.stack_pointer as usize];
-                    temp2_8 = self.memory[self.stack_pointer as usize + 1];
+                    temp1_8 = self.bus_read(self.stack_pointer);
+                    temp2_8 = self.bus_read(self.stack_pointer.wrapping_add(1));
                     self.stack_pointer = self.stack_pointer.wrapping_add(2);
 
                     self.program_counter = (temp2_8 as u16) << 8 | temp1_8 as u16;
@@ -2422,16 +2930,9 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xF1 => {
-                self.a = self.memory[self.stack_pointer as usize + 1];
-                let flags = self.memory[self.stack_pointer as usize];
-
-                
-
-                self.carry = (flags & 0b00000001) != 0;      // bit 0
-                self.parity = (flags & 0b00000100) != 0;     // bit 2
-                self.auxiliary_carry = (flags & 0b00010000) != 0; // bit 4
-                self.zero = (flags & 0b01000000) != 0;       // bit 6
-                self.sign = (flags & 0b10000000) != 0;       // bit 7
+                let flags = self.bus_read(self.stack_pointer);
+                self.a = self.bus_read(self.stack_pointer.wrapping_add(1));
+                self.set_flags_byte(flags);
 
                 self.program_counter = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_add(2);
@@ -2439,9 +2940,9 @@ impl I8080Core {
             }
             0xF2 => {
                 if !self.sign {
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
+                    self.program_counter = (self.bus_read(self.program_counter.wrapping_add(2)) as u16)
                         << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -2449,51 +2950,39 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xF3 => {
-                //TODO attach intruupts and this disables them till 0xFB
+                self.interrupts_enabled = false;
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0xF4 => {
                 temp3_16 = self.program_counter.wrapping_add(3);
                 if !self.sign {
+                    let target = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                    self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = temp3_16 as u8;
-
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
-                        << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                    self.bus_write(self.stack_pointer, temp3_16 as u8);
+                    self.program_counter = target;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
                 return StepInstructionResult::Ok;
             }
             0xF5 => {
-                // Build the flags byte according to Intel 8080 format
-                let mut flags = 0b00000010; // Bit 1 is always set
-                
-                if self.carry { flags |= 0b00000001; }      // Bit 0 - Carry
-                if self.parity { flags |= 0b00000100; }     // Bit 2 - Parity
-                if self.auxiliary_carry { flags |= 0b00010000; } // Bit 4 - Aux Carry
-                if self.zero { flags |= 0b01000000; }       // Bit 6 - Zero
-                if self.sign { flags |= 0b10000000; }       // Bit 7 - Sign
-                
-                // Explicitly clear reserved bits (though they should already be 0)
-                //flags &= 0b11010111; // Clear bits 3 and 5 (keep them 0)
-                
-                // Push flags first, then accumulator
+                // A goes in the high byte and the flags byte (see flags_byte) in the low
+                let flags = self.flags_byte();
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = self.a;  // flags first
+                self.bus_write(self.stack_pointer, self.a);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = flags;  // A second
-                
+                self.bus_write(self.stack_pointer, flags);
+
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0xF6 => {
                 self.auxiliary_carry = false;
-                self.a = self.a | self.memory[self.program_counter as usize + 1];
+                self.a |= self.bus_read(self.program_counter.wrapping_add(1));
                 self.set_sign_flag(self.a);
                 self.set_zero_flag(self.a);
                 self.set_parity_flag(self.a as u16);
@@ -2504,16 +2993,16 @@ impl I8080Core {
             0xF7 => {
                 temp3_16 = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = temp3_16 as u8;
+                self.bus_write(self.stack_pointer, temp3_16 as u8);
                 self.program_counter = 0x0030;
                 return StepInstructionResult::Ok;
             }
             0xF8 => {
                 if self.sign {
-                    temp1_8 = self.memory[self.stack_pointer as usize];
-                    temp2_8 = self.memory[self.stack_pointer as usize + 1];
+                    temp1_8 = self.bus_read(self.stack_pointer);
+                    temp2_8 = self.bus_read(self.stack_pointer.wrapping_add(1));
                     self.stack_pointer = self.stack_pointer.wrapping_add(2);
 
                     self.program_counter = (temp2_8 as u16) << 8 | temp1_8 as u16;
@@ -2523,15 +3012,15 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xF9 => {
-                self.stack_pointer = (self.h as u16) << 8 | self.l as u16;
+                self.stack_pointer = self.hl();
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0xFA => {
                 if self.sign {
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
+                    self.program_counter = (self.bus_read(self.program_counter.wrapping_add(2)) as u16)
                         << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
@@ -2539,43 +3028,41 @@ impl I8080Core {
                 return StepInstructionResult::Ok;
             }
             0xFB => {
-                //todo!(enable interupts)
+                self.interrupts_enabled = true;
+                self.interrupt_delay = true;
                 self.program_counter = self.program_counter.wrapping_add(1);
                 return StepInstructionResult::Ok;
             }
             0xFC => {
                 temp3_16 = self.program_counter.wrapping_add(3);
                 if self.sign {
+                    let target = (self.bus_read(self.program_counter.wrapping_add(2)) as u16) << 8
+                        | self.bus_read(self.program_counter.wrapping_add(1)) as u16;
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                    self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                     self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                    self.memory[self.stack_pointer as usize] = temp3_16 as u8;
-
-                    self.program_counter = (self.memory[self.program_counter as usize + 2] as u16)
-                        << 8
-                        | self.memory[self.program_counter as usize + 1] as u16;
+                    self.bus_write(self.stack_pointer, temp3_16 as u8);
+                    self.program_counter = target;
                 } else {
                     self.program_counter = self.program_counter.wrapping_add(3);
                 }
                 return StepInstructionResult::Ok;
             }
-            0xFD => {
-                return StepInstructionResult::Ok;
-            }
+            0xFD => return self.undocumented_opcode(0xFD, 0xCD),
             0xFE => { 
 
-                temp1_8 = self.memory[self.program_counter as usize + 1] as u8;   
+                temp1_8 = self.bus_read(self.program_counter.wrapping_add(1));   
                 let dif = (self.a as u16).wrapping_sub(temp1_8 as u16);
 
-                self.auxiliary_carry = (self.a & 0x0F) >= (temp1_8 as u8 & 0x0F);
+                self.auxiliary_carry = (self.a & 0x0F) >= (temp1_8 & 0x0F);
                 
-                self.carry = self.a < temp1_8 as u8;
+                self.carry = self.a < temp1_8;
 
                 self.set_sign_flag(dif as u8);
 
                 self.set_zero_flag(dif as u8);
 
-                self.set_parity_flag(dif as u16);
+                self.set_parity_flag(dif);
 
                 self.program_counter = self.program_counter.wrapping_add(2);
                 return StepInstructionResult::Ok;
@@ -2583,9 +3070,9 @@ impl I8080Core {
             0xFF => {
                 temp3_16 = self.program_counter.wrapping_add(1);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = (temp3_16 >> 8) as u8;
+                self.bus_write(self.stack_pointer, (temp3_16 >> 8) as u8);
                 self.stack_pointer = self.stack_pointer.wrapping_sub(1);
-                self.memory[self.stack_pointer as usize] = temp3_16 as u8;
+                self.bus_write(self.stack_pointer, temp3_16 as u8);
                 self.program_counter = 0x0038;
                 return StepInstructionResult::Ok;
             }
@@ -0,0 +1,216 @@
+use crate::{CpuModel, I8080Core};
+
+/*
+ * I8085_CYCLE_TABLE - Base number of clock cycles (T states) each opcode takes on an 8085
+ * Conditional JMPs, CALLs and RETs list their not taken cost, taking the branch costs 3, 9 and 6
+ * more cycles (see i8085_conditional_cycles). Undocumented opcodes the core treats as NOPs are
+ * counted as NOPs.
+ */
+pub const I8085_CYCLE_TABLE: [u8; 256] = [
+    //0  1   2   3   4   5   6   7   8   9   A   B   C   D   E   F
+    4,  10, 7,  6,  4,  4,  7,  4,  4,  10, 7,  6,  4,  4,  7,  4,  // 0x00
+    4,  10, 7,  6,  4,  4,  7,  4,  4,  10, 7,  6,  4,  4,  7,  4,  // 0x10
+    4,  10, 16, 6,  4,  4,  7,  4,  4,  10, 16, 6,  4,  4,  7,  4,  // 0x20
+    4,  10, 13, 6,  10, 10, 10, 4,  4,  10, 13, 6,  4,  4,  7,  4,  // 0x30
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x40
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x50
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x60
+    7,  7,  7,  7,  7,  7,  5,  7,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x70
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x80
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x90
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0xA0
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0xB0
+    6,  10, 7,  10, 9,  12, 7,  12, 6,  10, 7,  4,  9,  18, 7,  12, // 0xC0
+    6,  10, 7,  10, 9,  12, 7,  12, 6,  4,  7,  10, 9,  4,  7,  12, // 0xD0
+    6,  10, 7,  16, 9,  12, 7,  12, 6,  6,  7,  4,  9,  4,  7,  12, // 0xE0
+    6,  10, 7,  4,  9,  12, 7,  12, 6,  6,  7,  4,  9,  4,  7,  12, // 0xF0
+];
+
+// Restart addresses of the 8085 interrupt inputs
+pub const TRAP_VECTOR: u16 = 0x0024;
+pub const RST55_VECTOR: u16 = 0x002C;
+pub const RST65_VECTOR: u16 = 0x0034;
+pub const RST75_VECTOR: u16 = 0x003C;
+
+// Cycles the 8085 takes to push the PC and restart when it accepts an interrupt
+const INTERRUPT_CYCLES: u64 = 12;
+
+/*
+ * I8085Pins - The extra inputs and outputs of the 8085
+ * RST 5.5 and 6.5 are level inputs the board holds high until the device is serviced, RST 7.5 is a
+ * flip flop set on a rising edge (see i8085_rst75) and TRAP is non maskable (see i8085_trap).
+ * masks holds the SIM mask bits, bit 0 for RST 5.5 up to bit 2 for RST 7.5, a set bit masks it.
+ * sid is the serial input line RIM reads and sod the serial output latch SIM writes.
+ */
+pub struct I8085Pins {
+    pub rst55: bool,
+    pub rst65: bool,
+    pub rst75: bool,
+    pub trap: bool,
+    pub masks: u8,
+    pub sid: bool,
+    pub sod: bool,
+
+    // INTE as it was before a TRAP, the next RIM reports it instead of the current state
+    trap_inte: Option<bool>,
+}
+
+impl Default for I8085Pins {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl I8085Pins {
+    /*
+     * new - Function
+     * Expects: N/A
+     * Does: Creates the pins in their reset state, all three RST inputs masked and nothing pending
+     * Returns: The I8085Pins
+     */
+    pub fn new() -> Self {
+        Self {
+            rst55: false,
+            rst65: false,
+            rst75: false,
+            trap: false,
+            masks: 0x07,
+            sid: false,
+            sod: false,
+            trap_inte: None,
+        }
+    }
+
+    /*
+     * reset - Function
+     * Expects: N/A
+     * Does: What RESET IN does to the interrupt logic, sets all three RST masks, clears the RST 7.5
+     * flip flop and SOD. RST 5.5, RST 6.5, TRAP and SID are inputs driven from outside so they stay
+     * Returns: N/A
+     */
+    pub fn reset(&mut self) {
+        self.masks = 0x07;
+        self.rst75 = false;
+        self.sod = false;
+        self.trap_inte = None;
+    }
+}
+
+impl I8080Core {
+    /*
+     * i8085_trap - Function
+     * Expects: model to be CpuModel::I8085
+     * Does: Raises TRAP, it is accepted before the next instruction whatever INTE and the masks say
+     * Returns: N/A
+     */
+    pub fn i8085_trap(&mut self) {
+        self.i8085.trap = true;
+    }
+
+    /*
+     * i8085_rst75 - Function
+     * Expects: model to be CpuModel::I8085
+     * Does: Gives RST 7.5 a rising edge which sets its flip flop, it stays pending (even while masked)
+     * until it is accepted or SIM resets it
+     * Returns: N/A
+     */
+    pub fn i8085_rst75(&mut self) {
+        self.i8085.rst75 = true;
+    }
+
+    /*
+     * i8085_rim - Function
+     * Expects: N/A
+     * Does: Performs RIM, loading A with SID, the pending RST 7.5/6.5/5.5 inputs, INTE and the masks
+     * Returns: N/A
+     */
+    pub(crate) fn i8085_rim(&mut self) {
+        let pins = &mut self.i8085;
+        let inte = pins.trap_inte.take().unwrap_or(self.interrupts_enabled);
+        self.a = (pins.sid as u8) << 7
+            | (pins.rst75 as u8) << 6
+            | (pins.rst65 as u8) << 5
+            | (pins.rst55 as u8) << 4
+            | (inte as u8) << 3
+            | pins.masks;
+    }
+
+    /*
+     * i8085_sim - Function
+     * Expects: N/A
+     * Does: Performs SIM from A, bit 3 (MSE) loads the masks from bits 0-2, bit 4 resets the RST 7.5
+     * flip flop and bit 6 (SOE) latches bit 7 onto SOD
+     * Returns: N/A
+     */
+    pub(crate) fn i8085_sim(&mut self) {
+        if self.a & 0x08 != 0 {
+            self.i8085.masks = self.a & 0x07;
+        }
+        if self.a & 0x10 != 0 {
+            self.i8085.rst75 = false;
+        }
+        if self.a & 0x40 != 0 {
+            self.i8085.sod = self.a & 0x80 != 0;
+        }
+    }
+
+    /*
+     * i8085_conditional_cycles - Function
+     * Expects: opcode to be the instruction about to be executed (flags not yet changed by it)
+     * Does: Works out the extra cycles a conditional JMP/CALL/RET costs on the 8085 when taken
+     * Returns: 3 for a taken JMP, 9 for a taken CALL, 6 for a taken RET otherwise 0
+     */
+    pub(crate) fn i8085_conditional_cycles(&self, opcode: u8) -> u64 {
+        let extra = match opcode & 0xC7 {
+            0xC0 => 6,
+            0xC2 => 3,
+            0xC4 => 9,
+            _ => return 0,
+        };
+        if self.condition_met(opcode) {
+            return extra;
+        }
+        return 0;
+    }
+
+    /*
+     * i8085_service_interrupts - Function
+     * Expects: model to be CpuModel::I8085, called between instructions
+     * Does: Samples TRAP, RST 7.5, 6.5 and 5.5 in priority order. The first one pending (and for the
+     * RST inputs enabled and unmasked) clears INTE, leaves HLT and restarts at its vector
+     * Returns: true if an interrupt was accepted
+     */
+    pub(crate) fn i8085_service_interrupts(&mut self) -> bool {
+        if self.model != CpuModel::I8085 {
+            return false;
+        }
+        let pins = &mut self.i8085;
+        let vector = if pins.trap {
+            pins.trap = false;
+            pins.trap_inte = Some(self.interrupts_enabled);
+            TRAP_VECTOR
+        } else if !self.interrupts_enabled || self.interrupt_delay {
+            return false;
+        } else if pins.rst75 && pins.masks & 0x04 == 0 {
+            pins.rst75 = false;
+            RST75_VECTOR
+        } else if pins.rst65 && pins.masks & 0x02 == 0 {
+            RST65_VECTOR
+        } else if pins.rst55 && pins.masks & 0x01 == 0 {
+            RST55_VECTOR
+        } else {
+            return false;
+        };
+        self.interrupts_enabled = false;
+        self.halted = false;
+
+        let return_address = self.program_counter;
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        self.bus_write(self.stack_pointer, (return_address >> 8) as u8);
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        self.bus_write(self.stack_pointer, return_address as u8);
+        self.program_counter = vector;
+        self.cycles = self.cycles.wrapping_add(INTERRUPT_CYCLES);
+        return true;
+    }
+}
@@ -0,0 +1,520 @@
+use crate::{I8080Core, CYCLE_TABLE};
+use std::fmt;
+
+/*
+ * Reg - The 8 bit operands in the order the opcode's 3 bit register fields encode them, M is the
+ * byte at HL
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reg {
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    M,
+    A,
+}
+
+/*
+ * RegPair - The pairs LXI, INX, DCX and DAD take, in encoding order
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegPair {
+    BC,
+    DE,
+    HL,
+    SP,
+}
+
+/*
+ * StackPair - The pairs PUSH and POP take, PSW in place of SP
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackPair {
+    BC,
+    DE,
+    HL,
+    PSW,
+}
+
+/*
+ * IndirectPair - The pairs STAX and LDAX can address memory through
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndirectPair {
+    BC,
+    DE,
+}
+
+/*
+ * Cond - The conditions of Jcc, Ccc and Rcc in encoding order
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cond {
+    NZ,
+    Z,
+    NC,
+    C,
+    PO,
+    PE,
+    P,
+    M,
+}
+
+/*
+ * Instruction - One decoded 8080 instruction with its operands
+ * Every one of the 256 opcodes decodes to something, the 12 undocumented ones to the Undocumented
+ * variants which keep the opcode so encode gives back the same bytes. MOV M,M doesn't exist, its
+ * encoding is HLT.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Nop,
+    Lxi(RegPair, u16),
+    Stax(IndirectPair),
+    Ldax(IndirectPair),
+    Shld(u16),
+    Lhld(u16),
+    Sta(u16),
+    Lda(u16),
+    Inx(RegPair),
+    Dcx(RegPair),
+    Dad(RegPair),
+    Inr(Reg),
+    Dcr(Reg),
+    Mvi(Reg, u8),
+    Rlc,
+    Rrc,
+    Ral,
+    Rar,
+    Daa,
+    Cma,
+    Stc,
+    Cmc,
+    Mov(Reg, Reg),
+    Hlt,
+    Add(Reg),
+    Adc(Reg),
+    Sub(Reg),
+    Sbb(Reg),
+    Ana(Reg),
+    Xra(Reg),
+    Ora(Reg),
+    Cmp(Reg),
+    Adi(u8),
+    Aci(u8),
+    Sui(u8),
+    Sbi(u8),
+    Ani(u8),
+    Xri(u8),
+    Ori(u8),
+    Cpi(u8),
+    Rcc(Cond),
+    Ret,
+    Jcc(Cond, u16),
+    Jmp(u16),
+    Ccc(Cond, u16),
+    Call(u16),
+    Rst(u8),
+    Push(StackPair),
+    Pop(StackPair),
+    Out(u8),
+    In(u8),
+    Xthl,
+    Xchg,
+    Pchl,
+    Sphl,
+    Di,
+    Ei,
+    // 0x08/0x10/0x18/0x20/0x28/0x30/0x38 and what the silicon does with 0xCB, 0xD9 and 0xDD/0xED/0xFD
+    UndocumentedNop(u8),
+    UndocumentedJmp(u16),
+    UndocumentedRet,
+    UndocumentedCall(u8, u16),
+}
+
+const REGS: [Reg; 8] = [Reg::B, Reg::C, Reg::D, Reg::E, Reg::H, Reg::L, Reg::M, Reg::A];
+const REG_PAIRS: [RegPair; 4] = [RegPair::BC, RegPair::DE, RegPair::HL, RegPair::SP];
+const STACK_PAIRS: [StackPair; 4] = [StackPair::BC, StackPair::DE, StackPair::HL, StackPair::PSW];
+const CONDS: [Cond; 8] = [Cond::NZ, Cond::Z, Cond::NC, Cond::C, Cond::PO, Cond::PE, Cond::P, Cond::M];
+
+impl Cond {
+    /*
+     * met - Function
+     * Expects: N/A
+     * Does: Checks the condition against the flags of core
+     * Returns: true if a Jcc, Ccc or Rcc on it would be taken
+     */
+    pub fn met(self, core: &I8080Core) -> bool {
+        return core.condition_met((self as u8) << 3);
+    }
+}
+
+impl Instruction {
+    /*
+     * decode - Function
+     * Expects: bytes to start with an opcode, operand bytes past the end of bytes read as 0
+     * Does: Decodes the instruction at the start of bytes
+     * Returns: The Instruction
+     */
+    pub fn decode(bytes: &[u8]) -> Instruction {
+        let byte = |index: usize| bytes.get(index).copied().unwrap_or(0);
+        let opcode = byte(0);
+        let imm = byte(1);
+        let direct = u16::from_le_bytes([byte(1), byte(2)]);
+
+        let reg = REGS[(opcode & 0x07) as usize];
+        let dst = REGS[((opcode >> 3) & 0x07) as usize];
+        let pair = REG_PAIRS[((opcode >> 4) & 0x03) as usize];
+        let stack_pair = STACK_PAIRS[((opcode >> 4) & 0x03) as usize];
+        let cond = CONDS[((opcode >> 3) & 0x07) as usize];
+
+        match opcode {
+            0x00 => return Instruction::Nop,
+            0x08 | 0x10 | 0x18 | 0x20 | 0x28 | 0x30 | 0x38 => return Instruction::UndocumentedNop(opcode),
+            0x22 => return Instruction::Shld(direct),
+            0x2A => return Instruction::Lhld(direct),
+            0x32 => return Instruction::Sta(direct),
+            0x3A => return Instruction::Lda(direct),
+            0x02 => return Instruction::Stax(IndirectPair::BC),
+            0x12 => return Instruction::Stax(IndirectPair::DE),
+            0x0A => return Instruction::Ldax(IndirectPair::BC),
+            0x1A => return Instruction::Ldax(IndirectPair::DE),
+            0x07 => return Instruction::Rlc,
+            0x0F => return Instruction::Rrc,
+            0x17 => return Instruction::Ral,
+            0x1F => return Instruction::Rar,
+            0x27 => return Instruction::Daa,
+            0x2F => return Instruction::Cma,
+            0x37 => return Instruction::Stc,
+            0x3F => return Instruction::Cmc,
+            _ if opcode & 0xCF == 0x01 => return Instruction::Lxi(pair, direct),
+            _ if opcode & 0xCF == 0x03 => return Instruction::Inx(pair),
+            _ if opcode & 0xCF == 0x09 => return Instruction::Dad(pair),
+            _ if opcode & 0xCF == 0x0B => return Instruction::Dcx(pair),
+            _ if opcode & 0xC7 == 0x04 => return Instruction::Inr(dst),
+            _ if opcode & 0xC7 == 0x05 => return Instruction::Dcr(dst),
+            _ if opcode & 0xC7 == 0x06 => return Instruction::Mvi(dst, imm),
+            0x76 => return Instruction::Hlt,
+            0x40..=0x7F => return Instruction::Mov(dst, reg),
+            0x80..=0xBF => match (opcode >> 3) & 0x07 {
+                0 => return Instruction::Add(reg),
+                1 => return Instruction::Adc(reg),
+                2 => return Instruction::Sub(reg),
+                3 => return Instruction::Sbb(reg),
+                4 => return Instruction::Ana(reg),
+                5 => return Instruction::Xra(reg),
+                6 => return Instruction::Ora(reg),
+                _ => return Instruction::Cmp(reg),
+            },
+            0xC6 => return Instruction::Adi(imm),
+            0xCE => return Instruction::Aci(imm),
+            0xD6 => return Instruction::Sui(imm),
+            0xDE => return Instruction::Sbi(imm),
+            0xE6 => return Instruction::Ani(imm),
+            0xEE => return Instruction::Xri(imm),
+            0xF6 => return Instruction::Ori(imm),
+            0xFE => return Instruction::Cpi(imm),
+            0xC3 => return Instruction::Jmp(direct),
+            0xCB => return Instruction::UndocumentedJmp(direct),
+            0xC9 => return Instruction::Ret,
+            0xD9 => return Instruction::UndocumentedRet,
+            0xCD => return Instruction::Call(direct),
+            0xDD | 0xED | 0xFD => return Instruction::UndocumentedCall(opcode, direct),
+            0xD3 => return Instruction::Out(imm),
+            0xDB => return Instruction::In(imm),
+            0xE3 => return Instruction::Xthl,
+            0xEB => return Instruction::Xchg,
+            0xE9 => return Instruction::Pchl,
+            0xF9 => return Instruction::Sphl,
+            0xF3 => return Instruction::Di,
+            0xFB => return Instruction::Ei,
+            _ if opcode & 0xC7 == 0xC0 => return Instruction::Rcc(cond),
+            _ if opcode & 0xC7 == 0xC2 => return Instruction::Jcc(cond, direct),
+            _ if opcode & 0xC7 == 0xC4 => return Instruction::Ccc(cond, direct),
+            _ if opcode & 0xC7 == 0xC7 => return Instruction::Rst((opcode >> 3) & 0x07),
+            _ if opcode & 0xCF == 0xC1 => return Instruction::Pop(stack_pair),
+            _ => return Instruction::Push(stack_pair),
+        }
+    }
+
+    /*
+     * opcode - Function
+     * Expects: N/A
+     * Does: Encodes the opcode byte alone, Rst takes its number modulo 8
+     * Returns: The opcode
+     */
+    pub fn opcode(&self) -> u8 {
+        let pair = |pair: RegPair| (pair as u8) << 4;
+        let alu = |operation: u8, reg: Reg| 0x80 | operation << 3 | reg as u8;
+
+        match *self {
+            Instruction::Nop => return 0x00,
+            Instruction::Lxi(p, _) => return 0x01 | pair(p),
+            Instruction::Stax(p) => return 0x02 | (p as u8) << 4,
+            Instruction::Ldax(p) => return 0x0A | (p as u8) << 4,
+            Instruction::Shld(_) => return 0x22,
+            Instruction::Lhld(_) => return 0x2A,
+            Instruction::Sta(_) => return 0x32,
+            Instruction::Lda(_) => return 0x3A,
+            Instruction::Inx(p) => return 0x03 | pair(p),
+            Instruction::Dcx(p) => return 0x0B | pair(p),
+            Instruction::Dad(p) => return 0x09 | pair(p),
+            Instruction::Inr(r) => return 0x04 | (r as u8) << 3,
+            Instruction::Dcr(r) => return 0x05 | (r as u8) << 3,
+            Instruction::Mvi(r, _) => return 0x06 | (r as u8) << 3,
+            Instruction::Rlc => return 0x07,
+            Instruction::Rrc => return 0x0F,
+            Instruction::Ral => return 0x17,
+            Instruction::Rar => return 0x1F,
+            Instruction::Daa => return 0x27,
+            Instruction::Cma => return 0x2F,
+            Instruction::Stc => return 0x37,
+            Instruction::Cmc => return 0x3F,
+            Instruction::Mov(dst, src) => return 0x40 | (dst as u8) << 3 | src as u8,
+            Instruction::Hlt => return 0x76,
+            Instruction::Add(r) => return alu(0, r),
+            Instruction::Adc(r) => return alu(1, r),
+            Instruction::Sub(r) => return alu(2, r),
+            Instruction::Sbb(r) => return alu(3, r),
+            Instruction::Ana(r) => return alu(4, r),
+            Instruction::Xra(r) => return alu(5, r),
+            Instruction::Ora(r) => return alu(6, r),
+            Instruction::Cmp(r) => return alu(7, r),
+            Instruction::Adi(_) => return 0xC6,
+            Instruction::Aci(_) => return 0xCE,
+            Instruction::Sui(_) => return 0xD6,
+            Instruction::Sbi(_) => return 0xDE,
+            Instruction::Ani(_) => return 0xE6,
+            Instruction::Xri(_) => return 0xEE,
+            Instruction::Ori(_) => return 0xF6,
+            Instruction::Cpi(_) => return 0xFE,
+            Instruction::Rcc(c) => return 0xC0 | (c as u8) << 3,
+            Instruction::Ret => return 0xC9,
+            Instruction::Jcc(c, _) => return 0xC2 | (c as u8) << 3,
+            Instruction::Jmp(_) => return 0xC3,
+            Instruction::Ccc(c, _) => return 0xC4 | (c as u8) << 3,
+            Instruction::Call(_) => return 0xCD,
+            Instruction::Rst(n) => return 0xC7 | (n & 0x07) << 3,
+            Instruction::Push(p) => return 0xC5 | (p as u8) << 4,
+            Instruction::Pop(p) => return 0xC1 | (p as u8) << 4,
+            Instruction::Out(_) => return 0xD3,
+            Instruction::In(_) => return 0xDB,
+            Instruction::Xthl => return 0xE3,
+            Instruction::Xchg => return 0xEB,
+            Instruction::Pchl => return 0xE9,
+            Instruction::Sphl => return 0xF9,
+            Instruction::Di => return 0xF3,
+            Instruction::Ei => return 0xFB,
+            Instruction::UndocumentedNop(opcode) => return opcode,
+            Instruction::UndocumentedJmp(_) => return 0xCB,
+            Instruction::UndocumentedRet => return 0xD9,
+            Instruction::UndocumentedCall(opcode, _) => return opcode,
+        }
+    }
+
+    /*
+     * encode - Function
+     * Expects: N/A
+     * Does: Encodes the instruction the way the assembler would, opcode then operand low byte first
+     * Returns: The 1 to 3 bytes
+     */
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![self.opcode()];
+        match *self {
+            Instruction::Mvi(_, imm)
+            | Instruction::Adi(imm)
+            | Instruction::Aci(imm)
+            | Instruction::Sui(imm)
+            | Instruction::Sbi(imm)
+            | Instruction::Ani(imm)
+            | Instruction::Xri(imm)
+            | Instruction::Ori(imm)
+            | Instruction::Cpi(imm)
+            | Instruction::Out(imm)
+            | Instruction::In(imm) => bytes.push(imm),
+            Instruction::Lxi(_, direct)
+            | Instruction::Shld(direct)
+            | Instruction::Lhld(direct)
+            | Instruction::Sta(direct)
+            | Instruction::Lda(direct)
+            | Instruction::Jcc(_, direct)
+            | Instruction::Jmp(direct)
+            | Instruction::Ccc(_, direct)
+            | Instruction::Call(direct)
+            | Instruction::UndocumentedJmp(direct)
+            | Instruction::UndocumentedCall(_, direct) => bytes.extend_from_slice(&direct.to_le_bytes()),
+            _ => {}
+        }
+        return bytes;
+    }
+
+    /*
+     * length - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: How many bytes the instruction takes, 1 to 3
+     */
+    pub fn length(&self) -> u16 {
+        return self.encode().len() as u16;
+    }
+
+    /*
+     * cycles - Function
+     * Expects: N/A
+     * Does: Looks the 8080 timing up in CYCLE_TABLE
+     * Returns: The clock cycles, for Ccc and Rcc when the condition isn't met (see taken_cycles)
+     */
+    pub fn cycles(&self) -> u8 {
+        return CYCLE_TABLE[self.opcode() as usize];
+    }
+
+    /*
+     * taken_cycles - Function
+     * Expects: N/A
+     * Does: Adds the extra 6 cycles a conditional CALL or RET costs when it is taken
+     * Returns: The clock cycles when the branch is taken, the same as cycles for everything else
+     */
+    pub fn taken_cycles(&self) -> u8 {
+        match self {
+            Instruction::Rcc(_) | Instruction::Ccc(..) => return self.cycles() + 6,
+            _ => return self.cycles(),
+        }
+    }
+}
+
+impl I8080Core {
+    /*
+     * decode_at - Function
+     * Expects: N/A
+     * Does: Decodes the instruction at address without executing it, operands wrap past 0xFFFF like
+     * the core does
+     * Returns: The Instruction
+     */
+    pub fn decode_at(&self, address: u16) -> Instruction {
+        let bytes = [
+            self.read_memory(address),
+            self.read_memory(address.wrapping_add(1)),
+            self.read_memory(address.wrapping_add(2)),
+        ];
+        return Instruction::decode(&bytes);
+    }
+}
+
+impl fmt::Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{:?}", self);
+    }
+}
+
+impl fmt::Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{:?}", self);
+    }
+}
+
+// Intel mnemonics naming pairs by their first register (B, D, H) the way the 8080 assembler does
+impl fmt::Display for RegPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            RegPair::BC => "B",
+            RegPair::DE => "D",
+            RegPair::HL => "H",
+            RegPair::SP => "SP",
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+impl fmt::Display for StackPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            StackPair::BC => "B",
+            StackPair::DE => "D",
+            StackPair::HL => "H",
+            StackPair::PSW => "PSW",
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+impl fmt::Display for IndirectPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            IndirectPair::BC => "B",
+            IndirectPair::DE => "D",
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+// Intel assembler syntax with operands in hex, "MOV A,M", "LXI H,0x1234", "JNZ 0x0100"
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Nop => return write!(f, "NOP"),
+            Instruction::Lxi(p, direct) => return write!(f, "LXI {},0x{:04X}", p, direct),
+            Instruction::Stax(p) => return write!(f, "STAX {}", p),
+            Instruction::Ldax(p) => return write!(f, "LDAX {}", p),
+            Instruction::Shld(direct) => return write!(f, "SHLD 0x{:04X}", direct),
+            Instruction::Lhld(direct) => return write!(f, "LHLD 0x{:04X}", direct),
+            Instruction::Sta(direct) => return write!(f, "STA 0x{:04X}", direct),
+            Instruction::Lda(direct) => return write!(f, "LDA 0x{:04X}", direct),
+            Instruction::Inx(p) => return write!(f, "INX {}", p),
+            Instruction::Dcx(p) => return write!(f, "DCX {}", p),
+            Instruction::Dad(p) => return write!(f, "DAD {}", p),
+            Instruction::Inr(r) => return write!(f, "INR {}", r),
+            Instruction::Dcr(r) => return write!(f, "DCR {}", r),
+            Instruction::Mvi(r, imm) => return write!(f, "MVI {},0x{:02X}", r, imm),
+            Instruction::Rlc => return write!(f, "RLC"),
+            Instruction::Rrc => return write!(f, "RRC"),
+            Instruction::Ral => return write!(f, "RAL"),
+            Instruction::Rar => return write!(f, "RAR"),
+            Instruction::Daa => return write!(f, "DAA"),
+            Instruction::Cma => return write!(f, "CMA"),
+            Instruction::Stc => return write!(f, "STC"),
+            Instruction::Cmc => return write!(f, "CMC"),
+            Instruction::Mov(dst, src) => return write!(f, "MOV {},{}", dst, src),
+            Instruction::Hlt => return write!(f, "HLT"),
+            Instruction::Add(r) => return write!(f, "ADD {}", r),
+            Instruction::Adc(r) => return write!(f, "ADC {}", r),
+            Instruction::Sub(r) => return write!(f, "SUB {}", r),
+            Instruction::Sbb(r) => return write!(f, "SBB {}", r),
+            Instruction::Ana(r) => return write!(f, "ANA {}", r),
+            Instruction::Xra(r) => return write!(f, "XRA {}", r),
+            Instruction::Ora(r) => return write!(f, "ORA {}", r),
+            Instruction::Cmp(r) => return write!(f, "CMP {}", r),
+            Instruction::Adi(imm) => return write!(f, "ADI 0x{:02X}", imm),
+            Instruction::Aci(imm) => return write!(f, "ACI 0x{:02X}", imm),
+            Instruction::Sui(imm) => return write!(f, "SUI 0x{:02X}", imm),
+            Instruction::Sbi(imm) => return write!(f, "SBI 0x{:02X}", imm),
+            Instruction::Ani(imm) => return write!(f, "ANI 0x{:02X}", imm),
+            Instruction::Xri(imm) => return write!(f, "XRI 0x{:02X}", imm),
+            Instruction::Ori(imm) => return write!(f, "ORI 0x{:02X}", imm),
+            Instruction::Cpi(imm) => return write!(f, "CPI 0x{:02X}", imm),
+            Instruction::Rcc(c) => return write!(f, "R{}", c),
+            Instruction::Ret => return write!(f, "RET"),
+            Instruction::Jcc(c, direct) => return write!(f, "J{} 0x{:04X}", c, direct),
+            Instruction::Jmp(direct) => return write!(f, "JMP 0x{:04X}", direct),
+            Instruction::Ccc(c, direct) => return write!(f, "C{} 0x{:04X}", c, direct),
+            Instruction::Call(direct) => return write!(f, "CALL 0x{:04X}", direct),
+            Instruction::Rst(n) => return write!(f, "RST {}", n),
+            Instruction::Push(p) => return write!(f, "PUSH {}", p),
+            Instruction::Pop(p) => return write!(f, "POP {}", p),
+            Instruction::Out(port) => return write!(f, "OUT 0x{:02X}", port),
+            Instruction::In(port) => return write!(f, "IN 0x{:02X}", port),
+            Instruction::Xthl => return write!(f, "XTHL"),
+            Instruction::Xchg => return write!(f, "XCHG"),
+            Instruction::Pchl => return write!(f, "PCHL"),
+            Instruction::Sphl => return write!(f, "SPHL"),
+            Instruction::Di => return write!(f, "DI"),
+            Instruction::Ei => return write!(f, "EI"),
+            Instruction::UndocumentedNop(opcode) => return write!(f, "*NOP 0x{:02X}", opcode),
+            Instruction::UndocumentedJmp(direct) => return write!(f, "*JMP 0x{:04X}", direct),
+            Instruction::UndocumentedRet => return write!(f, "*RET"),
+            Instruction::UndocumentedCall(opcode, direct) => return write!(f, "*CALL 0x{:04X} (0x{:02X})", direct, opcode),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use crate::midway::{Button, MidwayBoard, SPACE_INVADERS};
+use crate::{LoadRomResult, StepInstructionResult, I8080_CLOCK_HZ};
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+
+pub mod sound;
+pub mod video;
+
+use sound::SoundDecoder;
+use video::{Framebuffer, Overlay};
+
+/*
+ * Space Invaders (Midway/Taito 1978) board built around the core
+ *
+ * Memory map (A14 and A15 aren't decoded so everything mirrors every 16K)
+ *   0x0000-0x1FFF  8K ROM (invaders.h, .g, .f, .e)
+ *   0x2000-0x23FF  work RAM
+ *   0x2400-0x3FFF  video RAM, 1 bit per pixel, 256x224 with the screen rotated
+ *
+ * Ports
+ *   IN 0/1/2   inputs and DIP switches       IN 3   shift register result
+ *   OUT 2      shift amount                  OUT 4  shift register data
+ *   OUT 3/5    sound latches                 OUT 6  watchdog
+ *
+ * The board is the MidwayBoard running the SPACE_INVADERS description, with the sound decoder
+ * mapped over the plain latch recorder on OUT 3 and 5. The video hardware interrupts twice a frame,
+ * RST 1 when the beam reaches the middle of the screen and RST 2 at the start of VBLANK.
+ */
+
+pub const ROM_SIZE: usize = 0x2000;
+pub const VIDEO_RAM_START: usize = 0x2400;
+pub const VIDEO_RAM_END: usize = 0x4000;
+
+pub const FRAMES_PER_SECOND: u64 = 60;
+pub const CYCLES_PER_FRAME: u64 = I8080_CLOCK_HZ / FRAMES_PER_SECOND;
+
+// RST 1 and RST 2 as put on the data bus by the interrupt hardware
+pub const MID_FRAME_INTERRUPT: u8 = 0xCF;
+pub const VBLANK_INTERRUPT: u8 = 0xD7;
+
+/*
+ * InvadersInput - Every button and switch wired to the input ports
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InvadersInput {
+    Coin,
+    Player1Start,
+    Player2Start,
+    Player1Fire,
+    Player1Left,
+    Player1Right,
+    Player2Fire,
+    Player2Left,
+    Player2Right,
+    Tilt,
+}
+
+/*
+ * DipSwitches - The operator settings on the board
+ * ships is 3 to 6, bonus_life_at_1000 gives the extra ship at 1000 points instead of 1500 and
+ * show_coin_info controls the coin text in the attract mode
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DipSwitches {
+    pub ships: u8,
+    pub bonus_life_at_1000: bool,
+    pub show_coin_info: bool,
+}
+
+impl Default for DipSwitches {
+    fn default() -> Self {
+        Self {
+            ships: 3,
+            bonus_life_at_1000: false,
+            show_coin_info: true,
+        }
+    }
+}
+
+impl InvadersInput {
+    /*
+     * button - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The midway Button the SPACE_INVADERS description wires this input as
+     */
+    pub fn button(&self) -> Button {
+        match self {
+            InvadersInput::Coin => Button::Coin,
+            InvadersInput::Player1Start => Button::Player1Start,
+            InvadersInput::Player2Start => Button::Player2Start,
+            InvadersInput::Player1Fire => Button::Player1Fire,
+            InvadersInput::Player1Left => Button::Player1Left,
+            InvadersInput::Player1Right => Button::Player1Right,
+            InvadersInput::Player2Fire => Button::Player2Fire,
+            InvadersInput::Player2Left => Button::Player2Left,
+            InvadersInput::Player2Right => Button::Player2Right,
+            InvadersInput::Tilt => Button::Tilt,
+        }
+    }
+}
+
+impl DipSwitches {
+    /*
+     * to_bits - Function
+     * Expects: N/A
+     * Does: Packs the settings the way SPACE_INVADERS wires its DIP bits, ships - 3 in bits 0-1,
+     * bonus life at 1000 in bit 2 and coin info off in bit 3
+     * Returns: The DIP switch byte
+     */
+    pub fn to_bits(&self) -> u8 {
+        let mut bits = self.ships.clamp(3, 6) - 3;
+        if self.bonus_life_at_1000 {
+            bits |= 0x04;
+        }
+        if !self.show_coin_info {
+            bits |= 0x08;
+        }
+        return bits;
+    }
+}
+
+pub struct SpaceInvaders {
+    // Memory map, inputs, shift register and interrupts all come from the SPACE_INVADERS description
+    pub board: MidwayBoard,
+
+    // Sound latches (ports 3 and 5) and the start/stop events decoded from them
+    pub sound: Rc<RefCell<SoundDecoder>>,
+}
+
+impl Default for SpaceInvaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpaceInvaders {
+    pub fn new() -> Self {
+        let board = MidwayBoard::new(&SPACE_INVADERS);
+        let sound = Rc::new(RefCell::new(SoundDecoder::new()));
+        {
+            let mut ports = board.ports.borrow_mut();
+            let id = ports.add(Box::new(sound.clone()));
+            for port in SPACE_INVADERS.sound_ports {
+                ports.map_output(*port..=*port, id);
+            }
+        }
+
+        return Self { board, sound };
+    }
+
+    /*
+     * load_rom_bytes - Function
+     * Expects: data to be a ROM image that fits in the 8K ROM area when placed at address
+     * Does: Copies data into the ROM area (bypassing the write protect)
+     * Returns: LoadRomResult::Error if the image doesn't fit otherwise LoadRomResult::Ok
+     */
+    pub fn load_rom_bytes(&mut self, data: &[u8], address: u16) -> LoadRomResult {
+        return self.board.load_rom_bytes(data, address);
+    }
+
+    /*
+     * load_rom_file - Function
+     * Expects: path to point at a ROM image, either the full 8K or one of the chips
+     * Does: Reads the file and places it at address
+     * Returns: A LoadRomResult that indicates what happened
+     */
+    pub fn load_rom_file(&mut self, path: &str, address: u16) -> LoadRomResult {
+        match fs::read(path) {
+            Ok(data) => {
+                let result = self.load_rom_bytes(&data, address);
+                if result != LoadRomResult::Ok {
+                    println!("ROM file doesn't fit at 0x{:04X}: {}", address, path);
+                }
+                return result;
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
+                println!("ROM file not found: {}", path);
+                return LoadRomResult::NotFound;
+            }
+            Err(_) => {
+                println!("Error loading ROM file: {}", path);
+                return LoadRomResult::Error;
+            }
+        }
+    }
+
+    /*
+     * load_rom_set - Function
+     * Expects: directory to contain invaders.h, invaders.g, invaders.f and invaders.e
+     * Does: Loads each chip at its address, see MidwayBoard::load_rom_set
+     * Returns: The first LoadRomResult that wasn't Ok or LoadRomResult::Ok
+     */
+    pub fn load_rom_set(&mut self, directory: &str) -> LoadRomResult {
+        return self.board.load_rom_set(directory);
+    }
+
+    /*
+     * set_input - Function
+     * Expects: N/A
+     * Does: Presses (true) or releases (false) the given button
+     * Returns: N/A
+     */
+    pub fn set_input(&mut self, input: InvadersInput, pressed: bool) {
+        self.board.set_button(input.button(), pressed);
+    }
+
+    /*
+     * set_dip_switches - Function
+     * Expects: N/A
+     * Does: Changes the operator settings, the game reads them through IN 2
+     * Returns: N/A
+     */
+    pub fn set_dip_switches(&mut self, dip_switches: DipSwitches) {
+        self.board.set_dip_switches(dip_switches.to_bits());
+    }
+
+    /*
+     * step - Function
+     * Expects: A ROM to have been loaded
+     * Does: Executes one instruction on the core, IN and OUT reach the board's devices through core.io
+     * Returns: The StepInstructionResult from the core
+     */
+    pub fn step(&mut self) -> StepInstructionResult {
+        return self.board.step();
+    }
+
+    /*
+     * run_frame - Function
+     * Expects: A ROM to have been loaded
+     * Does: Runs one 60Hz frame worth of cycles firing the mid screen and VBLANK interrupts
+     * Returns: The failing StepInstructionResult if an instruction failed otherwise StepInstructionResult::Ok
+     */
+    pub fn run_frame(&mut self) -> StepInstructionResult {
+        return self.board.run_frame();
+    }
+
+    /*
+     * video_ram - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The 7K of video RAM, 32 bytes per 256 pixel line of the unrotated screen (LSB first)
+     */
+    pub fn video_ram(&self) -> &[u8] {
+        return self.board.video_ram();
+    }
+
+    /*
+     * render - Function
+     * Expects: N/A
+     * Does: Renders the current contents of video RAM, see Framebuffer::render
+     * Returns: The Framebuffer
+     */
+    pub fn render(&self, overlay: Option<&Overlay>) -> Framebuffer {
+        return self.board.render(overlay);
+    }
+}
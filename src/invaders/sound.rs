@@ -0,0 +1,385 @@
+use crate::io::IoDevice;
+use std::fs;
+use std::io;
+
+/*
+ * Space Invaders sound
+ *
+ * The board has no sound CPU, each sound is a discrete analog circuit switched on by a bit in one
+ * of two output latches:
+ *   OUT 3  bit 0 UFO (repeats)   bit 1 shot           bit 2 player death   bit 3 invader hit
+ *          bit 4 extra life      bit 5 amplifier enable
+ *   OUT 5  bits 0-3 the four fleet movement steps       bit 4 UFO hit      bit 5 cocktail flip
+ * A 0 to 1 transition starts a sound and 1 to 0 stops it, the decoder sits on OUT 3 and 5 as a
+ * device and turns the latch writes into those start/stop events stamped with the core's cycle count.
+ */
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Sound {
+    Ufo,
+    Shot,
+    PlayerDeath,
+    InvaderHit,
+    ExtraLife,
+    Fleet1,
+    Fleet2,
+    Fleet3,
+    Fleet4,
+    UfoHit,
+}
+
+// Which latch bit drives which sound
+const PORT3_SOUNDS: [(u8, Sound); 5] = [
+    (0x01, Sound::Ufo),
+    (0x02, Sound::Shot),
+    (0x04, Sound::PlayerDeath),
+    (0x08, Sound::InvaderHit),
+    (0x10, Sound::ExtraLife),
+];
+const PORT5_SOUNDS: [(u8, Sound); 5] = [
+    (0x01, Sound::Fleet1),
+    (0x02, Sound::Fleet2),
+    (0x04, Sound::Fleet3),
+    (0x08, Sound::Fleet4),
+    (0x10, Sound::UfoHit),
+];
+
+impl Sound {
+    /*
+     * sample_name - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The file name the common invaders sample sets use for this sound
+     */
+    pub fn sample_name(&self) -> &'static str {
+        match self {
+            Sound::Ufo => "0.wav",
+            Sound::Shot => "1.wav",
+            Sound::PlayerDeath => "2.wav",
+            Sound::InvaderHit => "3.wav",
+            Sound::Fleet1 => "4.wav",
+            Sound::Fleet2 => "5.wav",
+            Sound::Fleet3 => "6.wav",
+            Sound::Fleet4 => "7.wav",
+            Sound::UfoHit => "8.wav",
+            Sound::ExtraLife => "9.wav",
+        }
+    }
+
+    /*
+     * loops - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: true for sounds that repeat for as long as their bit is set (only the UFO)
+     */
+    pub fn loops(&self) -> bool {
+        return *self == Sound::Ufo;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SoundEvent {
+    pub sound: Sound,
+    // true when the sound starts, false when it stops
+    pub started: bool,
+    // core cycle count when the OUT that caused it started
+    pub cycle: u64,
+}
+
+impl SoundEvent {
+    /*
+     * seconds - Function
+     * Expects: clock_hz to be the clock the core ran at
+     * Does: N/A
+     * Returns: The time of the event in seconds since the core started
+     */
+    pub fn seconds(&self, clock_hz: u64) -> f64 {
+        return self.cycle as f64 / clock_hz as f64;
+    }
+}
+
+/*
+ * SoundDecoder - Watches the two sound latches and queues events for every bit that changes
+ */
+#[derive(Default)]
+pub struct SoundDecoder {
+    pub port3: u8,
+    pub port5: u8,
+    events: Vec<SoundEvent>,
+    // the core's cycle count as of the last access, see IoDevice::clock
+    cycles: u64,
+}
+
+impl SoundDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /*
+     * write - Function
+     * Expects: port to be the OUT port and cycle the core's cycle count
+     * Does: Compares value with what the latch held before and queues a start or stop event for
+     * each sound bit that changed, ports other than 3 and 5 are ignored
+     * Returns: N/A
+     */
+    pub fn write(&mut self, port: u8, value: u8, cycle: u64) {
+        let (previous, sounds) = match port {
+            3 => (&mut self.port3, &PORT3_SOUNDS),
+            5 => (&mut self.port5, &PORT5_SOUNDS),
+            _ => return,
+        };
+        let changed = *previous ^ value;
+        *previous = value;
+
+        for (mask, sound) in sounds.iter() {
+            if changed & mask != 0 {
+                self.events.push(SoundEvent {
+                    sound: *sound,
+                    started: value & mask != 0,
+                    cycle,
+                });
+            }
+        }
+    }
+
+    /*
+     * amplifier_enabled - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: true if the game has the amplifier switched on (port 3 bit 5)
+     */
+    pub fn amplifier_enabled(&self) -> bool {
+        return self.port3 & 0x20 != 0;
+    }
+
+    /*
+     * take_events - Function
+     * Expects: N/A
+     * Does: Empties the event queue
+     * Returns: Every event since the last call in the order they happened
+     */
+    pub fn take_events(&mut self) -> Vec<SoundEvent> {
+        return std::mem::take(&mut self.events);
+    }
+}
+
+impl IoDevice for SoundDecoder {
+    fn input(&mut self, _port: u8) -> u8 {
+        return 0;
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.write(port, value, self.cycles);
+    }
+
+    fn clock(&mut self, cycles: u64) {
+        self.cycles = cycles;
+    }
+}
+
+/*
+ * SampleMixer - Renders a stream of sound events to audio using recorded samples of each circuit
+ * Sounds without a sample are silent, one shot sounds play to the end of their sample even if the
+ * bit drops first which is what the analog circuits do with their decay.
+ */
+pub struct SampleMixer {
+    pub sample_rate: u32,
+    // clock of the core whose cycle counts stamp the events
+    pub clock_hz: u64,
+    samples: Vec<(Sound, Vec<i16>)>,
+}
+
+impl SampleMixer {
+    /*
+     * new - Function
+     * Expects: clock_hz to be the clock the core runs at, I8080_CLOCK_HZ on the real board
+     * Does: Builds a mixer with no samples loaded
+     * Returns: The mixer or an InvalidInput error if either rate is 0
+     */
+    pub fn new(sample_rate: u32, clock_hz: u64) -> io::Result<Self> {
+        if sample_rate == 0 || clock_hz == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "sample rate and clock must not be 0"));
+        }
+        return Ok(Self {
+            sample_rate,
+            clock_hz,
+            samples: Vec::new(),
+        });
+    }
+
+    /*
+     * set_sample - Function
+     * Expects: audio to be mono at self.sample_rate
+     * Does: Uses audio for sound, replacing any sample it already had
+     * Returns: N/A
+     */
+    pub fn set_sample(&mut self, sound: Sound, audio: Vec<i16>) {
+        self.samples.retain(|(existing, _)| *existing != sound);
+        self.samples.push((sound, audio));
+    }
+
+    /*
+     * load_samples - Function
+     * Expects: directory to hold WAV files named like Sound::sample_name (0.wav - 9.wav)
+     * Does: Loads every sample that exists, converting it to mono at self.sample_rate
+     * Returns: How many samples were loaded or the error from a file that exists but couldn't be read
+     */
+    pub fn load_samples(&mut self, directory: &str) -> io::Result<usize> {
+        let sounds = PORT3_SOUNDS.iter().chain(PORT5_SOUNDS.iter()).map(|(_, sound)| *sound);
+        let mut loaded = 0;
+        for sound in sounds {
+            let path = format!("{}/{}", directory.trim_end_matches('/'), sound.sample_name());
+            let data = match fs::read(&path) {
+                Ok(data) => data,
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            let (rate, audio) = decode_wav(&data)?;
+            self.set_sample(sound, resample(&audio, rate, self.sample_rate));
+            loaded += 1;
+        }
+        return Ok(loaded);
+    }
+
+    /*
+     * mix - Function
+     * Expects: events to be in order and stamped with cycles of a core running at self.clock_hz
+     * Does: Renders end_cycle cycles worth of audio placing each sample where its event happened
+     * Returns: Mono 16 bit audio at self.sample_rate
+     */
+    pub fn mix(&self, events: &[SoundEvent], end_cycle: u64) -> Vec<i16> {
+        let to_frame = |cycle: u64| (cycle as u128 * self.sample_rate as u128 / self.clock_hz as u128) as usize;
+        let length = to_frame(end_cycle);
+        let mut mixed = vec![0i32; length];
+
+        for (index, event) in events.iter().enumerate() {
+            if !event.started {
+                continue;
+            }
+            let sample = match self.samples.iter().find(|(sound, _)| *sound == event.sound) {
+                Some((_, sample)) if !sample.is_empty() => sample,
+                _ => continue,
+            };
+
+            let start = to_frame(event.cycle);
+            let end = if event.sound.loops() {
+                // loop until the matching stop event (or the end of the recording)
+                let stop = events[index + 1..].iter().find(|later| later.sound == event.sound && !later.started);
+                match stop {
+                    Some(stop) => to_frame(stop.cycle),
+                    None => length,
+                }
+            } else {
+                start + sample.len()
+            };
+
+            for (offset, frame) in (start..end.min(length)).enumerate() {
+                mixed[frame] += sample[offset % sample.len()] as i32;
+            }
+        }
+
+        return mixed.iter().map(|value| (*value).clamp(i16::MIN as i32, i16::MAX as i32) as i16).collect();
+    }
+}
+
+/*
+ * decode_wav - Function
+ * Expects: data to be a PCM WAV file, 8 bit unsigned or 16 bit signed, any number of channels
+ * Does: Parses the RIFF chunks and mixes every channel down to one
+ * Returns: The sample rate and mono 16 bit audio, or an InvalidData error for anything else
+ */
+pub fn decode_wav(data: &[u8]) -> io::Result<(u32, Vec<i16>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err(invalid("not a RIFF WAVE file"));
+    }
+
+    let mut format: Option<(u16, u16, u32, u16)> = None;
+    let mut position = 12;
+    while position + 8 <= data.len() {
+        let id = &data[position..position + 4];
+        let size = u32::from_le_bytes([data[position + 4], data[position + 5], data[position + 6], data[position + 7]]) as usize;
+        let body_start = position + 8;
+        let body_end = (body_start + size).min(data.len());
+        let body = &data[body_start..body_end];
+
+        if id == b"fmt " && body.len() >= 16 {
+            let audio_format = u16::from_le_bytes([body[0], body[1]]);
+            let channels = u16::from_le_bytes([body[2], body[3]]);
+            let rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+            let bits = u16::from_le_bytes([body[14], body[15]]);
+            format = Some((audio_format, channels, rate, bits));
+        } else if id == b"data" {
+            let (audio_format, channels, rate, bits) = match format {
+                Some(format) => format,
+                None => return Err(invalid("data chunk before fmt chunk")),
+            };
+            if audio_format != 1 || channels == 0 || (bits != 8 && bits != 16) {
+                return Err(invalid("only 8 or 16 bit PCM is supported"));
+            }
+
+            let bytes_per_frame = (bits / 8) as usize * channels as usize;
+            let mut audio = Vec::with_capacity(body.len() / bytes_per_frame);
+            for frame in body.chunks_exact(bytes_per_frame) {
+                let mut sum: i32 = 0;
+                for channel in 0..channels as usize {
+                    sum += if bits == 8 {
+                        (frame[channel] as i32 - 128) << 8
+                    } else {
+                        i16::from_le_bytes([frame[channel * 2], frame[channel * 2 + 1]]) as i32
+                    };
+                }
+                audio.push((sum / channels as i32) as i16);
+            }
+            return Ok((rate, audio));
+        }
+
+        // chunks are padded to an even length
+        position = body_start + size + (size & 1);
+    }
+    return Err(invalid("no data chunk"));
+}
+
+/*
+ * encode_wav - Function
+ * Expects: N/A
+ * Does: Builds a mono 16 bit PCM WAV file
+ * Returns: The file contents
+ */
+pub fn encode_wav(audio: &[i16], sample_rate: u32) -> Vec<u8> {
+    let data_size = (audio.len() * 2) as u32;
+    let mut data = Vec::with_capacity(44 + audio.len() * 2);
+    data.extend_from_slice(b"RIFF");
+    data.extend_from_slice(&(36 + data_size).to_le_bytes());
+    data.extend_from_slice(b"WAVEfmt ");
+    data.extend_from_slice(&16u32.to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    data.extend_from_slice(&1u16.to_le_bytes()); // mono
+    data.extend_from_slice(&sample_rate.to_le_bytes());
+    data.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    data.extend_from_slice(&2u16.to_le_bytes()); // block align
+    data.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    data.extend_from_slice(b"data");
+    data.extend_from_slice(&data_size.to_le_bytes());
+    for sample in audio {
+        data.extend_from_slice(&sample.to_le_bytes());
+    }
+    return data;
+}
+
+/*
+ * resample - Function
+ * Expects: N/A
+ * Does: Converts audio from one sample rate to another by picking the nearest sample, plenty for
+ * these short effects
+ * Returns: The converted audio
+ */
+pub fn resample(audio: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
+    if from_rate == to_rate || from_rate == 0 {
+        return audio.to_vec();
+    }
+    let length = (audio.len() as u64 * to_rate as u64 / from_rate as u64) as usize;
+    return (0..length)
+        .map(|index| audio[((index as u64 * from_rate as u64 / to_rate as u64) as usize).min(audio.len() - 1)])
+        .collect();
+}
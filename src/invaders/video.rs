@@ -0,0 +1,292 @@
+use crate::crc32;
+use std::fs;
+use std::io;
+
+/*
+ * Space Invaders video output
+ *
+ * Video RAM holds a 256x224 1 bit per pixel raster, 32 bytes a line with the least significant bit
+ * being the leftmost pixel. The monitor sits in the cabinet rotated 90 degrees counter clockwise so
+ * the picture the player sees is 224 wide and 256 tall. The monitor itself is black and white, the
+ * colours come from strips of cellophane (gels) stuck over parts of the screen.
+ */
+
+pub const SCREEN_WIDTH: usize = 224;
+pub const SCREEN_HEIGHT: usize = 256;
+const RASTER_BYTES_PER_LINE: usize = 32;
+
+pub const WHITE: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+pub const BLACK: [u8; 4] = [0x00, 0x00, 0x00, 0xFF];
+pub const RED: [u8; 4] = [0xFF, 0x20, 0x20, 0xFF];
+pub const GREEN: [u8; 4] = [0x20, 0xFF, 0x20, 0xFF];
+
+/*
+ * Orientation - How the monitor is mounted in the cabinet
+ * RotatedCounterClockwise gives a 224x256 picture (Space Invaders and most of the Midway 8080 games),
+ * Upright shows the raster as is at 256x224
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Orientation {
+    RotatedCounterClockwise,
+    Upright,
+}
+
+/*
+ * ColorStrip - A rectangle of the rotated screen (in screen pixels, end exclusive) tinted a colour
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ColorStrip {
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+    pub color: [u8; 4],
+}
+
+/*
+ * Overlay - The set of gels over the monitor, lit pixels outside every strip are white
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Overlay {
+    pub strips: Vec<ColorStrip>,
+}
+
+impl Overlay {
+    /*
+     * cabinet - Function
+     * Expects: N/A
+     * Does: Builds the usual upright cabinet gels, red over the UFO band and green over the player,
+     * the shields and the reserve ships in the bottom left
+     * Returns: The Overlay
+     */
+    pub fn cabinet() -> Self {
+        Self {
+            strips: vec![
+                ColorStrip { top: 32, bottom: 64, left: 0, right: SCREEN_WIDTH, color: RED },
+                ColorStrip { top: 184, bottom: 240, left: 0, right: SCREEN_WIDTH, color: GREEN },
+                ColorStrip { top: 240, bottom: SCREEN_HEIGHT, left: 16, right: 134, color: GREEN },
+            ],
+        }
+    }
+
+    /*
+     * color_at - Function
+     * Expects: x and y to be inside the screen
+     * Does: Finds the first strip covering the pixel
+     * Returns: The colour a lit pixel at x, y shows up as
+     */
+    pub fn color_at(&self, x: usize, y: usize) -> [u8; 4] {
+        for strip in &self.strips {
+            if y >= strip.top && y < strip.bottom && x >= strip.left && x < strip.right {
+                return strip.color;
+            }
+        }
+        return WHITE;
+    }
+}
+
+/*
+ * Framebuffer - A rendered frame as the player sees it, RGBA with 4 bytes per pixel row by row
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    /*
+     * render - Function
+     * Expects: video_ram to be the 7K from 0x2400 (see SpaceInvaders::video_ram)
+     * Does: Rotates the raster into screen orientation and colours lit pixels through overlay, pass
+     * None for plain black and white
+     * Returns: The Framebuffer
+     */
+    pub fn render(video_ram: &[u8], overlay: Option<&Overlay>) -> Self {
+        return Self::render_oriented(video_ram, overlay, Orientation::RotatedCounterClockwise);
+    }
+
+    /*
+     * render_oriented - Function
+     * Expects: video_ram to be the board's 256x224 raster
+     * Does: Same as render but for a monitor mounted the given way
+     * Returns: The Framebuffer
+     */
+    pub fn render_oriented(video_ram: &[u8], overlay: Option<&Overlay>, orientation: Orientation) -> Self {
+        let (width, height) = match orientation {
+            Orientation::RotatedCounterClockwise => (SCREEN_WIDTH, SCREEN_HEIGHT),
+            Orientation::Upright => (SCREEN_HEIGHT, SCREEN_WIDTH),
+        };
+        let mut pixels = vec![0; width * height * 4];
+        for chunk in pixels.chunks_exact_mut(4) {
+            chunk.copy_from_slice(&BLACK);
+        }
+
+        for (offset, byte) in video_ram.iter().enumerate() {
+            if *byte == 0 {
+                continue;
+            }
+            let line = offset / RASTER_BYTES_PER_LINE;
+            for bit in 0..8 {
+                if byte & (1 << bit) == 0 {
+                    continue;
+                }
+                let raster_x = (offset % RASTER_BYTES_PER_LINE) * 8 + bit;
+                let (x, y) = match orientation {
+                    Orientation::RotatedCounterClockwise => (line, SCREEN_HEIGHT - 1 - raster_x),
+                    Orientation::Upright => (raster_x, line),
+                };
+                if x >= width || y >= height {
+                    continue;
+                }
+
+                let color = match overlay {
+                    Some(overlay) => overlay.color_at(x, y),
+                    None => WHITE,
+                };
+                let index = (y * width + x) * 4;
+                pixels[index..index + 4].copy_from_slice(&color);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /*
+     * pixel - Function
+     * Expects: x and y to be inside the frame
+     * Does: N/A
+     * Returns: The RGBA value of the pixel
+     */
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let index = (y * self.width + x) * 4;
+        let mut rgba = [0; 4];
+        rgba.copy_from_slice(&self.pixels[index..index + 4]);
+        return rgba;
+    }
+
+    /*
+     * to_ppm - Function
+     * Expects: N/A
+     * Does: Encodes the frame as a binary (P6) PPM, alpha is dropped
+     * Returns: The file contents
+     */
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for rgba in self.pixels.chunks_exact(4) {
+            data.extend_from_slice(&rgba[..3]);
+        }
+        return data;
+    }
+
+    /*
+     * to_png - Function
+     * Expects: N/A
+     * Does: Encodes the frame as an RGBA PNG, the image data goes in uncompressed (stored) deflate
+     * blocks so no compression library is needed
+     * Returns: The file contents
+     */
+    pub fn to_png(&self) -> Vec<u8> {
+        // every row starts with filter type 0 (none)
+        let mut raw = Vec::with_capacity((self.width * 4 + 1) * self.height);
+        for row in self.pixels.chunks_exact(self.width * 4) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bits per channel, colour type 6 (RGBA), deflate, adaptive filtering, no interlace
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut data = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        write_png_chunk(&mut data, b"IHDR", &header);
+        write_png_chunk(&mut data, b"IDAT", &zlib_stored(&raw));
+        write_png_chunk(&mut data, b"IEND", &[]);
+        return data;
+    }
+
+    /*
+     * save_ppm - Function
+     * Expects: path to be writable
+     * Does: Writes the frame as a PPM file
+     * Returns: Any IO error from writing
+     */
+    pub fn save_ppm(&self, path: &str) -> io::Result<()> {
+        return fs::write(path, self.to_ppm());
+    }
+
+    /*
+     * save_png - Function
+     * Expects: path to be writable
+     * Does: Writes the frame as a PNG file
+     * Returns: Any IO error from writing
+     */
+    pub fn save_png(&self, path: &str) -> io::Result<()> {
+        return fs::write(path, self.to_png());
+    }
+}
+
+/*
+ * write_png_chunk - Helper Function
+ * Expects: kind to be a 4 letter PNG chunk type
+ * Does: Appends the length, type, data and CRC of the chunk to out
+ * Returns: N/A
+ */
+fn write_png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/*
+ * zlib_stored - Helper Function
+ * Expects: N/A
+ * Does: Wraps data in a zlib stream made of stored (uncompressed) deflate blocks
+ * Returns: The zlib stream
+ */
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xFFFF;
+    let mut out = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        out.push(if last { 0x01 } else { 0x00 });
+        let length = block.len() as u16;
+        out.extend_from_slice(&length.to_le_bytes());
+        out.extend_from_slice(&(!length).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    return out;
+}
+
+/*
+ * adler32 - Helper Function
+ * Expects: N/A
+ * Does: Computes the Adler-32 checksum that ends a zlib stream
+ * Returns: The checksum
+ */
+fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    return (b << 16) | a;
+}
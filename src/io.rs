@@ -0,0 +1,306 @@
+use crate::{I8080Core, StepInstructionResult};
+use std::cell::RefCell;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+/*
+ * IoDevice - Whatever sits on the I/O bus of the core, IN asks it for a byte and OUT hands it one
+ * Devices own their state (a shift register, a UART, a console) so nothing has to live in globals.
+ * Attach one with core.io = Some(Box::new(device)), without one IN leaves A alone and OUT goes nowhere.
+ * A machine that needs to reach a device's state while the core owns it attaches an Rc<RefCell<_>>
+ * of the device and keeps a clone.
+ */
+pub trait IoDevice {
+    fn input(&mut self, port: u8) -> u8;
+    fn output(&mut self, port: u8, value: u8);
+
+    /*
+     * clock - Function
+     * Expects: cycles to be the core's cycle count
+     * Does: Called right before every IN and OUT so devices that care about timing (sound latches,
+     * anything that stamps its events) know when the access happened. The default ignores it
+     * Returns: N/A
+     */
+    fn clock(&mut self, _cycles: u64) {}
+
+    /*
+     * trapped_port - Function
+     * Expects: N/A
+     * Does: Lets a device refuse an access, the core asks after every instruction and stops with
+     * StepInstructionResult::UnmappedPort. Devices that take everything keep the default
+     * Returns: The port of the refused access, clearing it, or None
+     */
+    fn trapped_port(&mut self) -> Option<u8> {
+        return None;
+    }
+}
+
+impl I8080Core {
+    /*
+     * io_input - Function
+     * Expects: no_device to be what the instruction loads when nothing is attached
+     * Does: Performs the IN bus cycle for port on the attached device, the observer sees the value
+     * loaded either way
+     * Returns: The byte the device put on the bus or no_device if there is no device
+     */
+    pub fn io_input(&mut self, port: u8, no_device: u8) -> u8 {
+        let value = match self.io.as_mut() {
+            Some(device) => {
+                device.clock(self.cycles);
+                let value = device.input(port);
+                self.io_trapped = self.io_trapped.or(device.trapped_port());
+                value
+            }
+            None => no_device,
+        };
+        if let Some(observer) = self.observer.as_mut() {
+            observer.port_input(port, value);
+        }
+        return value;
+    }
+
+    /*
+     * io_output - Function
+     * Expects: N/A
+     * Does: Performs the OUT bus cycle writing value to port on the attached device if there is one
+     * Returns: N/A
+     */
+    pub fn io_output(&mut self, port: u8, value: u8) {
+        if let Some(device) = self.io.as_mut() {
+            device.clock(self.cycles);
+            device.output(port, value);
+            self.io_trapped = self.io_trapped.or(device.trapped_port());
+        }
+        if let Some(observer) = self.observer.as_mut() {
+            observer.port_output(port, value);
+        }
+    }
+
+    /*
+     * io_trap - Function
+     * Expects: pc to be where the instruction that just ran started
+     * Does: Turns an access the attached device refused during that instruction into a failure
+     * Returns: StepInstructionResult::UnmappedPort or result untouched
+     */
+    pub(crate) fn io_trap(&mut self, pc: u16, result: StepInstructionResult) -> StepInstructionResult {
+        if let Some(port) = self.io_trapped.take() {
+            return StepInstructionResult::UnmappedPort { pc, port };
+        }
+        return result;
+    }
+}
+
+impl<D: IoDevice> IoDevice for Rc<RefCell<D>> {
+    fn input(&mut self, port: u8) -> u8 {
+        return self.borrow_mut().input(port);
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.borrow_mut().output(port, value);
+    }
+
+    fn clock(&mut self, cycles: u64) {
+        self.borrow_mut().clock(cycles);
+    }
+
+    fn trapped_port(&mut self) -> Option<u8> {
+        return self.borrow_mut().trapped_port();
+    }
+}
+
+/*
+ * UnmappedPorts - What a PortMap does with a port nothing is mapped on
+ * Float: IN reads 0xFF like a bus nobody drives with pull ups, OUT is dropped
+ * OpenBus: IN reads whatever byte last went over the I/O bus, OUT is dropped but still lands on the bus
+ * Trap: IN reads 0xFF and the step stops with StepInstructionResult::UnmappedPort, same for OUT
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnmappedPorts {
+    Float,
+    OpenBus,
+    Trap,
+}
+
+/*
+ * PortMap - Device registry that routes IN and OUT by port number
+ * Devices are added once and mapped onto single ports or ranges, separately for IN and OUT so a read
+ * only and a write only device can share a port. Devices still see the full port number.
+ * A PortMap is an IoDevice itself, attach it with core.io = Some(Box::new(ports)).
+ */
+pub struct PortMap {
+    pub unmapped: UnmappedPorts,
+    // last byte that went over the bus, what OpenBus reads back
+    pub bus: u8,
+    // cycle count handed over by clock, passed on to whichever device the access goes to
+    cycles: u64,
+    devices: Vec<Box<dyn IoDevice>>,
+    inputs: [Option<usize>; 256],
+    outputs: [Option<usize>; 256],
+    trapped: Option<u8>,
+}
+
+impl PortMap {
+    pub fn new(unmapped: UnmappedPorts) -> Self {
+        Self {
+            unmapped,
+            bus: 0xFF,
+            cycles: 0,
+            devices: Vec::new(),
+            inputs: [None; 256],
+            outputs: [None; 256],
+            trapped: None,
+        }
+    }
+
+    /*
+     * add - Function
+     * Expects: N/A
+     * Does: Registers device with the map, it answers no ports until mapped
+     * Returns: The id to map it with
+     */
+    pub fn add(&mut self, device: Box<dyn IoDevice>) -> usize {
+        self.devices.push(device);
+        return self.devices.len() - 1;
+    }
+
+    /*
+     * map_input - Function
+     * Expects: id to come from add
+     * Does: Routes IN on every port in ports to the device, replacing whatever was there
+     * Returns: N/A
+     */
+    pub fn map_input(&mut self, ports: RangeInclusive<u8>, id: usize) {
+        assert!(id < self.devices.len(), "no device {} in the port map", id);
+        for port in ports {
+            self.inputs[port as usize] = Some(id);
+        }
+    }
+
+    /*
+     * map_output - Function
+     * Expects: id to come from add
+     * Does: Routes OUT on every port in ports to the device, replacing whatever was there
+     * Returns: N/A
+     */
+    pub fn map_output(&mut self, ports: RangeInclusive<u8>, id: usize) {
+        assert!(id < self.devices.len(), "no device {} in the port map", id);
+        for port in ports {
+            self.outputs[port as usize] = Some(id);
+        }
+    }
+
+    /*
+     * map - Function
+     * Expects: N/A
+     * Does: Adds device and routes both IN and OUT on ports to it
+     * Returns: The id of the device
+     */
+    pub fn map(&mut self, ports: RangeInclusive<u8>, device: Box<dyn IoDevice>) -> usize {
+        let id = self.add(device);
+        self.map_input(ports.clone(), id);
+        self.map_output(ports, id);
+        return id;
+    }
+
+    /*
+     * unmap - Function
+     * Expects: N/A
+     * Does: Drops both directions of ports back to the unmapped policy, the devices stay registered
+     * Returns: N/A
+     */
+    pub fn unmap(&mut self, ports: RangeInclusive<u8>) {
+        for port in ports {
+            self.inputs[port as usize] = None;
+            self.outputs[port as usize] = None;
+        }
+    }
+
+    pub fn device(&mut self, id: usize) -> &mut dyn IoDevice {
+        return self.devices[id].as_mut();
+    }
+}
+
+impl IoDevice for PortMap {
+    fn input(&mut self, port: u8) -> u8 {
+        self.bus = match self.inputs[port as usize] {
+            Some(id) => {
+                self.devices[id].clock(self.cycles);
+                let value = self.devices[id].input(port);
+                self.trapped = self.devices[id].trapped_port();
+                value
+            }
+            None => match self.unmapped {
+                UnmappedPorts::Float => 0xFF,
+                UnmappedPorts::OpenBus => self.bus,
+                UnmappedPorts::Trap => {
+                    self.trapped = Some(port);
+                    0xFF
+                }
+            },
+        };
+        return self.bus;
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.bus = value;
+        match self.outputs[port as usize] {
+            Some(id) => {
+                self.devices[id].clock(self.cycles);
+                self.devices[id].output(port, value);
+                self.trapped = self.devices[id].trapped_port();
+            }
+            None if self.unmapped == UnmappedPorts::Trap => self.trapped = Some(port),
+            None => {}
+        }
+    }
+
+    fn clock(&mut self, cycles: u64) {
+        self.cycles = cycles;
+    }
+
+    fn trapped_port(&mut self) -> Option<u8> {
+        return self.trapped.take();
+    }
+}
+
+/*
+ * ShiftRegister - The dedicated 16 bit shift register of the Midway 8080 boards as a device
+ * OUT data_port shifts a byte in from the top, OUT amount_port picks which 8 bits IN result_port
+ * returns (bits 15-8 for 0 down to bits 8-1 for 7). Other ports read 0 and ignore writes.
+ */
+pub struct ShiftRegister {
+    pub value: u16,
+    pub offset: u8,
+    pub amount_port: u8,
+    pub data_port: u8,
+    pub result_port: u8,
+}
+
+impl ShiftRegister {
+    pub fn new(amount_port: u8, data_port: u8, result_port: u8) -> Self {
+        Self {
+            value: 0,
+            offset: 0,
+            amount_port,
+            data_port,
+            result_port,
+        }
+    }
+}
+
+impl IoDevice for ShiftRegister {
+    fn input(&mut self, port: u8) -> u8 {
+        if port != self.result_port {
+            return 0;
+        }
+        return (self.value >> (8 - self.offset)) as u8;
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        if port == self.amount_port {
+            self.offset = value & 0x07;
+        } else if port == self.data_port {
+            self.value = ((value as u16) << 8) | (self.value >> 8);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use std::io;
+
+// Deepest nesting of arrays and objects parse accepts, the parser recurses once per level
+pub const MAX_DEPTH: usize = 128;
+
+/*
+ * Json - A parsed JSON value
+ * Just enough JSON for reading test suites and configuration, objects keep their keys in file order
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /*
+     * parse - Function
+     * Expects: N/A
+     * Does: Parses text as one JSON value, surrounding whitespace is allowed
+     * Returns: The Json or an InvalidData error saying where parsing stopped
+     */
+    pub fn parse(text: &str) -> io::Result<Json> {
+        let mut parser = Parser { bytes: text.as_bytes(), position: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position != parser.bytes.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        return Ok(value);
+    }
+
+    /*
+     * get - Function
+     * Expects: N/A
+     * Does: Looks key up if this is an object
+     * Returns: The value or None if there is no such key or this isn't an object
+     */
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => return members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => return None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => return Some(values),
+            _ => return None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => return Some(text),
+            _ => return None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => return Some(*number),
+            _ => return None,
+        }
+    }
+
+    /*
+     * as_u64 - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The number if this is a whole, non negative number otherwise None
+     */
+    pub fn as_u64(&self) -> Option<u64> {
+        let number = self.as_f64()?;
+        if number < 0.0 || number.fract() != 0.0 || number > u64::MAX as f64 {
+            return None;
+        }
+        return Some(number as u64);
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => return Some(*value),
+            _ => return None,
+        }
+    }
+}
+
+/*
+ * Parser - Recursive descent over the bytes of the text, position is the next byte to look at and
+ * depth how many arrays and objects it is inside
+ */
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> io::Error {
+        return io::Error::new(io::ErrorKind::InvalidData, format!("JSON {} at byte {}", message, self.position));
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.position) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> io::Result<()> {
+        self.skip_whitespace();
+        if self.bytes.get(self.position) != Some(&byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        self.position += 1;
+        return Ok(());
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> io::Result<Json> {
+        if !self.bytes[self.position..].starts_with(word.as_bytes()) {
+            return Err(self.error("unexpected character"));
+        }
+        self.position += word.len();
+        return Ok(value);
+    }
+
+    fn value(&mut self) -> io::Result<Json> {
+        self.skip_whitespace();
+        match self.bytes.get(self.position) {
+            Some(b'{' | b'[') => {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error("nested too deep"));
+                }
+                self.depth += 1;
+                let value = if self.bytes[self.position] == b'{' { self.object() } else { self.array() };
+                self.depth -= 1;
+                return value;
+            }
+            Some(b'"') => return Ok(Json::String(self.string()?)),
+            Some(b't') => return self.literal("true", Json::Bool(true)),
+            Some(b'f') => return self.literal("false", Json::Bool(false)),
+            Some(b'n') => return self.literal("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => return self.number(),
+            Some(_) => return Err(self.error("unexpected character")),
+            None => return Err(self.error("unexpected end")),
+        }
+    }
+
+    fn object(&mut self) -> io::Result<Json> {
+        let mut members = Vec::new();
+        self.position += 1;
+        self.skip_whitespace();
+        if self.bytes.get(self.position) == Some(&b'}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.bytes.get(self.position) != Some(&b'"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            self.expect(b':')?;
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.bytes.get(self.position) {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> io::Result<Json> {
+        let mut values = Vec::new();
+        self.position += 1;
+        self.skip_whitespace();
+        if self.bytes.get(self.position) == Some(&b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.bytes.get(self.position) {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        // skip the opening quote, runs without escapes are copied in one go
+        self.position += 1;
+        let mut text = String::new();
+        let mut start = self.position;
+        loop {
+            match self.bytes.get(self.position) {
+                Some(b'"') => {
+                    text.push_str(self.slice(start)?);
+                    self.position += 1;
+                    return Ok(text);
+                }
+                Some(b'\\') => {
+                    text.push_str(self.slice(start)?);
+                    self.position += 1;
+                    let escaped = match self.bytes.get(self.position) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{08}',
+                        Some(b'f') => '\u{0C}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    text.push(escaped);
+                    self.position += 1;
+                    start = self.position;
+                }
+                Some(_) => self.position += 1,
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn slice(&self, start: usize) -> io::Result<&str> {
+        return std::str::from_utf8(&self.bytes[start..self.position]).map_err(|_| self.error("invalid UTF-8"));
+    }
+
+    // \uXXXX with the position on the u, a high surrogate has to be followed by a \u low surrogate
+    // and the pair is joined up, leaves the position on the last hex digit
+    fn unicode_escape(&mut self) -> io::Result<char> {
+        let mut code = self.hex4()?;
+        if (0xD800..0xDC00).contains(&code) {
+            if !self.bytes[self.position + 1..].starts_with(b"\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.position += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+        }
+        return char::from_u32(code).ok_or_else(|| self.error("bad unicode escape"));
+    }
+
+    // exactly four hex digits, from_str_radix on its own would let a sign through
+    fn hex4(&mut self) -> io::Result<u32> {
+        let digits = self.bytes.get(self.position + 1..self.position + 5).ok_or_else(|| self.error("short unicode escape"))?;
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(self.error("bad unicode escape"));
+        }
+        let code = digits.iter().fold(0, |code, digit| (code << 4) | (*digit as char).to_digit(16).unwrap());
+        self.position += 4;
+        return Ok(code);
+    }
+
+    fn number(&mut self) -> io::Result<Json> {
+        let start = self.position;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.bytes.get(self.position) {
+            self.position += 1;
+        }
+        let text = self.slice(start)?;
+        match text.parse::<f64>() {
+            Ok(number) => return Ok(Json::Number(number)),
+            Err(_) => return Err(self.error("bad number")),
+        }
+    }
+}
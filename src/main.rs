@@ -1,17 +1,72 @@
-use crate::i8080::I8080Core;
+use i8080_emulator::io::{IoDevice, PortMap, UnmappedPorts};
+use i8080_emulator::{I8080Core, LoadRomResult, StepInstructionResult, Throttle, I8080_CLOCK_HZ};
+use std::io::Write;
 
-mod i8080;
+// Console output, writes go to stdout and there is nothing to read
+struct Console {
+    stdout: std::io::Stdout,
+}
+
+impl IoDevice for Console {
+    fn input(&mut self, _port: u8) -> u8 {
+        0
+    }
 
-fn handle_out(core: &mut I8080Core, port: u8, value: u8){
-    if port == 0 {
-        print!("{}", value as char);
+    fn output(&mut self, _port: u8, value: u8) {
+        let _ = self.stdout.write_all(&[value]);
+        let _ = self.stdout.flush();
     }
 }
 
 
+/*
+ * Usage: main <PROGRAM> [MHZ]
+ * Loads the program at 0x0100 and runs it paced to MHZ (2 MHz by default), port 0 is the console
+ */
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        println!("Usage: {} <PROGRAM> [MHZ]", args[0]);
+        return;
+    }
+
+    let clock_hz = match args.get(2) {
+        Some(mhz) => match mhz.parse::<f64>() {
+            Ok(mhz) if mhz > 0.0 => (mhz * 1_000_000.0) as u64,
+            _ => {
+                println!("Invalid clock speed: {}", mhz);
+                return;
+            }
+        },
+        None => I8080_CLOCK_HZ,
+    };
+
     let mut core = I8080Core::new();
-    core.on_out = Some(handle_out);
-    core.i8080_load_rom("/home/mockedarche/Documents/Programming/i8080 emulator/rust_rewrite/i8080_emulator/test roms/8080EXM.COM", 0x0100);
-    println!("this is main");
+    // port 0 is the console, every other port floats
+    let mut ports = PortMap::new(UnmappedPorts::Float);
+    let console = ports.add(Box::new(Console { stdout: std::io::stdout() }));
+    ports.map_output(0..=0, console);
+    core.io = Some(Box::new(ports));
+    if core.i8080_load_rom(&args[1], 0x0100) != LoadRomResult::Ok {
+        return;
+    }
+
+    let mut throttle = Throttle::new(clock_hz);
+    loop {
+        match core.i8080_run_throttled(&mut throttle) {
+            StepInstructionResult::Halt => {
+                println!("\nEncountered a HALT STOPPING");
+                break;
+            }
+            StepInstructionResult::Error => {
+                println!("\nStep failed and returned an error so exiting");
+                break;
+            }
+            StepInstructionResult::IllegalOpcode { pc, opcode } => {
+                println!("\nIllegal opcode 0x{:02X} at 0x{:04X} so exiting", opcode, pc);
+                break;
+            }
+            _ => {}
+        }
+    }
 }
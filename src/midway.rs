@@ -0,0 +1,575 @@
+use crate::invaders::video::{Framebuffer, Orientation, Overlay};
+use crate::invaders::{MID_FRAME_INTERRUPT, VBLANK_INTERRUPT};
+use crate::io::{IoDevice, PortMap, ShiftRegister, UnmappedPorts};
+use crate::{crc32, I8080Core, LoadRomResult, StepInstructionResult, I8080_CLOCK_HZ};
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+
+/*
+ * Midway 8080 board family
+ *
+ * Space Invaders and a run of Midway/Taito games (Lunar Rescue, Balloon Bomber, Galaxy Wars, Gun
+ * Fight, ...) share the same 8080 board with small differences in where the ROMs sit, which port
+ * bits the controls and DIP switches land on, where the shift register lives and how the monitor
+ * is mounted. A MachineDescription captures all of that so a new variant is a new description
+ * rather than new code, MidwayBoard runs any of them.
+ */
+
+const CYCLES_PER_FRAME: u64 = I8080_CLOCK_HZ / 60;
+const VIDEO_RAM_START: usize = 0x2400;
+const VIDEO_RAM_END: usize = 0x4000;
+
+/*
+ * Button - Every control a variant can wire to an input port
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Button {
+    Coin,
+    Player1Start,
+    Player2Start,
+    Player1Fire,
+    Player1Left,
+    Player1Right,
+    Player1Up,
+    Player1Down,
+    Player2Fire,
+    Player2Left,
+    Player2Right,
+    Player2Up,
+    Player2Down,
+    Tilt,
+}
+
+/*
+ * BitSource - What drives one bit of an input port
+ * Button reads 1 while pressed, ButtonActiveLow reads 0 while pressed and Dip reads the numbered
+ * bit of MidwayInputs::dip_switches
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BitSource {
+    Low,
+    High,
+    Button(Button),
+    ButtonActiveLow(Button),
+    Dip(u8),
+}
+
+/*
+ * InputPort - An IN port and its 8 bits, bit 0 first
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct InputPort {
+    pub port: u8,
+    pub bits: [BitSource; 8],
+}
+
+/*
+ * ShifterPorts - Where the dedicated shift register sits, OUT amount sets the shift, OUT data
+ * shifts in a byte and IN result reads it back
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ShifterPorts {
+    pub amount: u8,
+    pub data: u8,
+    pub result: u8,
+}
+
+/*
+ * RomFile - One ROM chip, crc32 is None when the expected checksum isn't known
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RomFile {
+    pub name: &'static str,
+    pub address: u16,
+    pub size: usize,
+    pub crc32: Option<u32>,
+}
+
+/*
+ * ScheduledInterrupt - An RST the video hardware raises at a fixed point of every frame
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ScheduledInterrupt {
+    pub cycle: u64,
+    pub opcode: u8,
+}
+
+/*
+ * MachineDescription - Everything that differs between the variants
+ * address_mask, rom_end and upper_rom feed the core's memory map, sound_ports are latched and
+ * recorded as LatchWrites, interrupts must be in cycle order within cycles_per_frame
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MachineDescription {
+    pub name: &'static str,
+    pub roms: &'static [RomFile],
+    pub address_mask: u16,
+    pub rom_end: u16,
+    pub upper_rom: Option<(u16, u16)>,
+    pub inputs: &'static [InputPort],
+    pub default_dip_switches: u8,
+    pub shifter: Option<ShifterPorts>,
+    pub sound_ports: &'static [u8],
+    pub cycles_per_frame: u64,
+    pub interrupts: &'static [ScheduledInterrupt],
+    pub orientation: Orientation,
+}
+
+// Both interrupts of the standard video timing, RST 1 at mid screen and RST 2 at VBLANK
+const STANDARD_INTERRUPTS: [ScheduledInterrupt; 2] = [
+    ScheduledInterrupt { cycle: CYCLES_PER_FRAME / 2, opcode: MID_FRAME_INTERRUPT },
+    ScheduledInterrupt { cycle: CYCLES_PER_FRAME, opcode: VBLANK_INTERRUPT },
+];
+
+// Input ports of the Space Invaders wiring, also used unchanged by several of the Taito games
+// DIP bits: 0-1 ships, 2 bonus life at 1000, 3 coin info off, 4 port 0 bit 0
+const INVADERS_INPUTS: [InputPort; 3] = [
+    InputPort {
+        port: 0,
+        bits: [
+            BitSource::Dip(4),
+            BitSource::High,
+            BitSource::High,
+            BitSource::High,
+            BitSource::Button(Button::Player1Fire),
+            BitSource::Button(Button::Player1Left),
+            BitSource::Button(Button::Player1Right),
+            BitSource::Low,
+        ],
+    },
+    InputPort {
+        port: 1,
+        bits: [
+            BitSource::Button(Button::Coin),
+            BitSource::Button(Button::Player2Start),
+            BitSource::Button(Button::Player1Start),
+            BitSource::High,
+            BitSource::Button(Button::Player1Fire),
+            BitSource::Button(Button::Player1Left),
+            BitSource::Button(Button::Player1Right),
+            BitSource::Low,
+        ],
+    },
+    InputPort {
+        port: 2,
+        bits: [
+            BitSource::Dip(0),
+            BitSource::Dip(1),
+            BitSource::Button(Button::Tilt),
+            BitSource::Dip(2),
+            BitSource::Button(Button::Player2Fire),
+            BitSource::Button(Button::Player2Left),
+            BitSource::Button(Button::Player2Right),
+            BitSource::Dip(3),
+        ],
+    },
+];
+
+const INVADERS_SHIFTER: ShifterPorts = ShifterPorts { amount: 2, data: 4, result: 3 };
+
+pub const SPACE_INVADERS: MachineDescription = MachineDescription {
+    name: "invaders",
+    roms: &[
+        RomFile { name: "invaders.h", address: 0x0000, size: 0x0800, crc32: Some(0x734F5AD8) },
+        RomFile { name: "invaders.g", address: 0x0800, size: 0x0800, crc32: Some(0x6BFACA4A) },
+        RomFile { name: "invaders.f", address: 0x1000, size: 0x0800, crc32: Some(0x0CCEAD96) },
+        RomFile { name: "invaders.e", address: 0x1800, size: 0x0800, crc32: Some(0x14E538B0) },
+    ],
+    address_mask: 0x3FFF,
+    rom_end: 0x2000,
+    upper_rom: None,
+    inputs: &INVADERS_INPUTS,
+    default_dip_switches: 0x00,
+    shifter: Some(INVADERS_SHIFTER),
+    sound_ports: &[3, 5],
+    cycles_per_frame: CYCLES_PER_FRAME,
+    interrupts: &STANDARD_INTERRUPTS,
+    orientation: Orientation::RotatedCounterClockwise,
+};
+
+// Lunar Rescue (Taito 1979) keeps the Space Invaders wiring but adds 4K of ROM at 0x4000, so the
+// address space mirrors every 32K rather than 16K and 0x6000-0x7FFF is RAM of its own
+pub const LUNAR_RESCUE: MachineDescription = MachineDescription {
+    name: "lrescue",
+    roms: &[
+        RomFile { name: "lrescue.1", address: 0x0000, size: 0x0800, crc32: Some(0x2BBC4778) },
+        RomFile { name: "lrescue.2", address: 0x0800, size: 0x0800, crc32: Some(0x49E79706) },
+        RomFile { name: "lrescue.3", address: 0x1000, size: 0x0800, crc32: Some(0x1AC969BE) },
+        RomFile { name: "lrescue.4", address: 0x1800, size: 0x0800, crc32: Some(0x782FEE3C) },
+        RomFile { name: "lrescue.5", address: 0x4000, size: 0x0800, crc32: Some(0x58FDE8BC) },
+        RomFile { name: "lrescue.6", address: 0x4800, size: 0x0800, crc32: Some(0xBFB0F65D) },
+    ],
+    address_mask: 0x7FFF,
+    rom_end: 0x2000,
+    upper_rom: Some((0x4000, 0x5000)),
+    inputs: &INVADERS_INPUTS,
+    default_dip_switches: 0x00,
+    shifter: Some(INVADERS_SHIFTER),
+    sound_ports: &[3, 5],
+    cycles_per_frame: CYCLES_PER_FRAME,
+    interrupts: &STANDARD_INTERRUPTS,
+    orientation: Orientation::RotatedCounterClockwise,
+};
+
+pub const MACHINES: [&MachineDescription; 2] = [&SPACE_INVADERS, &LUNAR_RESCUE];
+
+/*
+ * find_machine - Function
+ * Expects: N/A
+ * Does: Looks a description up by its short name
+ * Returns: The MachineDescription or None if there isn't one with that name
+ */
+pub fn find_machine(name: &str) -> Option<&'static MachineDescription> {
+    return MACHINES.iter().find(|machine| machine.name == name).copied();
+}
+
+/*
+ * LatchWrite - An OUT to one of the description's sound ports
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LatchWrite {
+    pub port: u8,
+    pub value: u8,
+    pub cycle: u64,
+}
+
+/*
+ * MidwayInputs - Every IN port of the description as one device, building each byte from the
+ * wiring, the buttons held and the DIP switches
+ */
+pub struct MidwayInputs {
+    pub description: &'static MachineDescription,
+    pub dip_switches: u8,
+    // bit n set while the Button with discriminant n is held
+    buttons: u32,
+}
+
+impl MidwayInputs {
+    pub fn new(description: &'static MachineDescription) -> Self {
+        Self {
+            description,
+            dip_switches: description.default_dip_switches,
+            buttons: 0,
+        }
+    }
+
+    /*
+     * set_button - Function
+     * Expects: N/A
+     * Does: Presses (true) or releases (false) button, buttons the variant doesn't wire do nothing
+     * Returns: N/A
+     */
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        if pressed {
+            self.buttons |= 1 << button as u32;
+        } else {
+            self.buttons &= !(1 << button as u32);
+        }
+    }
+
+    /*
+     * button_pressed - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: true if button is currently held
+     */
+    pub fn button_pressed(&self, button: Button) -> bool {
+        return self.buttons & (1 << button as u32) != 0;
+    }
+}
+
+impl IoDevice for MidwayInputs {
+    fn input(&mut self, port: u8) -> u8 {
+        let input = match self.description.inputs.iter().find(|input| input.port == port) {
+            Some(input) => input,
+            None => return 0,
+        };
+        let mut value = 0;
+        for (bit, source) in input.bits.iter().enumerate() {
+            let set = match source {
+                BitSource::Low => false,
+                BitSource::High => true,
+                BitSource::Button(button) => self.button_pressed(*button),
+                BitSource::ButtonActiveLow(button) => !self.button_pressed(*button),
+                BitSource::Dip(switch) => self.dip_switches & (1 << switch) != 0,
+            };
+            if set {
+                value |= 1 << bit;
+            }
+        }
+        return value;
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+}
+
+/*
+ * SoundLatches - Records every OUT to the sound ports stamped with the core's cycle count, for
+ * variants without a decoder of their own
+ */
+#[derive(Default)]
+pub struct SoundLatches {
+    writes: Vec<LatchWrite>,
+    // the core's cycle count as of the last access, see IoDevice::clock
+    cycles: u64,
+}
+
+impl SoundLatches {
+    /*
+     * take_writes - Function
+     * Expects: N/A
+     * Does: Empties the recorded writes
+     * Returns: Every write since the last call in order
+     */
+    pub fn take_writes(&mut self) -> Vec<LatchWrite> {
+        return std::mem::take(&mut self.writes);
+    }
+}
+
+impl IoDevice for SoundLatches {
+    fn input(&mut self, _port: u8) -> u8 {
+        return 0;
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.writes.push(LatchWrite {
+            port,
+            value,
+            cycle: self.cycles,
+        });
+    }
+
+    fn clock(&mut self, cycles: u64) {
+        self.cycles = cycles;
+    }
+}
+
+/*
+ * MidwayBoard - A core wired up from a MachineDescription
+ * The inputs, the shift register and the sound latches are devices on ports, a PortMap the core
+ * reaches through core.io. The board keeps a handle on the map so a variant can map more devices
+ * or replace one, Space Invaders puts its sound decoder on the latch ports that way.
+ */
+pub struct MidwayBoard {
+    pub core: I8080Core,
+    pub description: &'static MachineDescription,
+    pub ports: Rc<RefCell<PortMap>>,
+    pub inputs: Rc<RefCell<MidwayInputs>>,
+    // Built from the description's ShifterPorts when the board has one
+    pub shifter: Option<Rc<RefCell<ShiftRegister>>>,
+    pub latches: Rc<RefCell<SoundLatches>>,
+    pub frame_number: u64,
+    frame_start_cycles: u64,
+}
+
+impl MidwayBoard {
+    pub fn new(description: &'static MachineDescription) -> Self {
+        let mut core = I8080Core::new();
+        core.address_mask = description.address_mask;
+        core.rom_end = description.rom_end;
+        core.upper_rom = description.upper_rom;
+
+        let inputs = Rc::new(RefCell::new(MidwayInputs::new(description)));
+        let shifter = description
+            .shifter
+            .map(|ports| Rc::new(RefCell::new(ShiftRegister::new(ports.amount, ports.data, ports.result))));
+        let latches = Rc::new(RefCell::new(SoundLatches::default()));
+
+        // the watchdog isn't emulated, it and anything else the description doesn't wire reads 0xFF
+        // and drops writes
+        let ports = Rc::new(RefCell::new(PortMap::new(UnmappedPorts::Float)));
+        {
+            let mut map = ports.borrow_mut();
+            let id = map.add(Box::new(inputs.clone()));
+            for input in description.inputs {
+                map.map_input(input.port..=input.port, id);
+            }
+            if let (Some(shifter), Some(wiring)) = (shifter.as_ref(), description.shifter) {
+                let id = map.add(Box::new(shifter.clone()));
+                map.map_input(wiring.result..=wiring.result, id);
+                map.map_output(wiring.amount..=wiring.amount, id);
+                map.map_output(wiring.data..=wiring.data, id);
+            }
+            let id = map.add(Box::new(latches.clone()));
+            for port in description.sound_ports {
+                map.map_output(*port..=*port, id);
+            }
+        }
+        core.io = Some(Box::new(ports.clone()));
+
+        Self {
+            core,
+            description,
+            ports,
+            inputs,
+            shifter,
+            latches,
+            frame_number: 0,
+            frame_start_cycles: 0,
+        }
+    }
+
+    /*
+     * load_rom_bytes - Function
+     * Expects: data to be a ROM image that fits below the description's rom_end or inside its
+     * upper_rom when placed at address
+     * Does: Copies data into the ROM area (bypassing the write protect)
+     * Returns: LoadRomResult::Error if the image doesn't fit otherwise LoadRomResult::Ok
+     */
+    pub fn load_rom_bytes(&mut self, data: &[u8], address: u16) -> LoadRomResult {
+        let start = address as usize;
+        let end = start + data.len();
+        let fits_upper = match self.description.upper_rom {
+            Some((upper_start, upper_end)) => start >= upper_start as usize && end <= upper_end as usize,
+            None => false,
+        };
+        if end > self.description.rom_end as usize && !fits_upper {
+            return LoadRomResult::Error;
+        }
+        self.core.memory[start..end].copy_from_slice(data);
+        return LoadRomResult::Ok;
+    }
+
+    /*
+     * load_rom_set - Function
+     * Expects: directory to contain every ROM the description lists
+     * Does: Loads each ROM at its address checking its size and (when known) its CRC, a bad CRC is
+     * reported but the image is still used as plenty of working boards have patched ROMs
+     * Returns: The first LoadRomResult that wasn't Ok or LoadRomResult::Ok
+     */
+    pub fn load_rom_set(&mut self, directory: &str) -> LoadRomResult {
+        for rom in self.description.roms {
+            let path = format!("{}/{}", directory.trim_end_matches('/'), rom.name);
+            let data = match fs::read(&path) {
+                Ok(data) => data,
+                Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    println!("ROM file not found: {}", path);
+                    return LoadRomResult::NotFound;
+                }
+                Err(_) => {
+                    println!("Error loading ROM file: {}", path);
+                    return LoadRomResult::Error;
+                }
+            };
+            if data.len() != rom.size {
+                println!("ROM {} is {} bytes but should be {}", rom.name, data.len(), rom.size);
+                return LoadRomResult::Error;
+            }
+            if let Some(expected) = rom.crc32 {
+                let actual = crc32(&data);
+                if actual != expected {
+                    println!("ROM {} has CRC {:08X} but expected {:08X}", rom.name, actual, expected);
+                }
+            }
+            let start = rom.address as usize;
+            self.core.memory[start..start + data.len()].copy_from_slice(&data);
+        }
+        return LoadRomResult::Ok;
+    }
+
+    /*
+     * set_button - Function
+     * Expects: N/A
+     * Does: Presses (true) or releases (false) button, see MidwayInputs::set_button
+     * Returns: N/A
+     */
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        self.inputs.borrow_mut().set_button(button, pressed);
+    }
+
+    /*
+     * set_dip_switches - Function
+     * Expects: N/A
+     * Does: Sets the DIP switches, bit n is what BitSource::Dip(n) reads
+     * Returns: N/A
+     */
+    pub fn set_dip_switches(&mut self, dip_switches: u8) {
+        self.inputs.borrow_mut().dip_switches = dip_switches;
+    }
+
+    /*
+     * take_latch_writes - Function
+     * Expects: N/A
+     * Does: Empties the recorded sound latch writes
+     * Returns: Every write since the last call in order
+     */
+    pub fn take_latch_writes(&mut self) -> Vec<LatchWrite> {
+        return self.latches.borrow_mut().take_writes();
+    }
+
+    /*
+     * step - Function
+     * Expects: The ROMs to have been loaded
+     * Does: Executes one instruction on the core, IN and OUT reach the board's devices through core.io
+     * Returns: The StepInstructionResult from the core
+     */
+    pub fn step(&mut self) -> StepInstructionResult {
+        return self.core.i8080_step();
+    }
+
+    /*
+     * run_until - Function
+     * Expects: N/A
+     * Does: Steps the board until the core's cycle counter reaches target, a halted core idles
+     * until then as it would waiting for the next interrupt
+     * Returns: The failing StepInstructionResult if an instruction failed otherwise StepInstructionResult::Ok
+     */
+    fn run_until(&mut self, target: u64) -> StepInstructionResult {
+        while self.core.cycles < target {
+            let result = self.step();
+            if result.is_failure() {
+                return result;
+            }
+            if self.core.halted {
+                self.core.cycles = target;
+            }
+        }
+        return StepInstructionResult::Ok;
+    }
+
+    /*
+     * run_frame - Function
+     * Expects: The ROMs to have been loaded
+     * Does: Runs one frame raising the description's interrupts at their scheduled cycles, the
+     * schedule is kept from the frame start so overshooting a target doesn't drift the frames
+     * Returns: The failing StepInstructionResult if an instruction failed otherwise StepInstructionResult::Ok
+     */
+    pub fn run_frame(&mut self) -> StepInstructionResult {
+        for interrupt in self.description.interrupts {
+            let result = self.run_until(self.frame_start_cycles + interrupt.cycle);
+            if result.is_failure() {
+                return result;
+            }
+            self.core.i8080_interrupt(interrupt.opcode);
+        }
+
+        self.frame_start_cycles += self.description.cycles_per_frame;
+        let result = self.run_until(self.frame_start_cycles);
+        if result.is_failure() {
+            return result;
+        }
+        self.frame_number += 1;
+        return StepInstructionResult::Ok;
+    }
+
+    /*
+     * video_ram - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The 7K of video RAM, 32 bytes per 256 pixel line of the unrotated screen (LSB first)
+     */
+    pub fn video_ram(&self) -> &[u8] {
+        return &self.core.memory[VIDEO_RAM_START..VIDEO_RAM_END];
+    }
+
+    /*
+     * render - Function
+     * Expects: N/A
+     * Does: Renders video RAM in the description's orientation, see Framebuffer::render_oriented
+     * Returns: The Framebuffer
+     */
+    pub fn render(&self, overlay: Option<&Overlay>) -> Framebuffer {
+        return Framebuffer::render_oriented(self.video_ram(), overlay, self.description.orientation);
+    }
+}
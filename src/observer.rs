@@ -0,0 +1,63 @@
+use crate::{I8080Core, StepInstructionResult};
+
+/*
+ * Observer - Hooks the core calls around each instruction and on every bus cycle it performs
+ * Tracers, profilers, coverage, watchpoints and sanitizers implement the hooks they need and leave the
+ * rest as the empty defaults. Attach one with core.observer = Some(Box::new(observer)), with none
+ * attached every hook site is a single None check.
+ * Memory hooks see the reads and writes the CPU makes (opcode and operand fetches, data, stack),
+ * hosts poking memory through read_memory and write_memory are not reported. Port hooks see the IN
+ * and OUT that go to core.io.
+ */
+pub trait Observer {
+    fn before_instruction(&mut self, _core: &I8080Core) {}
+    fn after_instruction(&mut self, _core: &I8080Core, _result: &StepInstructionResult) {}
+    fn memory_read(&mut self, _address: u16, _value: u8) {}
+    fn memory_write(&mut self, _address: u16, _value: u8) {}
+    fn port_input(&mut self, _port: u8, _value: u8) {}
+    fn port_output(&mut self, _port: u8, _value: u8) {}
+}
+
+impl I8080Core {
+    /*
+     * bus_read - Function
+     * Expects: N/A
+     * Does: Reads the byte at address as a CPU memory cycle, telling the observer
+     * Returns: The byte at address
+     */
+    pub(crate) fn bus_read(&mut self, address: u16) -> u8 {
+        let value = self.read_memory(address);
+        if let Some(observer) = self.observer.as_mut() {
+            observer.memory_read(address, value);
+        }
+        return value;
+    }
+
+    /*
+     * bus_write - Function
+     * Expects: N/A
+     * Does: Writes value at address as a CPU memory cycle, telling the observer (even when the write
+     * lands in ROM and is dropped)
+     * Returns: N/A
+     */
+    pub(crate) fn bus_write(&mut self, address: u16, value: u8) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.memory_write(address, value);
+        }
+        self.write_memory(address, value);
+    }
+
+    /*
+     * observe - Function
+     * Expects: N/A
+     * Does: Calls hook with the observer and a view of the core, the observer is lifted out for the
+     * call so it can look at the whole core
+     * Returns: N/A
+     */
+    pub(crate) fn observe(&mut self, hook: impl FnOnce(&mut dyn Observer, &I8080Core)) {
+        if let Some(mut observer) = self.observer.take() {
+            hook(observer.as_mut(), self);
+            self.observer = Some(observer);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{CpuModel, I8080Core};
+
+/*
+ * Reg8 - The 8 bit registers by name, F is the flags packed into a byte (see flags_byte)
+ * For debuggers, save states and test fixtures that pick registers at runtime
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reg8 {
+    A,
+    F,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+}
+
+/*
+ * Reg16 - The register pairs and 16 bit registers, PSW is A in the high byte and the flags byte in the low
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reg16 {
+    BC,
+    DE,
+    HL,
+    SP,
+    PC,
+    PSW,
+}
+
+impl Reg8 {
+    pub fn get(self, core: &I8080Core) -> u8 {
+        match self {
+            Reg8::A => return core.a,
+            Reg8::F => return core.flags_byte(),
+            Reg8::B => return core.b,
+            Reg8::C => return core.c,
+            Reg8::D => return core.d,
+            Reg8::E => return core.e,
+            Reg8::H => return core.h,
+            Reg8::L => return core.l,
+        }
+    }
+
+    pub fn set(self, core: &mut I8080Core, value: u8) {
+        match self {
+            Reg8::A => core.a = value,
+            Reg8::F => core.set_flags_byte(value),
+            Reg8::B => core.b = value,
+            Reg8::C => core.c = value,
+            Reg8::D => core.d = value,
+            Reg8::E => core.e = value,
+            Reg8::H => core.h = value,
+            Reg8::L => core.l = value,
+        }
+    }
+}
+
+impl Reg16 {
+    pub fn get(self, core: &I8080Core) -> u16 {
+        match self {
+            Reg16::BC => return core.bc(),
+            Reg16::DE => return core.de(),
+            Reg16::HL => return core.hl(),
+            Reg16::SP => return core.stack_pointer,
+            Reg16::PC => return core.program_counter,
+            Reg16::PSW => return core.psw(),
+        }
+    }
+
+    pub fn set(self, core: &mut I8080Core, value: u16) {
+        match self {
+            Reg16::BC => core.set_bc(value),
+            Reg16::DE => core.set_de(value),
+            Reg16::HL => core.set_hl(value),
+            Reg16::SP => core.stack_pointer = value,
+            Reg16::PC => core.program_counter = value,
+            Reg16::PSW => core.set_psw(value),
+        }
+    }
+}
+
+impl I8080Core {
+    pub fn bc(&self) -> u16 {
+        return (self.b as u16) << 8 | self.c as u16;
+    }
+
+    pub fn set_bc(&mut self, value: u16) {
+        self.b = (value >> 8) as u8;
+        self.c = value as u8;
+    }
+
+    pub fn de(&self) -> u16 {
+        return (self.d as u16) << 8 | self.e as u16;
+    }
+
+    pub fn set_de(&mut self, value: u16) {
+        self.d = (value >> 8) as u8;
+        self.e = value as u8;
+    }
+
+    pub fn hl(&self) -> u16 {
+        return (self.h as u16) << 8 | self.l as u16;
+    }
+
+    pub fn set_hl(&mut self, value: u16) {
+        self.h = (value >> 8) as u8;
+        self.l = value as u8;
+    }
+
+    pub fn psw(&self) -> u16 {
+        return (self.a as u16) << 8 | self.flags_byte() as u16;
+    }
+
+    pub fn set_psw(&mut self, value: u16) {
+        self.a = (value >> 8) as u8;
+        self.set_flags_byte(value as u8);
+    }
+
+    /*
+     * flags_byte - Function
+     * Expects: N/A
+     * Does: Packs the flags the way PUSH PSW stores them, S Z 0 AC 0 P 1 CY from bit 7 down. In Z80
+     * mode this is the F register instead (N in bit 1, see z80_flags)
+     * Returns: The flags byte
+     */
+    pub fn flags_byte(&self) -> u8 {
+        if self.model == CpuModel::Z80 {
+            return self.z80_flags();
+        }
+        return (self.sign as u8) << 7
+            | (self.zero as u8) << 6
+            | (self.auxiliary_carry as u8) << 4
+            | (self.parity as u8) << 2
+            | 0x02
+            | self.carry as u8;
+    }
+
+    /*
+     * set_flags_byte - Function
+     * Expects: N/A
+     * Does: Unpacks a flags byte the way POP PSW does, bits 1, 3 and 5 are ignored. In Z80 mode it
+     * sets the F register instead (see set_z80_flags)
+     * Returns: N/A
+     */
+    pub fn set_flags_byte(&mut self, flags: u8) {
+        if self.model == CpuModel::Z80 {
+            self.set_z80_flags(flags);
+            return;
+        }
+        self.sign = flags & 0x80 != 0;
+        self.zero = flags & 0x40 != 0;
+        self.auxiliary_carry = flags & 0x10 != 0;
+        self.parity = flags & 0x04 != 0;
+        self.carry = flags & 0x01 != 0;
+    }
+}
@@ -0,0 +1,362 @@
+use crate::io::IoDevice;
+use crate::json::Json;
+use crate::{CpuModel, I8080Core};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+/*
+ * Single step tests
+ *
+ * Runner for the per opcode JSON suites in the SingleStepTests format, one file per opcode holding
+ * an array of cases:
+ *   { "name": "3c 0000",
+ *     "initial": { "pc": 4660, "sp": 0, "a": 1, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 0, "l": 0,
+ *                  "ram": [[4660, 60]] },
+ *     "final":   { ...the same fields after the instruction... },
+ *     "cycles":  [[4660, 60, "r"], ...one entry per clock...],
+ *     "ports":   [[16, 255, "r"]] }
+ * Every register and the flags byte (see I8080Core::flags_byte) in final are compared, so is each
+ * ram address listed there and the number of clock cycles. IN reads its byte from ports in order and
+ * every OUT must match the next "w" entry. "ports" and "cycles" may be left out.
+ */
+
+/*
+ * CpuState - The registers and memory of one side of a case
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuState {
+    pub pc: u16,
+    pub sp: u16,
+    pub a: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub f: u8,
+    pub h: u8,
+    pub l: u8,
+    pub ram: Vec<(u16, u8)>,
+}
+
+/*
+ * PortAccess - One IN or OUT a case expects, value is what IN reads or what OUT must write
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PortAccess {
+    pub port: u8,
+    pub value: u8,
+    pub write: bool,
+}
+
+/*
+ * SingleStepTest - One case, cycles is None when the file doesn't list them
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SingleStepTest {
+    pub name: String,
+    pub initial: CpuState,
+    pub expected: CpuState,
+    pub cycles: Option<u64>,
+    pub ports: Vec<PortAccess>,
+}
+
+/*
+ * OpcodeReport - How the cases of one file went, failures holds the first few mismatch descriptions
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpcodeReport {
+    pub name: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub failures: Vec<String>,
+}
+
+// How many failure descriptions a report keeps, a broken opcode fails every case the same way
+const REPORTED_FAILURES: usize = 3;
+
+impl OpcodeReport {
+    /*
+     * pass_rate - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The percentage of cases that passed, 100 for a file without cases
+     */
+    pub fn pass_rate(&self) -> f64 {
+        let total = self.passed + self.failed;
+        if total == 0 {
+            return 100.0;
+        }
+        return self.passed as f64 * 100.0 / total as f64;
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, message);
+}
+
+// A whole number field that has to fit in T
+fn field<T: TryFrom<u64>>(json: &Json, key: &str) -> io::Result<T> {
+    let value = json.get(key).and_then(Json::as_u64).ok_or_else(|| invalid(format!("missing or bad \"{}\"", key)))?;
+    return T::try_from(value).map_err(|_| invalid(format!("\"{}\" out of range", key)));
+}
+
+impl CpuState {
+    /*
+     * from_json - Function
+     * Expects: json to be an "initial" or "final" object
+     * Does: Reads the registers and the ram list out of it
+     * Returns: The CpuState or an InvalidData error naming the field that is wrong
+     */
+    pub fn from_json(json: &Json) -> io::Result<CpuState> {
+        let mut ram = Vec::new();
+        for entry in json.get("ram").and_then(Json::as_array).unwrap_or(&[]) {
+            match entry.as_array() {
+                Some([address, value]) => {
+                    let address = address.as_u64().filter(|address| *address <= 0xFFFF);
+                    let value = value.as_u64().filter(|value| *value <= 0xFF);
+                    match (address, value) {
+                        (Some(address), Some(value)) => ram.push((address as u16, value as u8)),
+                        _ => return Err(invalid("bad \"ram\" entry".to_string())),
+                    }
+                }
+                _ => return Err(invalid("bad \"ram\" entry".to_string())),
+            }
+        }
+        return Ok(CpuState {
+            pc: field(json, "pc")?,
+            sp: field(json, "sp")?,
+            a: field(json, "a")?,
+            b: field(json, "b")?,
+            c: field(json, "c")?,
+            d: field(json, "d")?,
+            e: field(json, "e")?,
+            f: field(json, "f")?,
+            h: field(json, "h")?,
+            l: field(json, "l")?,
+            ram,
+        });
+    }
+
+    /*
+     * apply - Function
+     * Expects: N/A
+     * Does: Loads the state into core
+     * Returns: N/A
+     */
+    pub fn apply(&self, core: &mut I8080Core) {
+        core.program_counter = self.pc;
+        core.stack_pointer = self.sp;
+        core.a = self.a;
+        core.b = self.b;
+        core.c = self.c;
+        core.d = self.d;
+        core.e = self.e;
+        core.h = self.h;
+        core.l = self.l;
+        core.set_flags_byte(self.f);
+        for (address, value) in self.ram.iter() {
+            core.memory[*address as usize] = *value;
+        }
+    }
+
+    /*
+     * differences - Function
+     * Expects: self to be the expected final state
+     * Does: Compares core against it register by register and at every ram address listed
+     * Returns: One "name expected 0x.. got 0x.." line per mismatch, empty when core matches
+     */
+    pub fn differences(&self, core: &I8080Core) -> Vec<String> {
+        let mut differences = Vec::new();
+        let registers = [
+            ("pc", self.pc, core.program_counter),
+            ("sp", self.sp, core.stack_pointer),
+            ("a", self.a as u16, core.a as u16),
+            ("b", self.b as u16, core.b as u16),
+            ("c", self.c as u16, core.c as u16),
+            ("d", self.d as u16, core.d as u16),
+            ("e", self.e as u16, core.e as u16),
+            ("f", self.f as u16, core.flags_byte() as u16),
+            ("h", self.h as u16, core.h as u16),
+            ("l", self.l as u16, core.l as u16),
+        ];
+        for (name, expected, actual) in registers {
+            if expected != actual {
+                differences.push(format!("{} expected 0x{:02X} got 0x{:02X}", name, expected, actual));
+            }
+        }
+        for (address, expected) in self.ram.iter() {
+            let actual = core.memory[*address as usize];
+            if *expected != actual {
+                differences.push(format!("ram[0x{:04X}] expected 0x{:02X} got 0x{:02X}", address, expected, actual));
+            }
+        }
+        return differences;
+    }
+}
+
+/*
+ * ScriptedPorts - Plays back the port accesses of a case, IN gets the next "r" value and OUT is
+ * checked against the next "w" entry, anything unexpected is written down
+ */
+struct ScriptedPorts {
+    reads: VecDeque<PortAccess>,
+    writes: VecDeque<PortAccess>,
+    mismatches: Rc<RefCell<Vec<String>>>,
+}
+
+impl IoDevice for ScriptedPorts {
+    fn input(&mut self, port: u8) -> u8 {
+        match self.reads.pop_front() {
+            Some(access) => {
+                if access.port != port {
+                    self.mismatches.borrow_mut().push(format!("IN port expected 0x{:02X} got 0x{:02X}", access.port, port));
+                }
+                return access.value;
+            }
+            None => {
+                self.mismatches.borrow_mut().push(format!("unexpected IN 0x{:02X}", port));
+                return 0xFF;
+            }
+        }
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        match self.writes.pop_front() {
+            Some(access) if access.port == port && access.value == value => {}
+            Some(access) => self.mismatches.borrow_mut().push(format!(
+                "OUT expected 0x{:02X} to port 0x{:02X} got 0x{:02X} to port 0x{:02X}",
+                access.value, access.port, value, port
+            )),
+            None => self.mismatches.borrow_mut().push(format!("unexpected OUT 0x{:02X} to port 0x{:02X}", value, port)),
+        }
+    }
+}
+
+impl SingleStepTest {
+    /*
+     * from_json - Function
+     * Expects: json to be one case object
+     * Does: Reads the name, both states, the cycle count and the port accesses
+     * Returns: The SingleStepTest or an InvalidData error
+     */
+    pub fn from_json(json: &Json) -> io::Result<SingleStepTest> {
+        let name = json.get("name").and_then(Json::as_str).unwrap_or("").to_string();
+        let initial = json.get("initial").ok_or_else(|| invalid(format!("{}: missing \"initial\"", name)))?;
+        let expected = json.get("final").ok_or_else(|| invalid(format!("{}: missing \"final\"", name)))?;
+        let cycles = json.get("cycles").and_then(Json::as_array).map(|cycles| cycles.len() as u64);
+
+        let mut ports = Vec::new();
+        for entry in json.get("ports").and_then(Json::as_array).unwrap_or(&[]) {
+            let access = match entry.as_array() {
+                Some([port, value, direction]) => match (port.as_u64(), value.as_u64(), direction.as_str()) {
+                    (Some(port @ 0..=0xFF), Some(value @ 0..=0xFF), Some("r" | "w")) => PortAccess {
+                        port: port as u8,
+                        value: value as u8,
+                        write: direction.as_str() == Some("w"),
+                    },
+                    _ => return Err(invalid(format!("{}: bad \"ports\" entry", name))),
+                },
+                _ => return Err(invalid(format!("{}: bad \"ports\" entry", name))),
+            };
+            ports.push(access);
+        }
+
+        let with_name = |error: io::Error| invalid(format!("{}: {}", name, error));
+        return Ok(SingleStepTest {
+            initial: CpuState::from_json(initial).map_err(with_name)?,
+            expected: CpuState::from_json(expected).map_err(with_name)?,
+            name,
+            cycles,
+            ports,
+        });
+    }
+
+    /*
+     * run - Function
+     * Expects: core to be reused between cases, only the ram the case lists is set up
+     * Does: Loads the initial state, steps one instruction and compares against the final state
+     * Returns: The mismatches, empty when the case passes
+     */
+    pub fn run(&self, core: &mut I8080Core) -> Vec<String> {
+        let mismatches = Rc::new(RefCell::new(Vec::new()));
+        core.io = Some(Box::new(ScriptedPorts {
+            reads: self.ports.iter().filter(|access| !access.write).copied().collect(),
+            writes: self.ports.iter().filter(|access| access.write).copied().collect(),
+            mismatches: mismatches.clone(),
+        }));
+        core.halted = false;
+        core.interrupts_enabled = false;
+        core.interrupt_delay = false;
+        self.initial.apply(core);
+
+        let before = core.cycles;
+        let result = core.i8080_step();
+        let mut differences = self.expected.differences(core);
+        if result.is_failure() {
+            differences.push("step failed".to_string());
+        }
+        if let Some(cycles) = self.cycles {
+            let taken = core.cycles.wrapping_sub(before);
+            if taken != cycles {
+                differences.push(format!("cycles expected {} got {}", cycles, taken));
+            }
+        }
+        core.io = None;
+        differences.extend(mismatches.borrow_mut().drain(..));
+        return differences;
+    }
+}
+
+/*
+ * parse_tests - Function
+ * Expects: text to be the contents of one suite file
+ * Does: Parses the array of cases
+ * Returns: The cases or an InvalidData error
+ */
+pub fn parse_tests(text: &str) -> io::Result<Vec<SingleStepTest>> {
+    let json = Json::parse(text)?;
+    let cases = json.as_array().ok_or_else(|| invalid("expected an array of cases".to_string()))?;
+    return cases.iter().map(SingleStepTest::from_json).collect();
+}
+
+/*
+ * run_tests - Function
+ * Expects: N/A
+ * Does: Runs every case on one core of model
+ * Returns: The OpcodeReport called name
+ */
+pub fn run_tests(name: &str, tests: &[SingleStepTest], model: CpuModel) -> OpcodeReport {
+    let mut core = I8080Core::new();
+    core.model = model;
+    let mut report = OpcodeReport { name: name.to_string(), passed: 0, failed: 0, failures: Vec::new() };
+
+    for test in tests {
+        let differences = test.run(&mut core);
+        if differences.is_empty() {
+            report.passed += 1;
+            continue;
+        }
+        report.failed += 1;
+        if report.failures.len() < REPORTED_FAILURES {
+            report.failures.push(format!("{}: {}", test.name, differences.join(", ")));
+        }
+    }
+    return report;
+}
+
+/*
+ * run_file - Function
+ * Expects: path to be one suite file
+ * Does: Loads and runs it, the report is named after the file
+ * Returns: The OpcodeReport or the error reading or parsing the file
+ */
+pub fn run_file(path: &str, model: CpuModel) -> io::Result<OpcodeReport> {
+    let text = fs::read_to_string(path)?;
+    let tests = parse_tests(&text)?;
+    let name = Path::new(path).file_stem().and_then(|stem| stem.to_str()).unwrap_or(path);
+    return Ok(run_tests(name, &tests, model));
+}
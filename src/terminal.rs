@@ -0,0 +1,395 @@
+use crate::altair::SerialBackend;
+
+/*
+ * Terminal emulation for the console
+ *
+ * CP/M software was installed for whatever terminal sat on the console and the two it most often
+ * knows are the Lear Siegler ADM-3A and the DEC VT52. Output bytes go through a TerminalDecoder for
+ * the chosen type which turns them into TerminalActions, those are then either replayed on the host
+ * terminal as ANSI (TerminalBackend) or applied to an in memory Screen.
+ *
+ * ADM-3A                                     VT52
+ *   ^H left  ^L right  ^K up  ^J down          ESC A up  ESC B down  ESC C right  ESC D left
+ *   ^^ home  ^Z clear screen                   ESC H home  ESC I reverse line feed
+ *   ESC = row+32 col+32  cursor address        ESC Y row+32 col+32  cursor address
+ *   ESC T clear to end of line (ADM-31)        ESC K clear to end of line
+ *   ESC Y clear to end of screen (ADM-31)      ESC J clear to end of screen
+ * The ADM-3A wraps at the end of a line, the VT52 sits on the last column.
+ */
+
+pub const SCREEN_ROWS: usize = 24;
+pub const SCREEN_COLUMNS: usize = 80;
+
+const ESC: u8 = 0x1B;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TerminalType {
+    Adm3a,
+    Vt52,
+}
+
+impl TerminalType {
+    /*
+     * from_name - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: The terminal called name (adm3a or vt52, any case) or None if it isn't one
+     */
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "adm3a" | "adm-3a" => Some(TerminalType::Adm3a),
+            "vt52" => Some(TerminalType::Vt52),
+            _ => None,
+        }
+    }
+
+    pub fn auto_wrap(&self) -> bool {
+        return *self == TerminalType::Adm3a;
+    }
+}
+
+/*
+ * TerminalAction - What a byte or escape sequence asks the terminal to do, rows and columns count
+ * from 0
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TerminalAction {
+    Print(u8),
+    CarriageReturn,
+    LineFeed,
+    ReverseLineFeed,
+    Backspace,
+    Tab,
+    Bell,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    Home,
+    MoveTo { row: u8, column: u8 },
+    ClearScreen,
+    ClearToEndOfLine,
+    ClearToEndOfScreen,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DecodeState {
+    Ground,
+    Escape,
+    CursorRow,
+    CursorColumn(u8),
+}
+
+/*
+ * TerminalDecoder - Turns the bytes a program writes to the console into TerminalActions
+ */
+pub struct TerminalDecoder {
+    pub kind: TerminalType,
+    state: DecodeState,
+}
+
+impl TerminalDecoder {
+    pub fn new(kind: TerminalType) -> Self {
+        Self {
+            kind,
+            state: DecodeState::Ground,
+        }
+    }
+
+    /*
+     * decode - Function
+     * Expects: N/A
+     * Does: Feeds one byte (bit 7 is ignored) through the escape sequence state machine
+     * Returns: The action the byte completes or None if it's part of a sequence, a padding or an
+     * unknown control character
+     */
+    pub fn decode(&mut self, byte: u8) -> Option<TerminalAction> {
+        let byte = byte & 0x7F;
+        match self.state {
+            DecodeState::Ground => return self.decode_ground(byte),
+            DecodeState::Escape => {
+                self.state = DecodeState::Ground;
+                return self.decode_escape(byte);
+            }
+            DecodeState::CursorRow => {
+                self.state = DecodeState::CursorColumn(byte.wrapping_sub(32));
+                return None;
+            }
+            DecodeState::CursorColumn(row) => {
+                self.state = DecodeState::Ground;
+                return Some(TerminalAction::MoveTo {
+                    row,
+                    column: byte.wrapping_sub(32),
+                });
+            }
+        }
+    }
+
+    fn decode_ground(&mut self, byte: u8) -> Option<TerminalAction> {
+        match (byte, self.kind) {
+            (ESC, _) => {
+                self.state = DecodeState::Escape;
+                None
+            }
+            (0x07, _) => Some(TerminalAction::Bell),
+            (0x08, _) => Some(TerminalAction::Backspace),
+            (0x09, _) => Some(TerminalAction::Tab),
+            (0x0A, _) => Some(TerminalAction::LineFeed),
+            (0x0D, _) => Some(TerminalAction::CarriageReturn),
+            (0x0B, TerminalType::Adm3a) => Some(TerminalAction::CursorUp),
+            (0x0C, TerminalType::Adm3a) => Some(TerminalAction::CursorRight),
+            (0x1A, TerminalType::Adm3a) => Some(TerminalAction::ClearScreen),
+            (0x1E, TerminalType::Adm3a) => Some(TerminalAction::Home),
+            (0x20..=0x7E, _) => Some(TerminalAction::Print(byte)),
+            _ => None,
+        }
+    }
+
+    fn decode_escape(&mut self, byte: u8) -> Option<TerminalAction> {
+        match (byte, self.kind) {
+            (b'=', TerminalType::Adm3a) | (b'Y', TerminalType::Vt52) => {
+                self.state = DecodeState::CursorRow;
+                None
+            }
+            (b'T', TerminalType::Adm3a) | (b't', TerminalType::Adm3a) => Some(TerminalAction::ClearToEndOfLine),
+            (b'Y', TerminalType::Adm3a) | (b'y', TerminalType::Adm3a) => Some(TerminalAction::ClearToEndOfScreen),
+            (b'A', TerminalType::Vt52) => Some(TerminalAction::CursorUp),
+            (b'B', TerminalType::Vt52) => Some(TerminalAction::CursorDown),
+            (b'C', TerminalType::Vt52) => Some(TerminalAction::CursorRight),
+            (b'D', TerminalType::Vt52) => Some(TerminalAction::CursorLeft),
+            (b'H', TerminalType::Vt52) => Some(TerminalAction::Home),
+            (b'I', TerminalType::Vt52) => Some(TerminalAction::ReverseLineFeed),
+            (b'J', TerminalType::Vt52) => Some(TerminalAction::ClearToEndOfScreen),
+            (b'K', TerminalType::Vt52) => Some(TerminalAction::ClearToEndOfLine),
+            // graphics, keypad mode and identify have nothing to show
+            _ => None,
+        }
+    }
+}
+
+/*
+ * write_ansi - Function
+ * Expects: N/A
+ * Does: Appends the ANSI (VT100) equivalent of action to output, line feeds use index (ESC D) so the
+ * column is kept whatever the host tty does with newlines
+ * Returns: N/A
+ */
+pub fn write_ansi(action: TerminalAction, output: &mut Vec<u8>) {
+    match action {
+        TerminalAction::Print(byte) => output.push(byte),
+        TerminalAction::CarriageReturn => output.push(b'\r'),
+        TerminalAction::LineFeed => output.extend_from_slice(b"\x1bD"),
+        TerminalAction::ReverseLineFeed => output.extend_from_slice(b"\x1bM"),
+        TerminalAction::Backspace | TerminalAction::CursorLeft => output.extend_from_slice(b"\x1b[D"),
+        TerminalAction::Tab => output.push(b'\t'),
+        TerminalAction::Bell => output.push(0x07),
+        TerminalAction::CursorUp => output.extend_from_slice(b"\x1b[A"),
+        TerminalAction::CursorDown => output.extend_from_slice(b"\x1b[B"),
+        TerminalAction::CursorRight => output.extend_from_slice(b"\x1b[C"),
+        TerminalAction::Home => output.extend_from_slice(b"\x1b[H"),
+        TerminalAction::MoveTo { row, column } => {
+            output.extend_from_slice(format!("\x1b[{};{}H", row as usize + 1, column as usize + 1).as_bytes());
+        }
+        TerminalAction::ClearScreen => output.extend_from_slice(b"\x1b[H\x1b[2J"),
+        TerminalAction::ClearToEndOfLine => output.extend_from_slice(b"\x1b[K"),
+        TerminalAction::ClearToEndOfScreen => output.extend_from_slice(b"\x1b[J"),
+    }
+}
+
+/*
+ * Screen - An in memory character screen that TerminalActions are applied to, for tests and for
+ * anything that wants to look at what a program drew
+ */
+pub struct Screen {
+    pub rows: usize,
+    pub columns: usize,
+    pub cursor_row: usize,
+    pub cursor_column: usize,
+    pub auto_wrap: bool,
+    cells: Vec<u8>,
+}
+
+impl Screen {
+    pub fn new(rows: usize, columns: usize, auto_wrap: bool) -> Self {
+        Self {
+            rows,
+            columns,
+            cursor_row: 0,
+            cursor_column: 0,
+            auto_wrap,
+            cells: vec![b' '; rows * columns],
+        }
+    }
+
+    pub fn cell(&self, row: usize, column: usize) -> u8 {
+        return self.cells[row * self.columns + column];
+    }
+
+    /*
+     * line - Function
+     * Expects: row to be on the screen
+     * Does: N/A
+     * Returns: The text of row without trailing spaces
+     */
+    pub fn line(&self, row: usize) -> String {
+        let start = row * self.columns;
+        return String::from_utf8_lossy(&self.cells[start..start + self.columns]).trim_end().to_string();
+    }
+
+    /*
+     * text - Function
+     * Expects: N/A
+     * Does: N/A
+     * Returns: Every line joined with newlines, trailing blank lines dropped
+     */
+    pub fn text(&self) -> String {
+        let lines: Vec<String> = (0..self.rows).map(|row| self.line(row)).collect();
+        return lines.join("\n").trim_end().to_string();
+    }
+
+    fn clear(&mut self, from: usize, to: usize) {
+        self.cells[from..to].fill(b' ');
+    }
+
+    fn scroll_up(&mut self) {
+        self.cells.copy_within(self.columns.., 0);
+        let last = (self.rows - 1) * self.columns;
+        self.clear(last, self.cells.len());
+    }
+
+    fn scroll_down(&mut self) {
+        let length = self.cells.len() - self.columns;
+        self.cells.copy_within(..length, self.columns);
+        self.clear(0, self.columns);
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor_row + 1 == self.rows {
+            self.scroll_up();
+        } else {
+            self.cursor_row += 1;
+        }
+    }
+
+    /*
+     * apply - Function
+     * Expects: N/A
+     * Does: Carries out action, the cursor is kept on the screen and a line feed on the bottom line
+     * scrolls
+     * Returns: N/A
+     */
+    pub fn apply(&mut self, action: TerminalAction) {
+        match action {
+            TerminalAction::Print(byte) => {
+                if self.cursor_column == self.columns {
+                    // only reachable with auto wrap, the wrap happens when the next character comes
+                    self.cursor_column = 0;
+                    self.line_feed();
+                }
+                self.cells[self.cursor_row * self.columns + self.cursor_column] = byte;
+                if self.cursor_column + 1 < self.columns || self.auto_wrap {
+                    self.cursor_column += 1;
+                }
+            }
+            TerminalAction::CarriageReturn => self.cursor_column = 0,
+            TerminalAction::LineFeed => self.line_feed(),
+            TerminalAction::ReverseLineFeed => {
+                if self.cursor_row == 0 {
+                    self.scroll_down();
+                } else {
+                    self.cursor_row -= 1;
+                }
+            }
+            TerminalAction::Backspace | TerminalAction::CursorLeft => {
+                self.cursor_column = self.cursor_column.min(self.columns - 1).saturating_sub(1);
+            }
+            TerminalAction::Tab => self.cursor_column = ((self.cursor_column / 8 + 1) * 8).min(self.columns - 1),
+            TerminalAction::Bell => {}
+            TerminalAction::CursorUp => self.cursor_row = self.cursor_row.saturating_sub(1),
+            TerminalAction::CursorDown => self.cursor_row = (self.cursor_row + 1).min(self.rows - 1),
+            TerminalAction::CursorRight => self.cursor_column = (self.cursor_column + 1).min(self.columns - 1),
+            TerminalAction::Home => {
+                self.cursor_row = 0;
+                self.cursor_column = 0;
+            }
+            TerminalAction::MoveTo { row, column } => {
+                self.cursor_row = (row as usize).min(self.rows - 1);
+                self.cursor_column = (column as usize).min(self.columns - 1);
+            }
+            TerminalAction::ClearScreen => {
+                self.clear(0, self.cells.len());
+                self.cursor_row = 0;
+                self.cursor_column = 0;
+            }
+            TerminalAction::ClearToEndOfLine => {
+                let start = self.cursor_row * self.columns + self.cursor_column.min(self.columns);
+                self.clear(start, (self.cursor_row + 1) * self.columns);
+            }
+            TerminalAction::ClearToEndOfScreen => {
+                let start = self.cursor_row * self.columns + self.cursor_column.min(self.columns);
+                self.clear(start, self.cells.len());
+            }
+        }
+    }
+}
+
+/*
+ * Terminal - A decoder and a screen together, write the console output to it and read the screen
+ */
+pub struct Terminal {
+    pub decoder: TerminalDecoder,
+    pub screen: Screen,
+}
+
+impl Terminal {
+    pub fn new(kind: TerminalType) -> Self {
+        Self {
+            decoder: TerminalDecoder::new(kind),
+            screen: Screen::new(SCREEN_ROWS, SCREEN_COLUMNS, kind.auto_wrap()),
+        }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if let Some(action) = self.decoder.decode(*byte) {
+                self.screen.apply(action);
+            }
+        }
+    }
+}
+
+/*
+ * TerminalBackend - Sits between a console and the host, output is translated from the terminal's
+ * codes to ANSI before going to inner and input passes straight through
+ */
+pub struct TerminalBackend {
+    pub decoder: TerminalDecoder,
+    pub inner: Box<dyn SerialBackend>,
+    ansi: Vec<u8>,
+}
+
+impl TerminalBackend {
+    pub fn new(kind: TerminalType, inner: Box<dyn SerialBackend>) -> Self {
+        Self {
+            decoder: TerminalDecoder::new(kind),
+            inner,
+            ansi: Vec::new(),
+        }
+    }
+}
+
+impl SerialBackend for TerminalBackend {
+    fn receive(&mut self) -> Option<u8> {
+        return self.inner.receive();
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        if let Some(action) = self.decoder.decode(byte) {
+            self.ansi.clear();
+            write_ansi(action, &mut self.ansi);
+            for byte in &self.ansi {
+                self.inner.transmit(*byte);
+            }
+        }
+    }
+}
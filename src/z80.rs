@@ -0,0 +1,1022 @@
+use crate::{CpuModel, I8080Core, StepInstructionResult};
+
+/*
+ * Z80_CYCLE_TABLE - Base number of clock cycles (T states) each unprefixed opcode takes on a Z80
+ * Conditional RETs, CALLs and relative jumps list their not taken cost, taking them costs 6, 7 and 5
+ * more cycles. DD/FD are the 4 cycles of the prefix, the instruction it modifies adds its own entry
+ * (and 8 more when (HL) becomes (IX+d)). CB and ED are 0 as their own decoders count the whole
+ * instruction.
+ */
+pub const Z80_CYCLE_TABLE: [u8; 256] = [
+    //0  1   2   3   4   5   6   7   8   9   A   B   C   D   E   F
+    4,  10, 7,  6,  4,  4,  7,  4,  4,  11, 7,  6,  4,  4,  7,  4,  // 0x00
+    8,  10, 7,  6,  4,  4,  7,  4,  12, 11, 7,  6,  4,  4,  7,  4,  // 0x10
+    7,  10, 16, 6,  4,  4,  7,  4,  7,  11, 16, 6,  4,  4,  7,  4,  // 0x20
+    7,  10, 13, 6,  11, 11, 10, 4,  7,  11, 13, 6,  4,  4,  7,  4,  // 0x30
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x40
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x50
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x60
+    7,  7,  7,  7,  7,  7,  4,  7,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x70
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x80
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0x90
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0xA0
+    4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,  // 0xB0
+    5,  10, 10, 10, 10, 11, 7,  11, 5,  10, 10, 0,  10, 17, 7,  11, // 0xC0
+    5,  10, 10, 11, 10, 11, 7,  11, 5,  4,  10, 11, 10, 4,  7,  11, // 0xD0
+    5,  10, 10, 19, 10, 11, 7,  11, 5,  4,  10, 4,  10, 0,  7,  11, // 0xE0
+    5,  10, 10, 4,  10, 11, 7,  11, 5,  6,  10, 4,  10, 4,  7,  11, // 0xF0
+];
+
+pub const NMI_VECTOR: u16 = 0x0066;
+
+// Interrupt mode IM n sets, indexed by bits 3-5 of the ED opcode
+const INTERRUPT_MODES: [u8; 8] = [0, 0, 1, 2, 0, 0, 1, 2];
+
+// Which register an instruction's HL (and H, L, (HL)) refers to, a DD or FD prefix swaps in IX or IY
+#[derive(Clone, Copy, PartialEq)]
+enum Index {
+    Hl,
+    Ix,
+    Iy,
+}
+
+/*
+ * Z80Registers - The state a Z80 has on top of the 8080 registers the core already holds
+ * The alternate set is kept as pairs (AF' with A in the high byte) and swapped in by EX AF,AF' and
+ * EXX. subtract is the N flag, the core's auxiliary_carry and parity double as H and P/V.
+ * interrupts_enabled on the core is IFF1.
+ */
+pub struct Z80Registers {
+    pub ix: u16,
+    pub iy: u16,
+    pub i: u8,
+    pub r: u8,
+
+    pub af_alternate: u16,
+    pub bc_alternate: u16,
+    pub de_alternate: u16,
+    pub hl_alternate: u16,
+
+    pub subtract: bool,
+    pub iff2: bool,
+    pub interrupt_mode: u8,
+}
+
+impl Default for Z80Registers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Z80Registers {
+    /*
+     * new - Function
+     * Expects: N/A
+     * Does: Creates the extra registers in their reset state (IM 0, IFF2 clear)
+     * Returns: The Z80Registers
+     */
+    pub fn new() -> Self {
+        Self {
+            ix: 0,
+            iy: 0,
+            i: 0,
+            r: 0,
+            af_alternate: 0,
+            bc_alternate: 0,
+            de_alternate: 0,
+            hl_alternate: 0,
+            subtract: false,
+            iff2: false,
+            interrupt_mode: 0,
+        }
+    }
+
+    /*
+     * reset - Function
+     * Expects: N/A
+     * Does: What /RESET does to the extra registers, I and R cleared, both IFFs cleared and IM 0.
+     * IX, IY and the alternate registers keep whatever they held
+     * Returns: N/A
+     */
+    pub fn reset(&mut self) {
+        self.i = 0;
+        self.r = 0;
+        self.iff2 = false;
+        self.interrupt_mode = 0;
+    }
+}
+
+impl I8080Core {
+    /*
+     * z80_flags - Function
+     * Expects: N/A
+     * Does: Packs the flags the Z80 way, S Z - H - P/V N C (the undocumented bits 3 and 5 read as 0)
+     * Returns: The F register
+     */
+    pub fn z80_flags(&self) -> u8 {
+        return (self.sign as u8) << 7
+            | (self.zero as u8) << 6
+            | (self.auxiliary_carry as u8) << 4
+            | (self.parity as u8) << 2
+            | (self.z80.subtract as u8) << 1
+            | self.carry as u8;
+    }
+
+    /*
+     * set_z80_flags - Function
+     * Expects: N/A
+     * Does: Unpacks an F register value into the flags
+     * Returns: N/A
+     */
+    pub fn set_z80_flags(&mut self, flags: u8) {
+        self.sign = flags & 0x80 != 0;
+        self.zero = flags & 0x40 != 0;
+        self.auxiliary_carry = flags & 0x10 != 0;
+        self.parity = flags & 0x04 != 0;
+        self.z80.subtract = flags & 0x02 != 0;
+        self.carry = flags & 0x01 != 0;
+    }
+
+    /*
+     * z80_interrupt - Function
+     * Expects: data to be what the interrupting device puts on the data bus
+     * Does: If IFF1 is set it clears both IFFs, leaves HALT and responds the way the interrupt mode
+     * says: IM 0 performs data as an RST, IM 1 restarts at 0x38 and IM 2 jumps through the vector
+     * table entry at I << 8 | data
+     * Returns: true if the interrupt was accepted otherwise false (disabled, EI delay or not an RST in IM 0)
+     */
+    pub fn z80_interrupt(&mut self, data: u8) -> bool {
+        if !self.interrupts_enabled || self.interrupt_delay {
+            return false;
+        }
+        if self.z80.interrupt_mode == 0 && data & 0xC7 != 0xC7 {
+            return false;
+        }
+        self.interrupts_enabled = false;
+        self.z80.iff2 = false;
+        self.halted = false;
+        self.z80_refresh();
+
+        let return_address = self.program_counter;
+        self.z80_push(return_address);
+        match self.z80.interrupt_mode {
+            0 => {
+                self.program_counter = (data & 0x38) as u16;
+                self.cycles = self.cycles.wrapping_add(13);
+            }
+            1 => {
+                self.program_counter = 0x0038;
+                self.cycles = self.cycles.wrapping_add(13);
+            }
+            _ => {
+                let entry = (self.z80.i as u16) << 8 | data as u16;
+                self.program_counter = self.z80_read_word(entry);
+                self.cycles = self.cycles.wrapping_add(19);
+            }
+        }
+        return true;
+    }
+
+    /*
+     * z80_nmi - Function
+     * Expects: model to be CpuModel::Z80
+     * Does: Takes a non maskable interrupt, IFF1 is cleared (IFF2 keeps its state for RETN), HALT is
+     * left and the PC is pushed before restarting at 0x66
+     * Returns: N/A
+     */
+    pub fn z80_nmi(&mut self) {
+        self.interrupts_enabled = false;
+        self.halted = false;
+        self.z80_refresh();
+        let return_address = self.program_counter;
+        self.z80_push(return_address);
+        self.program_counter = NMI_VECTOR;
+        self.cycles = self.cycles.wrapping_add(11);
+    }
+
+    /*
+     * z80_execute - Function
+     * Expects: model to be CpuModel::Z80
+     * Does: Fetches and performs one instruction (including any prefixes) adding the cycles it took
+     * Returns: A StepInstructionResult indicating how things went
+     */
+    pub(crate) fn z80_execute(&mut self) -> StepInstructionResult {
+        debug_assert!(self.model == CpuModel::Z80);
+        let opcode = self.z80_fetch_opcode();
+        return self.z80_execute_opcode(opcode, Index::Hl);
+    }
+
+    // R counts opcode fetches in its low 7 bits, bit 7 is only ever changed by LD R,A
+    fn z80_refresh(&mut self) {
+        self.z80.r = (self.z80.r & 0x80) | (self.z80.r.wrapping_add(1) & 0x7F);
+    }
+
+    fn z80_fetch_opcode(&mut self) -> u8 {
+        self.z80_refresh();
+        return self.z80_fetch();
+    }
+
+    fn z80_fetch(&mut self) -> u8 {
+        let value = self.bus_read(self.program_counter);
+        self.program_counter = self.program_counter.wrapping_add(1);
+        return value;
+    }
+
+    fn z80_fetch_word(&mut self) -> u16 {
+        let low = self.z80_fetch() as u16;
+        let high = self.z80_fetch() as u16;
+        return high << 8 | low;
+    }
+
+    fn z80_read_word(&mut self, address: u16) -> u16 {
+        let low = self.bus_read(address) as u16;
+        let high = self.bus_read(address.wrapping_add(1)) as u16;
+        return high << 8 | low;
+    }
+
+    fn z80_write_word(&mut self, address: u16, value: u16) {
+        self.bus_write(address, value as u8);
+        self.bus_write(address.wrapping_add(1), (value >> 8) as u8);
+    }
+
+    fn z80_push(&mut self, value: u16) {
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        self.bus_write(self.stack_pointer, (value >> 8) as u8);
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        self.bus_write(self.stack_pointer, value as u8);
+    }
+
+    fn z80_pop(&mut self) -> u16 {
+        let value = self.z80_read_word(self.stack_pointer);
+        self.stack_pointer = self.stack_pointer.wrapping_add(2);
+        return value;
+    }
+
+    fn z80_hl(&self, index: Index) -> u16 {
+        match index {
+            Index::Hl => return self.hl(),
+            Index::Ix => return self.z80.ix,
+            Index::Iy => return self.z80.iy,
+        }
+    }
+
+    fn z80_set_hl(&mut self, index: Index, value: u16) {
+        match index {
+            Index::Hl => self.set_hl(value),
+            Index::Ix => self.z80.ix = value,
+            Index::Iy => self.z80.iy = value,
+        }
+    }
+
+    // Register pairs as the p field encodes them, BC DE HL SP
+    fn z80_pair(&self, p: u8, index: Index) -> u16 {
+        match p {
+            0 => return self.bc(),
+            1 => return self.de(),
+            2 => return self.z80_hl(index),
+            _ => return self.stack_pointer,
+        }
+    }
+
+    fn z80_set_pair(&mut self, p: u8, index: Index, value: u16) {
+        match p {
+            0 => self.set_bc(value),
+            1 => self.set_de(value),
+            2 => self.z80_set_hl(index, value),
+            _ => self.stack_pointer = value,
+        }
+    }
+
+    // Register pairs as PUSH and POP encode them, BC DE HL AF
+    fn z80_stack_pair(&self, p: u8, index: Index) -> u16 {
+        if p == 3 {
+            return (self.a as u16) << 8 | self.z80_flags() as u16;
+        }
+        return self.z80_pair(p, index);
+    }
+
+    fn z80_set_stack_pair(&mut self, p: u8, index: Index, value: u16) {
+        if p == 3 {
+            self.a = (value >> 8) as u8;
+            self.set_z80_flags(value as u8);
+            return;
+        }
+        self.z80_set_pair(p, index, value);
+    }
+
+    // 8 bit registers as the r field encodes them (never 6, the memory operand), H and L become the
+    // halves of IX or IY under a prefix
+    fn z80_register(&self, r: u8, index: Index) -> u8 {
+        match (r, index) {
+            (0, _) => return self.b,
+            (1, _) => return self.c,
+            (2, _) => return self.d,
+            (3, _) => return self.e,
+            (4, Index::Hl) => return self.h,
+            (5, Index::Hl) => return self.l,
+            (4, _) => return (self.z80_hl(index) >> 8) as u8,
+            (5, _) => return self.z80_hl(index) as u8,
+            _ => return self.a,
+        }
+    }
+
+    fn z80_set_register(&mut self, r: u8, index: Index, value: u8) {
+        match (r, index) {
+            (0, _) => self.b = value,
+            (1, _) => self.c = value,
+            (2, _) => self.d = value,
+            (3, _) => self.e = value,
+            (4, Index::Hl) => self.h = value,
+            (5, Index::Hl) => self.l = value,
+            (4, _) => {
+                let pair = self.z80_hl(index);
+                self.z80_set_hl(index, (value as u16) << 8 | (pair & 0x00FF));
+            }
+            (5, _) => {
+                let pair = self.z80_hl(index);
+                self.z80_set_hl(index, (pair & 0xFF00) | value as u16);
+            }
+            _ => self.a = value,
+        }
+    }
+
+    // Address of the memory operand, (HL) or (IX+d)/(IY+d) which fetches the displacement
+    fn z80_address(&mut self, index: Index) -> u16 {
+        if index == Index::Hl {
+            return self.z80_hl(index);
+        }
+        let displacement = self.z80_fetch() as i8;
+        self.cycles = self.cycles.wrapping_add(8);
+        return self.z80_hl(index).wrapping_add(displacement as i16 as u16);
+    }
+
+    fn z80_relative_jump(&mut self, displacement: u8) {
+        self.program_counter = self.program_counter.wrapping_add(displacement as i8 as i16 as u16);
+    }
+
+    fn z80_set_szp(&mut self, value: u8) {
+        self.set_sign_flag(value);
+        self.set_zero_flag(value);
+        self.set_parity_flag(value as u16);
+    }
+
+    /*
+     * z80_alu - Function
+     * Expects: operation to be the ALU field, ADD ADC SUB SBC AND XOR OR CP
+     * Does: Performs it on A and value with Z80 flags (P/V is overflow for arithmetic and parity for
+     * logic, AND sets H, the subtractions set N)
+     * Returns: N/A
+     */
+    fn z80_alu(&mut self, operation: u8, value: u8) {
+        let a = self.a;
+        match operation {
+            0 | 1 => {
+                let carry_in = (operation == 1 && self.carry) as u8;
+                let sum = a as u16 + value as u16 + carry_in as u16;
+                let result = sum as u8;
+                self.auxiliary_carry = (a & 0x0F) + (value & 0x0F) + carry_in > 0x0F;
+                self.parity = (a ^ result) & (value ^ result) & 0x80 != 0;
+                self.carry = sum > 0xFF;
+                self.z80.subtract = false;
+                self.set_sign_flag(result);
+                self.set_zero_flag(result);
+                self.a = result;
+            }
+            2 | 3 | 7 => {
+                let carry_in = (operation == 3 && self.carry) as u8;
+                let result = a.wrapping_sub(value).wrapping_sub(carry_in);
+                self.auxiliary_carry = (a & 0x0F) < (value & 0x0F) + carry_in;
+                self.parity = (a ^ value) & (a ^ result) & 0x80 != 0;
+                self.carry = (a as u16) < value as u16 + carry_in as u16;
+                self.z80.subtract = true;
+                self.set_sign_flag(result);
+                self.set_zero_flag(result);
+                if operation != 7 {
+                    self.a = result;
+                }
+            }
+            _ => {
+                self.a = match operation {
+                    4 => a & value,
+                    5 => a ^ value,
+                    _ => a | value,
+                };
+                self.auxiliary_carry = operation == 4;
+                self.carry = false;
+                self.z80.subtract = false;
+                self.z80_set_szp(self.a);
+            }
+        }
+    }
+
+    fn z80_increment(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_add(1);
+        self.auxiliary_carry = value & 0x0F == 0x0F;
+        self.parity = value == 0x7F;
+        self.z80.subtract = false;
+        self.set_sign_flag(result);
+        self.set_zero_flag(result);
+        return result;
+    }
+
+    fn z80_decrement(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        self.auxiliary_carry = value & 0x0F == 0;
+        self.parity = value == 0x80;
+        self.z80.subtract = true;
+        self.set_sign_flag(result);
+        self.set_zero_flag(result);
+        return result;
+    }
+
+    // ADD HL,rr only touches H, N and C
+    fn z80_add_pair(&mut self, first: u16, second: u16) -> u16 {
+        let sum = first as u32 + second as u32;
+        self.auxiliary_carry = (first & 0x0FFF) + (second & 0x0FFF) > 0x0FFF;
+        self.carry = sum > 0xFFFF;
+        self.z80.subtract = false;
+        return sum as u16;
+    }
+
+    // ADC HL,rr and SBC HL,rr set every flag from the 16 bit result
+    fn z80_adc_sbc_pair(&mut self, value: u16, subtract: bool) {
+        let hl = self.z80_hl(Index::Hl);
+        let carry_in = self.carry as u16;
+        let result = if subtract {
+            self.auxiliary_carry = (hl & 0x0FFF) < (value & 0x0FFF) + carry_in;
+            self.carry = (hl as u32) < value as u32 + carry_in as u32;
+            let result = hl.wrapping_sub(value).wrapping_sub(carry_in);
+            self.parity = (hl ^ value) & (hl ^ result) & 0x8000 != 0;
+            result
+        } else {
+            self.auxiliary_carry = (hl & 0x0FFF) + (value & 0x0FFF) + carry_in > 0x0FFF;
+            self.carry = hl as u32 + value as u32 + carry_in as u32 > 0xFFFF;
+            let result = hl.wrapping_add(value).wrapping_add(carry_in);
+            self.parity = (hl ^ result) & (value ^ result) & 0x8000 != 0;
+            result
+        };
+        self.sign = result & 0x8000 != 0;
+        self.zero = result == 0;
+        self.z80.subtract = subtract;
+        self.z80_set_hl(Index::Hl, result);
+    }
+
+    fn z80_daa(&mut self) {
+        let a = self.a;
+        let mut correction = 0;
+        let mut carry = self.carry;
+        if self.auxiliary_carry || a & 0x0F > 9 {
+            correction |= 0x06;
+        }
+        if carry || a > 0x99 {
+            correction |= 0x60;
+            carry = true;
+        }
+        if self.z80.subtract {
+            self.auxiliary_carry = self.auxiliary_carry && a & 0x0F < 6;
+            self.a = a.wrapping_sub(correction);
+        } else {
+            self.auxiliary_carry = a & 0x0F > 9;
+            self.a = a.wrapping_add(correction);
+        }
+        self.carry = carry;
+        self.z80_set_szp(self.a);
+    }
+
+    // The accumulator rotates only touch H, N and C
+    fn z80_rotate_accumulator(&mut self, y: u8) {
+        let a = self.a;
+        let (result, carry) = match y {
+            0 => (a.rotate_left(1), a & 0x80 != 0),
+            1 => (a.rotate_right(1), a & 0x01 != 0),
+            2 => (a << 1 | self.carry as u8, a & 0x80 != 0),
+            _ => (a >> 1 | (self.carry as u8) << 7, a & 0x01 != 0),
+        };
+        self.a = result;
+        self.carry = carry;
+        self.auxiliary_carry = false;
+        self.z80.subtract = false;
+    }
+
+    // CB rotates and shifts, RLC RRC RL RR SLA SRA SLL SRL
+    fn z80_shift(&mut self, y: u8, value: u8) -> u8 {
+        let (result, carry) = match y {
+            0 => (value.rotate_left(1), value & 0x80 != 0),
+            1 => (value.rotate_right(1), value & 0x01 != 0),
+            2 => (value << 1 | self.carry as u8, value & 0x80 != 0),
+            3 => (value >> 1 | (self.carry as u8) << 7, value & 0x01 != 0),
+            4 => (value << 1, value & 0x80 != 0),
+            5 => (value >> 1 | (value & 0x80), value & 0x01 != 0),
+            6 => (value << 1 | 1, value & 0x80 != 0),
+            _ => (value >> 1, value & 0x01 != 0),
+        };
+        self.carry = carry;
+        self.auxiliary_carry = false;
+        self.z80.subtract = false;
+        self.z80_set_szp(result);
+        return result;
+    }
+
+    // Performs a CB opcode on value, the new value to store or None for BIT which only sets flags
+    fn z80_bit_operation(&mut self, opcode: u8, value: u8) -> Option<u8> {
+        let y = (opcode >> 3) & 0x07;
+        match opcode >> 6 {
+            0 => return Some(self.z80_shift(y, value)),
+            1 => {
+                let clear = value & (1 << y) == 0;
+                self.zero = clear;
+                self.parity = clear;
+                self.sign = y == 7 && !clear;
+                self.auxiliary_carry = true;
+                self.z80.subtract = false;
+                return None;
+            }
+            2 => return Some(value & !(1 << y)),
+            _ => return Some(value | (1 << y)),
+        }
+    }
+
+    /*
+     * z80_execute_opcode - Function
+     * Expects: opcode to be an unprefixed opcode already fetched, index the prefix in front of it
+     * Does: Decodes it by its x/y/z fields and performs it, DD/FD/CB/ED hand over to the next byte
+     * Returns: A StepInstructionResult indicating how things went
+     */
+    fn z80_execute_opcode(&mut self, opcode: u8, index: Index) -> StepInstructionResult {
+        self.cycles = self.cycles.wrapping_add(Z80_CYCLE_TABLE[opcode as usize] as u64);
+        let x = opcode >> 6;
+        let y = (opcode >> 3) & 0x07;
+        let z = opcode & 0x07;
+        let p = y >> 1;
+        let q = y & 0x01;
+
+        match (x, z) {
+            (0, 0) => match y {
+                0 => {}
+                1 => {
+                    let af = (self.a as u16) << 8 | self.z80_flags() as u16;
+                    let alternate = self.z80.af_alternate;
+                    self.z80.af_alternate = af;
+                    self.a = (alternate >> 8) as u8;
+                    self.set_z80_flags(alternate as u8);
+                }
+                2 => {
+                    let displacement = self.z80_fetch();
+                    self.b = self.b.wrapping_sub(1);
+                    if self.b != 0 {
+                        self.z80_relative_jump(displacement);
+                        self.cycles = self.cycles.wrapping_add(5);
+                    }
+                }
+                3 => {
+                    let displacement = self.z80_fetch();
+                    self.z80_relative_jump(displacement);
+                }
+                _ => {
+                    // JR NZ/Z/NC/C share the condition encoding of the first four JP cc
+                    let displacement = self.z80_fetch();
+                    if self.condition_met((y - 4) << 3) {
+                        self.z80_relative_jump(displacement);
+                        self.cycles = self.cycles.wrapping_add(5);
+                    }
+                }
+            },
+            (0, 1) => {
+                if q == 0 {
+                    let value = self.z80_fetch_word();
+                    self.z80_set_pair(p, index, value);
+                } else {
+                    let sum = self.z80_add_pair(self.z80_hl(index), self.z80_pair(p, index));
+                    self.z80_set_hl(index, sum);
+                }
+            }
+            (0, 2) => match y {
+                0 => self.bus_write(self.z80_pair(0, Index::Hl), self.a),
+                1 => self.a = self.bus_read(self.z80_pair(0, Index::Hl)),
+                2 => self.bus_write(self.z80_pair(1, Index::Hl), self.a),
+                3 => self.a = self.bus_read(self.z80_pair(1, Index::Hl)),
+                4 => {
+                    let address = self.z80_fetch_word();
+                    self.z80_write_word(address, self.z80_hl(index));
+                }
+                5 => {
+                    let address = self.z80_fetch_word();
+                    let value = self.z80_read_word(address);
+                    self.z80_set_hl(index, value);
+                }
+                6 => {
+                    let address = self.z80_fetch_word();
+                    self.bus_write(address, self.a);
+                }
+                _ => {
+                    let address = self.z80_fetch_word();
+                    self.a = self.bus_read(address);
+                }
+            },
+            (0, 3) => {
+                let value = self.z80_pair(p, index);
+                let result = if q == 0 { value.wrapping_add(1) } else { value.wrapping_sub(1) };
+                self.z80_set_pair(p, index, result);
+            }
+            (0, 4) | (0, 5) => {
+                if y == 6 {
+                    let address = self.z80_address(index);
+                    let value = self.bus_read(address);
+                    let result = if z == 4 { self.z80_increment(value) } else { self.z80_decrement(value) };
+                    self.bus_write(address, result);
+                } else {
+                    let value = self.z80_register(y, index);
+                    let result = if z == 4 { self.z80_increment(value) } else { self.z80_decrement(value) };
+                    self.z80_set_register(y, index, result);
+                }
+            }
+            (0, 6) => {
+                if y == 6 {
+                    // LD (IX+d),n overlaps fetching n with the address calculation
+                    let address = match index {
+                        Index::Hl => self.z80_hl(index),
+                        _ => {
+                            let displacement = self.z80_fetch() as i8;
+                            self.cycles = self.cycles.wrapping_add(5);
+                            self.z80_hl(index).wrapping_add(displacement as i16 as u16)
+                        }
+                    };
+                    let value = self.z80_fetch();
+                    self.bus_write(address, value);
+                } else {
+                    let value = self.z80_fetch();
+                    self.z80_set_register(y, index, value);
+                }
+            }
+            (0, _) => match y {
+                0..=3 => self.z80_rotate_accumulator(y),
+                4 => self.z80_daa(),
+                5 => {
+                    self.a = !self.a;
+                    self.auxiliary_carry = true;
+                    self.z80.subtract = true;
+                }
+                6 => {
+                    self.carry = true;
+                    self.auxiliary_carry = false;
+                    self.z80.subtract = false;
+                }
+                _ => {
+                    self.auxiliary_carry = self.carry;
+                    self.carry = !self.carry;
+                    self.z80.subtract = false;
+                }
+            },
+            (1, _) => {
+                if y == 6 && z == 6 {
+                    self.halted = true;
+                    return StepInstructionResult::Halt;
+                } else if y == 6 {
+                    // the register half of LD (IX+d),r is never IXH/IXL
+                    let address = self.z80_address(index);
+                    self.bus_write(address, self.z80_register(z, Index::Hl));
+                } else if z == 6 {
+                    let address = self.z80_address(index);
+                    let value = self.bus_read(address);
+                    self.z80_set_register(y, Index::Hl, value);
+                } else {
+                    let value = self.z80_register(z, index);
+                    self.z80_set_register(y, index, value);
+                }
+            }
+            (2, _) => {
+                let value = if z == 6 {
+                    let address = self.z80_address(index);
+                    self.bus_read(address)
+                } else {
+                    self.z80_register(z, index)
+                };
+                self.z80_alu(y, value);
+            }
+            (_, 0) => {
+                if self.condition_met(opcode) {
+                    self.program_counter = self.z80_pop();
+                    self.cycles = self.cycles.wrapping_add(6);
+                }
+            }
+            (_, 1) => {
+                if q == 0 {
+                    let value = self.z80_pop();
+                    self.z80_set_stack_pair(p, index, value);
+                } else {
+                    match p {
+                        0 => self.program_counter = self.z80_pop(),
+                        1 => {
+                            let (bc, de, hl) = (self.z80_pair(0, Index::Hl), self.z80_pair(1, Index::Hl), self.z80_hl(Index::Hl));
+                            self.z80_set_pair(0, Index::Hl, self.z80.bc_alternate);
+                            self.z80_set_pair(1, Index::Hl, self.z80.de_alternate);
+                            self.z80_set_hl(Index::Hl, self.z80.hl_alternate);
+                            self.z80.bc_alternate = bc;
+                            self.z80.de_alternate = de;
+                            self.z80.hl_alternate = hl;
+                        }
+                        2 => self.program_counter = self.z80_hl(index),
+                        _ => self.stack_pointer = self.z80_hl(index),
+                    }
+                }
+            }
+            (_, 2) => {
+                let address = self.z80_fetch_word();
+                if self.condition_met(opcode) {
+                    self.program_counter = address;
+                }
+            }
+            (_, 3) => match y {
+                0 => self.program_counter = self.z80_fetch_word(),
+                1 => {
+                    if index == Index::Hl {
+                        return self.z80_execute_cb();
+                    }
+                    return self.z80_execute_indexed_cb(index);
+                }
+                2 => {
+                    let port = self.z80_fetch();
+                    self.io_output(port, self.a);
+                }
+                3 => {
+                    let port = self.z80_fetch();
+                    self.a = self.io_input(port, self.a);
+                }
+                4 => {
+                    let value = self.z80_read_word(self.stack_pointer);
+                    self.z80_write_word(self.stack_pointer, self.z80_hl(index));
+                    self.z80_set_hl(index, value);
+                }
+                5 => {
+                    // EX DE,HL ignores a prefix
+                    std::mem::swap(&mut self.d, &mut self.h);
+                    std::mem::swap(&mut self.e, &mut self.l);
+                }
+                6 => {
+                    self.interrupts_enabled = false;
+                    self.z80.iff2 = false;
+                }
+                _ => {
+                    self.interrupts_enabled = true;
+                    self.z80.iff2 = true;
+                    self.interrupt_delay = true;
+                }
+            },
+            (_, 4) => {
+                let address = self.z80_fetch_word();
+                if self.condition_met(opcode) {
+                    let return_address = self.program_counter;
+                    self.z80_push(return_address);
+                    self.program_counter = address;
+                    self.cycles = self.cycles.wrapping_add(7);
+                }
+            }
+            (_, 5) => {
+                if q == 0 {
+                    let value = self.z80_stack_pair(p, index);
+                    self.z80_push(value);
+                } else {
+                    match p {
+                        0 => {
+                            let address = self.z80_fetch_word();
+                            let return_address = self.program_counter;
+                            self.z80_push(return_address);
+                            self.program_counter = address;
+                        }
+                        1 => {
+                            let next = self.z80_fetch_opcode();
+                            return self.z80_execute_opcode(next, Index::Ix);
+                        }
+                        2 => return self.z80_execute_ed(),
+                        _ => {
+                            let next = self.z80_fetch_opcode();
+                            return self.z80_execute_opcode(next, Index::Iy);
+                        }
+                    }
+                }
+            }
+            (_, 6) => {
+                let value = self.z80_fetch();
+                self.z80_alu(y, value);
+            }
+            _ => {
+                let return_address = self.program_counter;
+                self.z80_push(return_address);
+                self.program_counter = (y as u16) << 3;
+            }
+        }
+        return StepInstructionResult::Ok;
+    }
+
+    /*
+     * z80_execute_cb - Function
+     * Expects: the CB prefix to have been fetched
+     * Does: Performs the rotate, shift, BIT, RES or SET that follows on a register or (HL)
+     * Returns: StepInstructionResult::Ok
+     */
+    fn z80_execute_cb(&mut self) -> StepInstructionResult {
+        let opcode = self.z80_fetch_opcode();
+        let z = opcode & 0x07;
+        if z == 6 {
+            let address = self.z80_hl(Index::Hl);
+            let value = self.bus_read(address);
+            let cycles = if opcode >> 6 == 1 { 12 } else { 15 };
+            self.cycles = self.cycles.wrapping_add(cycles);
+            if let Some(result) = self.z80_bit_operation(opcode, value) {
+                self.bus_write(address, result);
+            }
+        } else {
+            let value = self.z80_register(z, Index::Hl);
+            self.cycles = self.cycles.wrapping_add(8);
+            if let Some(result) = self.z80_bit_operation(opcode, value) {
+                self.z80_set_register(z, Index::Hl, result);
+            }
+        }
+        return StepInstructionResult::Ok;
+    }
+
+    /*
+     * z80_execute_indexed_cb - Function
+     * Expects: DD CB or FD CB to have been fetched
+     * Does: Fetches the displacement then the opcode and performs it on (IX+d)/(IY+d), the
+     * undocumented forms naming a register also copy the result into it
+     * Returns: StepInstructionResult::Ok
+     */
+    fn z80_execute_indexed_cb(&mut self, index: Index) -> StepInstructionResult {
+        let displacement = self.z80_fetch() as i8;
+        let opcode = self.z80_fetch();
+        let address = self.z80_hl(index).wrapping_add(displacement as i16 as u16);
+        let value = self.bus_read(address);
+        let cycles = if opcode >> 6 == 1 { 16 } else { 19 };
+        self.cycles = self.cycles.wrapping_add(cycles);
+        if let Some(result) = self.z80_bit_operation(opcode, value) {
+            self.bus_write(address, result);
+            let z = opcode & 0x07;
+            if z != 6 {
+                self.z80_set_register(z, Index::Hl, result);
+            }
+        }
+        return StepInstructionResult::Ok;
+    }
+
+    /*
+     * z80_execute_ed - Function
+     * Expects: the ED prefix to have been fetched
+     * Does: Performs the ED instruction that follows, opcodes with no instruction behave as 8 cycle NOPs
+     * Returns: StepInstructionResult::Ok
+     */
+    fn z80_execute_ed(&mut self) -> StepInstructionResult {
+        let opcode = self.z80_fetch_opcode();
+        let x = opcode >> 6;
+        let y = (opcode >> 3) & 0x07;
+        let z = opcode & 0x07;
+        let p = y >> 1;
+        let q = y & 0x01;
+
+        let cycles = match (x, z) {
+            (1, 0) => {
+                // IN r,(C), the y = 6 form only sets the flags
+                let value = self.io_input(self.c, 0xFF);
+                self.z80_set_szp(value);
+                self.auxiliary_carry = false;
+                self.z80.subtract = false;
+                if y != 6 {
+                    self.z80_set_register(y, Index::Hl, value);
+                }
+                12
+            }
+            (1, 1) => {
+                let value = if y == 6 { 0 } else { self.z80_register(y, Index::Hl) };
+                self.io_output(self.c, value);
+                12
+            }
+            (1, 2) => {
+                let value = self.z80_pair(p, Index::Hl);
+                self.z80_adc_sbc_pair(value, q == 0);
+                15
+            }
+            (1, 3) => {
+                let address = self.z80_fetch_word();
+                if q == 0 {
+                    self.z80_write_word(address, self.z80_pair(p, Index::Hl));
+                } else {
+                    let value = self.z80_read_word(address);
+                    self.z80_set_pair(p, Index::Hl, value);
+                }
+                20
+            }
+            (1, 4) => {
+                // NEG is 0 - A
+                let value = self.a;
+                self.a = 0;
+                self.z80_alu(2, value);
+                8
+            }
+            (1, 5) => {
+                // RETN and RETI both restore IFF1 from IFF2
+                self.program_counter = self.z80_pop();
+                self.interrupts_enabled = self.z80.iff2;
+                14
+            }
+            (1, 6) => {
+                self.z80.interrupt_mode = INTERRUPT_MODES[y as usize];
+                8
+            }
+            (1, _) => match y {
+                0 => {
+                    self.z80.i = self.a;
+                    9
+                }
+                1 => {
+                    self.z80.r = self.a;
+                    9
+                }
+                2 | 3 => {
+                    self.a = if y == 2 { self.z80.i } else { self.z80.r };
+                    self.set_sign_flag(self.a);
+                    self.set_zero_flag(self.a);
+                    self.parity = self.z80.iff2;
+                    self.auxiliary_carry = false;
+                    self.z80.subtract = false;
+                    9
+                }
+                4 | 5 => {
+                    let address = self.z80_hl(Index::Hl);
+                    let value = self.bus_read(address);
+                    let (memory, a) = if y == 4 {
+                        (self.a << 4 | value >> 4, (self.a & 0xF0) | (value & 0x0F))
+                    } else {
+                        (value << 4 | (self.a & 0x0F), (self.a & 0xF0) | value >> 4)
+                    };
+                    self.bus_write(address, memory);
+                    self.a = a;
+                    self.z80_set_szp(a);
+                    self.auxiliary_carry = false;
+                    self.z80.subtract = false;
+                    18
+                }
+                _ => 8,
+            },
+            (2, 0..=3) if y >= 4 => self.z80_block(y, z),
+            _ => 8,
+        };
+        self.cycles = self.cycles.wrapping_add(cycles);
+        return StepInstructionResult::Ok;
+    }
+
+    /*
+     * z80_block - Function
+     * Expects: y and z to be the fields of an ED block instruction (y 4 LDI CPI INI OUTI, 5 the
+     * decrementing forms, 6 and 7 their repeating forms)
+     * Does: Performs one iteration, a repeating form that isn't done moves the PC back onto itself so
+     * every iteration is its own step (and interrupts can be taken in between)
+     * Returns: The cycles the iteration took
+     */
+    fn z80_block(&mut self, y: u8, z: u8) -> u64 {
+        let step = if y & 0x01 == 1 { 0xFFFF } else { 0x0001 };
+        let hl = self.z80_hl(Index::Hl);
+        self.z80_set_hl(Index::Hl, hl.wrapping_add(step));
+
+        let again = match z {
+            0 | 1 => {
+                let value = self.bus_read(hl);
+                if z == 0 {
+                    let de = self.z80_pair(1, Index::Hl);
+                    self.bus_write(de, value);
+                    self.z80_set_pair(1, Index::Hl, de.wrapping_add(step));
+                    self.auxiliary_carry = false;
+                    self.z80.subtract = false;
+                } else {
+                    let result = self.a.wrapping_sub(value);
+                    self.auxiliary_carry = (self.a & 0x0F) < (value & 0x0F);
+                    self.set_sign_flag(result);
+                    self.set_zero_flag(result);
+                    self.z80.subtract = true;
+                }
+                let bc = self.z80_pair(0, Index::Hl).wrapping_sub(1);
+                self.z80_set_pair(0, Index::Hl, bc);
+                self.parity = bc != 0;
+                bc != 0 && !(z == 1 && self.zero)
+            }
+            _ => {
+                if z == 2 {
+                    let value = self.io_input(self.c, 0xFF);
+                    self.bus_write(hl, value);
+                    self.b = self.b.wrapping_sub(1);
+                } else {
+                    let value = self.bus_read(hl);
+                    self.b = self.b.wrapping_sub(1);
+                    self.io_output(self.c, value);
+                }
+                self.zero = self.b == 0;
+                self.z80.subtract = true;
+                self.b != 0
+            }
+        };
+        if y >= 6 && again {
+            self.program_counter = self.program_counter.wrapping_sub(2);
+            return 21;
+        }
+        return 16;
+    }
+}
@@ -0,0 +1,132 @@
+use i8080_emulator::altair::disk::{DiskController, DiskImage, DISK_SIZE, GEOMETRY, SECTOR_SIZE};
+use i8080_emulator::altair::Altair;
+use i8080_emulator::StepInstructionResult;
+use std::fs;
+
+// Selects drive 0, loads the head, steps in to track 1 and waits for sector 3 to come round
+const SEEK_TRACK_1_SECTOR_3: [u8; 28] = [
+    0x31, 0x00, 0x10, // LXI SP,1000
+    0x3E, 0x00,       // MVI A,00
+    0xD3, 0x08,       // OUT 08      select drive 0
+    0x3E, 0x04,       // MVI A,04
+    0xD3, 0x09,       // OUT 09      load head
+    0x3E, 0x01,       // MVI A,01
+    0xD3, 0x09,       // OUT 09      step in
+    0xDB, 0x09,       // IN 09       wait for sector true
+    0x0F,             // RRC
+    0xDA, 0x0F, 0x00, // JC 000F
+    0xE6, 0x1F,       // ANI 1F      sector number
+    0xFE, 0x03,       // CPI 03
+    0xC2, 0x0F, 0x00, // JNZ 000F
+];
+
+// Reads the sector under the head into 0x2000
+const READ_SECTOR: [u8; 14] = [
+    0x21, 0x00, 0x20, // LXI H,2000
+    0x0E, 0x89,       // MVI C,137
+    0xDB, 0x0A,       // IN 0A
+    0x77,             // MOV M,A
+    0x23,             // INX H
+    0x0D,             // DCR C
+    0xC2, 0x21, 0x00, // JNZ 0021
+    0x76,             // HLT
+];
+
+// Turns on write enable and writes the 137 bytes at 0x2000 to the sector under the head
+const WRITE_SECTOR: [u8; 18] = [
+    0x3E, 0x80,       // MVI A,80
+    0xD3, 0x09,       // OUT 09      write enable
+    0x21, 0x00, 0x20, // LXI H,2000
+    0x0E, 0x89,       // MVI C,137
+    0x7E,             // MOV A,M
+    0xD3, 0x0A,       // OUT 0A
+    0x23,             // INX H
+    0x0D,             // DCR C
+    0xC2, 0x25, 0x00, // JNZ 0025
+    0x76,             // HLT
+];
+
+fn run_until_halt(altair: &mut Altair) {
+    for _ in 0..1_000_000 {
+        if altair.step() == StepInstructionResult::Halt {
+            return;
+        }
+    }
+    panic!("program never halted, PC is 0x{:04X}", altair.core.program_counter);
+}
+
+fn altair_with_program(disk: DiskImage, routine: &[u8]) -> Altair {
+    let mut altair = Altair::new();
+    let mut controller = DiskController::new();
+    controller.insert(0, disk);
+    altair.attach_disk_controller(controller);
+
+    let program: Vec<u8> = SEEK_TRACK_1_SECTOR_3.iter().chain(routine.iter()).copied().collect();
+    altair.core.memory[..program.len()].copy_from_slice(&program);
+    altair
+}
+
+fn temp_image(name: &str, data: &[u8]) -> String {
+    let path = std::env::temp_dir().join(format!("i8080_{}_{}.dsk", name, std::process::id()));
+    fs::write(&path, data).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn reads_sector_from_image_file() {
+    let mut image = vec![0xE5; DISK_SIZE];
+    let offset = GEOMETRY.sector_offset(1, 3).unwrap();
+    for i in 0..SECTOR_SIZE {
+        image[offset + i] = i as u8;
+    }
+    let path = temp_image("read", &image);
+
+    let mut altair = altair_with_program(DiskImage::open(&path, true, GEOMETRY).unwrap(), &READ_SECTOR);
+    run_until_halt(&mut altair);
+    fs::remove_file(&path).unwrap();
+
+    let expected: Vec<u8> = (0..SECTOR_SIZE).map(|i| i as u8).collect();
+    assert_eq!(&altair.core.memory[0x2000..0x2000 + SECTOR_SIZE], &expected[..]);
+}
+
+#[test]
+fn writes_sector_back_to_image_file() {
+    let path = temp_image("write", &vec![0xE5; DISK_SIZE]);
+
+    let mut altair = altair_with_program(DiskImage::open(&path, false, GEOMETRY).unwrap(), &WRITE_SECTOR);
+    for i in 0..SECTOR_SIZE {
+        altair.core.memory[0x2000 + i] = 0xFF - i as u8;
+    }
+    run_until_halt(&mut altair);
+
+    let image = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let offset = GEOMETRY.sector_offset(1, 3).unwrap();
+    let expected: Vec<u8> = (0..SECTOR_SIZE).map(|i| 0xFF - i as u8).collect();
+    assert_eq!(&image[offset..offset + SECTOR_SIZE], &expected[..]);
+    assert!(image[..offset].iter().all(|byte| *byte == 0xE5));
+    assert!(altair.disk_controller.unwrap().borrow_mut().take_error().is_none());
+}
+
+#[test]
+fn status_reads_all_ones_without_a_disk() {
+    let mut controller = DiskController::new();
+    controller.write(0x08, 0x00);
+    assert_eq!(controller.read(0x08), 0xFF);
+}
+
+#[test]
+fn write_to_read_only_disk_is_reported() {
+    let path = temp_image("read_only", &vec![0xE5; DISK_SIZE]);
+
+    let mut altair = altair_with_program(DiskImage::open(&path, true, GEOMETRY).unwrap(), &WRITE_SECTOR);
+    run_until_halt(&mut altair);
+
+    let image = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(image.iter().all(|byte| *byte == 0xE5));
+    let controller = altair.disk_controller.unwrap();
+    let error = controller.borrow_mut().take_error().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+    assert!(controller.borrow_mut().take_error().is_none());
+}
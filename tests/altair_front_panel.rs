@@ -0,0 +1,103 @@
+use i8080_emulator::altair::Altair;
+use i8080_emulator::StepInstructionResult;
+
+// Dean McDaniel's "kill the bit", a lit bit rotates across A8-A15 and flipping the matching sense
+// switch as it goes past knocks it out
+const KILL_THE_BIT: [u8; 24] = [
+    0x21, 0x00, 0x00, // 0000 LXI H,0000
+    0x16, 0x80,       // 0003 MVI D,80
+    0x01, 0x0E, 0x00, // 0005 LXI B,000E
+    0x1A,             // 0008 LDAX D
+    0x1A,             // 0009 LDAX D
+    0x1A,             // 000A LDAX D
+    0x1A,             // 000B LDAX D
+    0x09,             // 000C DAD B
+    0xD2, 0x08, 0x00, // 000D JNC 0008
+    0xDB, 0xFF,       // 0010 IN FF
+    0xAA,             // 0012 XRA D
+    0x0F,             // 0013 RRC
+    0x57,             // 0014 MOV D,A
+    0xC3, 0x08, 0x00, // 0015 JMP 0008
+];
+
+// Toggles program in at 0x0000 through the switches the way an operator would
+fn toggle_in(altair: &mut Altair, program: &[u8]) {
+    altair.stop();
+    altair.front_panel.switches = 0x0000;
+    altair.examine();
+    for (index, byte) in program.iter().enumerate() {
+        altair.front_panel.switches = *byte as u16;
+        if index == 0 {
+            altair.deposit();
+        } else {
+            altair.deposit_next();
+        }
+    }
+    altair.reset();
+}
+
+// Runs until the next LDAX D and returns the address LEDs it left behind
+fn next_lit_address(altair: &mut Altair) -> u16 {
+    for _ in 0..1_000_000 {
+        let opcode = altair.core.memory[altair.core.program_counter as usize];
+        altair.step();
+        if opcode == 0x1A {
+            return altair.front_panel.leds.address;
+        }
+    }
+    panic!("never reached LDAX D");
+}
+
+#[test]
+fn deposit_and_examine() {
+    let mut altair = Altair::new();
+    toggle_in(&mut altair, &KILL_THE_BIT);
+    assert_eq!(altair.core.memory[..KILL_THE_BIT.len()], KILL_THE_BIT);
+
+    altair.front_panel.switches = 0x0010;
+    altair.examine();
+    assert_eq!(altair.front_panel.leds.address, 0x0010);
+    assert_eq!(altair.front_panel.leds.data, 0xDB);
+    altair.examine_next();
+    assert_eq!(altair.front_panel.leds.address, 0x0011);
+    assert_eq!(altair.front_panel.leds.data, 0xFF);
+    assert!(altair.front_panel.leds.wait);
+}
+
+#[test]
+fn stopped_machine_only_single_steps() {
+    let mut altair = Altair::new();
+    toggle_in(&mut altair, &KILL_THE_BIT);
+
+    assert!(altair.run_cycles(1000) == StepInstructionResult::Ok);
+    assert_eq!(altair.core.program_counter, 0x0000);
+
+    altair.single_step();
+    altair.single_step();
+    assert_eq!(altair.core.program_counter, 0x0005);
+    assert_eq!(altair.core.d, 0x80);
+    assert_eq!(altair.front_panel.leds.address, 0x0005);
+    assert_eq!(altair.front_panel.leds.data, 0x01);
+}
+
+#[test]
+fn kill_the_bit() {
+    let mut altair = Altair::new();
+    toggle_in(&mut altair, &KILL_THE_BIT);
+    altair.run();
+
+    // the bit walks down the high address LEDs, the switches are all off so nothing is killed
+    assert_eq!(next_lit_address(&mut altair) >> 8, 0x80);
+    while altair.core.d == 0x80 {
+        altair.step();
+    }
+    assert_eq!(next_lit_address(&mut altair) >> 8, 0x40);
+
+    // raising sense switch 6 as the bit passes it knocks it out
+    altair.front_panel.switches = 0x4000;
+    while altair.core.d == 0x40 {
+        altair.step();
+    }
+    assert_eq!(altair.core.d, 0x00);
+    assert_eq!(next_lit_address(&mut altair) >> 8, 0x00);
+}
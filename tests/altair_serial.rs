@@ -0,0 +1,107 @@
+use i8080_emulator::altair::{
+    Altair, BufferSerial, SerialBackend, SerialBoardKind, SIO_DEFAULT_PORT, TWO_SIO_DEFAULT_PORT,
+};
+use i8080_emulator::StepInstructionResult;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Lets the test keep hold of the buffer the serial card talks to
+struct SharedSerial(Rc<RefCell<BufferSerial>>);
+
+impl SerialBackend for SharedSerial {
+    fn receive(&mut self) -> Option<u8> {
+        self.0.borrow_mut().receive()
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        self.0.borrow_mut().transmit(byte);
+    }
+}
+
+fn altair_with_serial(kind: SerialBoardKind, base_port: u8, input: &[u8]) -> (Altair, Rc<RefCell<BufferSerial>>) {
+    let buffer = Rc::new(RefCell::new(BufferSerial::new(input)));
+    let mut altair = Altair::new();
+    altair.attach_serial(kind, base_port, Box::new(SharedSerial(buffer.clone())));
+    (altair, buffer)
+}
+
+// Loads program at 0x0000 and runs it from there until it reaches its HLT
+fn run(altair: &mut Altair, program: &[u8]) {
+    altair.core.memory[..program.len()].copy_from_slice(program);
+    altair.core.program_counter = 0x0000;
+    altair.core.halted = false;
+    for _ in 0..10_000 {
+        if altair.step() == StepInstructionResult::Halt {
+            return;
+        }
+    }
+    panic!("program never halted, PC is 0x{:04X}", altair.core.program_counter);
+}
+
+// IN status, MOV B,A, IN data, MOV C,A, IN status, HLT
+fn read_status_data_status(base_port: u8) -> [u8; 9] {
+    [0xDB, base_port, 0x47, 0xDB, base_port + 1, 0x4F, 0xDB, base_port, 0x76]
+}
+
+#[test]
+fn sio_status_bits() {
+    let (mut altair, _) = altair_with_serial(SerialBoardKind::Sio, SIO_DEFAULT_PORT, b"A");
+    run(&mut altair, &read_status_data_status(SIO_DEFAULT_PORT));
+    // active low, a character waiting clears bit 0 and the transmitter is always free (bit 7 clear)
+    assert_eq!((altair.core.b, altair.core.c, altair.core.a), (0x00, b'A', 0x01));
+
+    // nothing waiting reads 0 from the data port
+    run(&mut altair, &read_status_data_status(SIO_DEFAULT_PORT));
+    assert_eq!((altair.core.b, altair.core.c, altair.core.a), (0x01, 0x00, 0x01));
+}
+
+#[test]
+fn two_sio_status_bits() {
+    let (mut altair, _) = altair_with_serial(SerialBoardKind::TwoSio, TWO_SIO_DEFAULT_PORT, b"A");
+    run(&mut altair, &read_status_data_status(TWO_SIO_DEFAULT_PORT));
+    // RDRF (bit 0) while a character waits, TDRE (bit 1) always
+    assert_eq!((altair.core.b, altair.core.c, altair.core.a), (0x03, b'A', 0x02));
+}
+
+#[test]
+fn two_sio_master_reset_drops_the_pending_character() {
+    let (mut altair, _) = altair_with_serial(SerialBoardKind::TwoSio, TWO_SIO_DEFAULT_PORT, b"XY");
+    // IN 10, MVI A,03, OUT 10, IN 11, HLT
+    run(&mut altair, &[0xDB, 0x10, 0x3E, 0x03, 0xD3, 0x10, 0xDB, 0x11, 0x76]);
+    assert_eq!(altair.core.a, b'Y');
+}
+
+#[test]
+fn echoes_until_carriage_return() {
+    // 0000 IN 10, ANI 01, JZ 0000, IN 11, OUT 11, CPI 0D, JNZ 0000, HLT
+    let echo = [
+        0xDB, 0x10, 0xE6, 0x01, 0xCA, 0x00, 0x00, 0xDB, 0x11, 0xD3, 0x11, 0xFE, 0x0D, 0xC2, 0x00, 0x00, 0x76,
+    ];
+    let (mut altair, buffer) = altair_with_serial(SerialBoardKind::TwoSio, TWO_SIO_DEFAULT_PORT, b"HELLO\rMORE");
+    run(&mut altair, &echo);
+    assert_eq!(buffer.borrow().output, b"HELLO\r");
+    assert_eq!(buffer.borrow().input, b"MORE");
+}
+
+#[test]
+fn sio_transmits_on_its_data_port() {
+    let (mut altair, buffer) = altair_with_serial(SerialBoardKind::Sio, 0x06, b"");
+    // MVI A,'K', OUT 07, OUT 06 (control, dropped), OUT 01 (no card), HLT
+    run(&mut altair, &[0x3E, b'K', 0xD3, 0x07, 0xD3, 0x06, 0xD3, 0x01, 0x76]);
+    assert_eq!(buffer.borrow().output, b"K");
+}
+
+#[test]
+fn sense_switches_and_empty_ports() {
+    let mut altair = Altair::new();
+    altair.front_panel.switches = 0xA55A;
+    // IN FF, MOV B,A, IN 10, HLT
+    run(&mut altair, &[0xDB, 0xFF, 0x47, 0xDB, 0x10, 0x76]);
+    // only A8-A15 are sense switches, and with no card on 0x10 the bus floats high
+    assert_eq!(altair.core.b, 0xA5);
+    assert_eq!(altair.core.a, 0xFF);
+
+    altair.front_panel.switches = 0x0300;
+    run(&mut altair, &[0xDB, 0xFF, 0x76]);
+    assert_eq!(altair.core.a, 0x03);
+}
@@ -0,0 +1,192 @@
+use i8080_emulator::{CpuModel, I8080Core};
+
+// Everything the ALU leaves behind: A and the flags byte as PUSH PSW would store it
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    a: u8,
+    flags: u8,
+}
+
+// Reference model of the 8080 ALU written from the Intel manual, independent of the core
+fn flags(result: u8, auxiliary_carry: bool, carry: bool) -> u8 {
+    let mut flags = 0x02;
+    if result & 0x80 != 0 {
+        flags |= 0x80;
+    }
+    if result == 0 {
+        flags |= 0x40;
+    }
+    if auxiliary_carry {
+        flags |= 0x10;
+    }
+    if result.count_ones() & 1 == 0 {
+        flags |= 0x04;
+    }
+    if carry {
+        flags |= 0x01;
+    }
+    flags
+}
+
+fn add(a: u8, b: u8, carry_in: bool) -> Outcome {
+    let sum = a as u16 + b as u16 + carry_in as u16;
+    let auxiliary_carry = (a & 0x0F) + (b & 0x0F) + carry_in as u8 > 0x0F;
+    Outcome { a: sum as u8, flags: flags(sum as u8, auxiliary_carry, sum > 0xFF) }
+}
+
+// The 8080 subtracts by adding the complement, the auxiliary carry is the carry out of bit 3 of
+// that addition and CY is the inverted carry out, a borrow
+fn subtract(a: u8, b: u8, borrow_in: bool) -> Outcome {
+    let sum = a as u16 + (!b) as u16 + (!borrow_in) as u16;
+    let auxiliary_carry = (a & 0x0F) + (!b & 0x0F) + (!borrow_in) as u8 > 0x0F;
+    Outcome { a: sum as u8, flags: flags(sum as u8, auxiliary_carry, sum <= 0xFF) }
+}
+
+fn logical(operation: u8, a: u8, b: u8, model: CpuModel) -> Outcome {
+    match operation {
+        // ANA: the 8080 sets AC from bit 3 of the operands ORed, the 8085 always sets it
+        4 => {
+            let auxiliary_carry = model == CpuModel::I8085 || (a | b) & 0x08 != 0;
+            Outcome { a: a & b, flags: flags(a & b, auxiliary_carry, false) }
+        }
+        5 => Outcome { a: a ^ b, flags: flags(a ^ b, false, false) },
+        _ => Outcome { a: a | b, flags: flags(a | b, false, false) },
+    }
+}
+
+fn alu(operation: u8, a: u8, b: u8, carry: bool, model: CpuModel) -> Outcome {
+    match operation {
+        0 => add(a, b, false),
+        1 => add(a, b, carry),
+        2 => subtract(a, b, false),
+        3 => subtract(a, b, carry),
+        4..=6 => logical(operation, a, b, model),
+        // CMP is a SUB that leaves A alone
+        _ => Outcome { a, flags: subtract(a, b, false).flags },
+    }
+}
+
+fn daa(a: u8, auxiliary_carry: bool, carry: bool) -> Outcome {
+    let mut correction = 0;
+    let mut carry_out = carry;
+    if a & 0x0F > 9 || auxiliary_carry {
+        correction |= 0x06;
+    }
+    if a >> 4 > 9 || carry || (a >> 4 >= 9 && a & 0x0F > 9) {
+        correction |= 0x60;
+        carry_out = true;
+    }
+    let result = a.wrapping_add(correction);
+    let auxiliary_carry = (a & 0x0F) + (correction & 0x0F) > 0x0F;
+    Outcome { a: result, flags: flags(result, auxiliary_carry, carry_out) }
+}
+
+// One core reused for every case, new() clears 64K which would dominate the run time
+struct Harness {
+    core: I8080Core,
+}
+
+impl Harness {
+    fn new(model: CpuModel) -> Self {
+        let mut core = I8080Core::new();
+        core.model = model;
+        Harness { core }
+    }
+
+    // Runs the instruction with A, B and the incoming flags set up and returns what it left
+    fn run(&mut self, program: &[u8], a: u8, b: u8, flags_in: u8) -> Outcome {
+        let core = &mut self.core;
+        core.memory[..program.len()].copy_from_slice(program);
+        core.program_counter = 0;
+        core.a = a;
+        core.b = b;
+        core.set_flags_byte(flags_in);
+        core.i8080_step();
+        assert_eq!(core.program_counter, program.len() as u16);
+        Outcome { a: core.a, flags: core.flags_byte() }
+    }
+}
+
+const MNEMONICS: [&str; 8] = ["ADD", "ADC", "SUB", "SBB", "ANA", "XRA", "ORA", "CMP"];
+const IMMEDIATE_MNEMONICS: [&str; 8] = ["ADI", "ACI", "SUI", "SBI", "ANI", "XRI", "ORI", "CPI"];
+
+fn check_alu(model: CpuModel) {
+    let mut harness = Harness::new(model);
+    for operation in 0..8u8 {
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                for carry in [false, true] {
+                    // the other flags going in are set opposite to what most results give so a
+                    // flag the ALU forgets to write shows up
+                    let flags_in = 0xD6 | carry as u8;
+                    let expected = alu(operation, a, b, carry, model);
+
+                    let register = harness.run(&[0x80 | operation << 3], a, b, flags_in);
+                    assert_eq!(
+                        register, expected,
+                        "{:?} {} B with A=0x{:02X} B=0x{:02X} CY={}", model, MNEMONICS[operation as usize], a, b, carry
+                    );
+                    let immediate = harness.run(&[0xC6 | operation << 3, b], a, 0, flags_in);
+                    assert_eq!(
+                        immediate, expected,
+                        "{:?} {} 0x{:02X} with A=0x{:02X} CY={}", model, IMMEDIATE_MNEMONICS[operation as usize], b, a, carry
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn alu_operations_8080() {
+    check_alu(CpuModel::I8080);
+}
+
+#[test]
+fn alu_operations_8085() {
+    check_alu(CpuModel::I8085);
+}
+
+#[test]
+fn increment_and_decrement() {
+    let mut harness = Harness::new(CpuModel::I8080);
+    for value in 0..=255u8 {
+        for flags_in in [0x02, 0xD7] {
+            // INR B and DCR B leave the carry alone
+            let carry = flags_in & 0x01 != 0;
+            let run_on_b = |harness: &mut Harness, opcode: u8| {
+                harness.run(&[opcode], 0x00, value, flags_in);
+                Outcome { a: harness.core.b, flags: harness.core.flags_byte() }
+            };
+
+            let result = value.wrapping_add(1);
+            let expected = Outcome { a: result, flags: flags(result, value & 0x0F == 0x0F, carry) };
+            assert_eq!(run_on_b(&mut harness, 0x04), expected, "INR B with B=0x{:02X} flags 0x{:02X}", value, flags_in);
+
+            let result = value.wrapping_sub(1);
+            let expected = Outcome { a: result, flags: flags(result, value & 0x0F != 0x00, carry) };
+            assert_eq!(run_on_b(&mut harness, 0x05), expected, "DCR B with B=0x{:02X} flags 0x{:02X}", value, flags_in);
+        }
+    }
+}
+
+#[test]
+fn decimal_adjust() {
+    let mut harness = Harness::new(CpuModel::I8080);
+    for a in 0..=255u8 {
+        for auxiliary_carry in [false, true] {
+            for carry in [false, true] {
+                let flags_in = 0x02 | (auxiliary_carry as u8) << 4 | carry as u8;
+                let expected = daa(a, auxiliary_carry, carry);
+                assert_eq!(
+                    harness.run(&[0x27], a, 0, flags_in),
+                    expected,
+                    "DAA with A=0x{:02X} AC={} CY={}",
+                    a,
+                    auxiliary_carry,
+                    carry
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use i8080_emulator::altair::SerialBackend;
+use i8080_emulator::cpm::disk::{DiskImage, DISK_PARAMETER_BLOCK, DISK_SIZE, GEOMETRY, SECTOR_SIZE};
+use i8080_emulator::cpm::{Cpm, COMMAND_TAIL_MAX};
+use i8080_emulator::StepInstructionResult;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs;
+use std::rc::Rc;
+
+// Stand in for the CCP on a system disk, calls the BIOS at 0xFA00 directly: prints 'O', selects
+// A:, reads track 2 sector 7 to 0x2000 and echoes one typed character
+const BOOT_PROGRAM: [u8; 45] = [
+    0x0E, 0x4F,       // MVI C,'O'
+    0xCD, 0x0C, 0xFA, // CALL CONOUT
+    0x0E, 0x00,       // MVI C,00
+    0xCD, 0x1B, 0xFA, // CALL SELDSK
+    0x22, 0x40, 0x00, // SHLD 0040
+    0x01, 0x02, 0x00, // LXI B,0002
+    0xCD, 0x1E, 0xFA, // CALL SETTRK
+    0x01, 0x07, 0x00, // LXI B,0007
+    0xCD, 0x21, 0xFA, // CALL SETSEC
+    0x01, 0x00, 0x20, // LXI B,2000
+    0xCD, 0x24, 0xFA, // CALL SETDMA
+    0xCD, 0x27, 0xFA, // CALL READ
+    0x32, 0x42, 0x00, // STA 0042
+    0xCD, 0x09, 0xFA, // CALL CONIN
+    0x4F,             // MOV C,A
+    0xCD, 0x0C, 0xFA, // CALL CONOUT
+    0x76,             // HLT
+];
+
+// Writes the 128 bytes at the default DMA address (0x0080) to track 3 sector 1 and keeps the BIOS result at 0x0042
+const WRITE_PROGRAM: [u8; 24] = [
+    0x0E, 0x00,       // MVI C,00
+    0xCD, 0x1B, 0xFA, // CALL SELDSK
+    0x01, 0x03, 0x00, // LXI B,0003
+    0xCD, 0x1E, 0xFA, // CALL SETTRK
+    0x01, 0x01, 0x00, // LXI B,0001
+    0xCD, 0x21, 0xFA, // CALL SETSEC
+    0xCD, 0x2A, 0xFA, // CALL WRITE
+    0x32, 0x42, 0x00, // STA 0042
+    0x76,             // HLT
+];
+
+// Prints HELLO through BDOS function 9 and ! through function 2 then returns to CP/M
+const HELLO_COM: [u8; 22] = [
+    0x0E, 0x09,       // 0100 MVI C,09
+    0x11, 0x10, 0x01, // 0102 LXI D,0110
+    0xCD, 0x05, 0x00, // 0105 CALL 0005
+    0x0E, 0x02,       // 0108 MVI C,02
+    0x1E, 0x21,       // 010A MVI E,'!'
+    0xCD, 0x05, 0x00, // 010C CALL 0005
+    0xC9,             // 010F RET
+    b'H', b'E', b'L', b'L', b'O', b'$',
+];
+
+/*
+ * Console with the output shared so the test can look at it after the machine has taken the box
+ */
+struct TestConsole {
+    input: VecDeque<u8>,
+    output: Rc<RefCell<Vec<u8>>>,
+}
+
+impl SerialBackend for TestConsole {
+    fn receive(&mut self) -> Option<u8> {
+        self.input.pop_front()
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        self.output.borrow_mut().push(byte);
+    }
+}
+
+fn machine(input: &[u8]) -> (Cpm, Rc<RefCell<Vec<u8>>>) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let console = TestConsole {
+        input: input.iter().copied().collect(),
+        output: output.clone(),
+    };
+    (Cpm::new(Box::new(console)), output)
+}
+
+// Builds a disk with program as the system, it lands at the CCP base on boot
+fn system_disk(program: &[u8]) -> Vec<u8> {
+    let mut image = vec![0xE5; DISK_SIZE];
+    let system = GEOMETRY.sector_offset(0, 2).unwrap();
+    image[system..system + program.len()].copy_from_slice(program);
+    image
+}
+
+fn run_until_halt(cpm: &mut Cpm) {
+    for _ in 0..100_000 {
+        if cpm.step() == StepInstructionResult::Halt {
+            return;
+        }
+    }
+    panic!("program never halted, PC is 0x{:04X}", cpm.core.program_counter);
+}
+
+#[test]
+fn boots_system_tracks_and_reads_through_bios() {
+    let mut image = system_disk(&BOOT_PROGRAM);
+    let data = GEOMETRY.sector_offset(2, 7).unwrap();
+    for i in 0..SECTOR_SIZE {
+        image[data + i] = i as u8;
+    }
+
+    let (mut cpm, output) = machine(b"x");
+    cpm.insert(0, DiskImage::from_bytes(&image, GEOMETRY));
+    assert!(cpm.boot() == i8080_emulator::LoadRomResult::Ok);
+    assert_eq!(cpm.core.program_counter, 0xE400);
+    run_until_halt(&mut cpm);
+
+    assert_eq!(&output.borrow()[..], b"Ox");
+    assert_eq!(cpm.core.memory[0x0042], 0);
+    let expected: Vec<u8> = (0..SECTOR_SIZE).map(|i| i as u8).collect();
+    assert_eq!(&cpm.core.memory[0x2000..0x2000 + SECTOR_SIZE], &expected[..]);
+
+    // SELDSK handed back a header whose DPB pointer leads to the 3740 parameters
+    let dph = u16::from_le_bytes([cpm.core.memory[0x40], cpm.core.memory[0x41]]) as usize;
+    let dpb = u16::from_le_bytes([cpm.core.memory[dph + 10], cpm.core.memory[dph + 11]]) as usize;
+    assert_eq!(&cpm.core.memory[dpb..dpb + DISK_PARAMETER_BLOCK.len()], &DISK_PARAMETER_BLOCK);
+}
+
+#[test]
+fn writes_sector_back_to_image_file() {
+    let path = std::env::temp_dir().join(format!("i8080_cpm_write_{}.dsk", std::process::id()));
+    fs::write(&path, system_disk(&WRITE_PROGRAM)).unwrap();
+    let path = path.to_string_lossy().into_owned();
+
+    let (mut cpm, _) = machine(b"");
+    cpm.insert(0, DiskImage::open(&path, false, GEOMETRY).unwrap());
+    cpm.boot();
+    for i in 0..SECTOR_SIZE {
+        cpm.core.memory[0x0080 + i] = 0xFF - i as u8;
+    }
+    run_until_halt(&mut cpm);
+
+    let image = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(cpm.core.memory[0x0042], 0);
+    let offset = GEOMETRY.sector_offset(3, 1).unwrap();
+    let expected: Vec<u8> = (0..SECTOR_SIZE).map(|i| 0xFF - i as u8).collect();
+    assert_eq!(&image[offset..offset + SECTOR_SIZE], &expected[..]);
+}
+
+#[test]
+fn write_to_read_only_disk_fails() {
+    let (mut cpm, _) = machine(b"");
+    let mut disk = DiskImage::from_bytes(&system_disk(&WRITE_PROGRAM), GEOMETRY);
+    disk.read_only = true;
+    cpm.insert(0, disk);
+    cpm.boot();
+    run_until_halt(&mut cpm);
+    assert_eq!(cpm.core.memory[0x0042], 1);
+}
+
+#[test]
+fn runs_com_file_with_bdos_console_calls() {
+    let path = std::env::temp_dir().join(format!("i8080_cpm_hello_{}.com", std::process::id()));
+    fs::write(&path, HELLO_COM).unwrap();
+
+    let (mut cpm, output) = machine(b"");
+    cpm.load_com(&path.to_string_lossy());
+    fs::remove_file(&path).unwrap();
+    for _ in 0..1000 {
+        cpm.step();
+        if cpm.core.program_counter == 0 {
+            break;
+        }
+    }
+
+    assert_eq!(cpm.core.program_counter, 0);
+    assert_eq!(&output.borrow()[..], b"HELLO!");
+}
+
+// Loads program as a .COM file at 0x0100 through a temporary file
+fn load_com(cpm: &mut Cpm, name: &str, program: &[u8]) {
+    let path = std::env::temp_dir().join(format!("i8080_cpm_{}_{}.com", name, std::process::id()));
+    fs::write(&path, program).unwrap();
+    cpm.load_com(&path.to_string_lossy());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn command_tail_stops_short_of_the_program() {
+    let (mut cpm, _) = machine(b"");
+    load_com(&mut cpm, "tail", &HELLO_COM);
+
+    // with its leading space a 125 character tail exactly fills the buffer, the NUL lands on 0x00FF
+    for (length, stored) in [(124, 125), (125, COMMAND_TAIL_MAX), (126, COMMAND_TAIL_MAX), (300, COMMAND_TAIL_MAX)] {
+        cpm.core.memory[0x0080..0x0100].fill(0xAA);
+        cpm.set_command_line(&"x".repeat(length));
+        assert_eq!(cpm.core.memory[0x0080] as usize, stored, "tail of {}", length);
+        assert_eq!(cpm.core.memory[0x0081], b' ');
+        assert!(cpm.core.memory[0x0082..0x0081 + stored].iter().all(|byte| *byte == b'X'));
+        assert_eq!(cpm.core.memory[0x0081 + stored], 0);
+        assert_eq!(&cpm.core.memory[0x0100..0x0100 + HELLO_COM.len()], &HELLO_COM);
+    }
+}
+
+#[test]
+fn print_string_stops_at_the_top_of_memory() {
+    // MVI C,09, LXI D,FFF0, CALL 0005, RET with no '$' between 0xFFF0 and the end of memory
+    let (mut cpm, output) = machine(b"");
+    load_com(&mut cpm, "print", &[0x0E, 0x09, 0x11, 0xF0, 0xFF, 0xCD, 0x05, 0x00, 0xC9, b'$']);
+    cpm.core.memory[0xFFF0..].fill(b'A');
+    for _ in 0..1000 {
+        cpm.step();
+        if cpm.core.program_counter == 0 {
+            break;
+        }
+    }
+
+    assert_eq!(cpm.core.program_counter, 0);
+    assert_eq!(&output.borrow()[..], &[b'A'; 16]);
+}
+
+#[test]
+fn bios_tables_wrap_past_the_top_of_memory() {
+    // the BIOS page starts at 0xFF00 and its disk parameter headers run past 0xFFFF
+    let (mut cpm, _) = machine(b"");
+    cpm.ccp_base = 0xE900;
+    load_com(&mut cpm, "wrap", &HELLO_COM);
+    assert_eq!(cpm.bios_base(), 0xFF00);
+    assert_eq!(&cpm.core.memory[0xFF00..0xFF03], &[0xC3, 0x00, 0xFF]);
+    assert_eq!(&cpm.core.memory[0x0000..0x0003], &[0xC3, 0x03, 0xFF]);
+    assert_eq!(&cpm.core.memory[0x0100..0x0100 + HELLO_COM.len()], &HELLO_COM);
+}
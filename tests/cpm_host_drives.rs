@@ -0,0 +1,184 @@
+use i8080_emulator::altair::BufferSerial;
+use i8080_emulator::cpm::host::{name_matches, to_cpm_name, to_host_name, HostDrive};
+use i8080_emulator::cpm::{parse_fcb, Cpm};
+use std::fs;
+use std::path::PathBuf;
+
+const FCB: u16 = 0x005C;
+const DMA: u16 = 0x2000;
+const RETURN_ADDRESS: u16 = 0x1234;
+
+fn temp_directory(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("i8080_cpm_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+    path
+}
+
+fn machine(drives: Vec<HostDrive>) -> Cpm {
+    let mut cpm = Cpm::new(Box::new(BufferSerial::new(b"")));
+    cpm.bdos_trap = true;
+    cpm.host_drives.mount_sequence(drives);
+    cpm
+}
+
+// Calls the BDOS the way a program does and returns what it left in A
+fn bdos(cpm: &mut Cpm, function: u8, de: u16) -> u8 {
+    cpm.core.c = function;
+    cpm.core.d = (de >> 8) as u8;
+    cpm.core.e = de as u8;
+    cpm.core.stack_pointer = 0xD000;
+    cpm.core.memory[0xD000..0xD002].copy_from_slice(&RETURN_ADDRESS.to_le_bytes());
+    cpm.core.program_counter = cpm.bdos_entry();
+    cpm.step();
+    assert_eq!(cpm.core.program_counter, RETURN_ADDRESS);
+    cpm.core.a
+}
+
+fn set_fcb(cpm: &mut Cpm, name: &str) {
+    let start = FCB as usize;
+    cpm.core.memory[start..start + 36].fill(0);
+    cpm.core.memory[start..start + 16].copy_from_slice(&parse_fcb(name));
+}
+
+#[test]
+fn translates_names_to_and_from_8_3() {
+    assert_eq!(&to_cpm_name("hello.asm").unwrap(), b"HELLO   ASM");
+    assert_eq!(&to_cpm_name("README").unwrap(), b"README     ");
+    assert_eq!(to_cpm_name("toolongname.txt"), None);
+    assert_eq!(to_cpm_name("notes.text"), None);
+    assert_eq!(to_cpm_name("a b.txt"), None);
+    assert_eq!(to_host_name(b"HELLO   ASM"), "hello.asm");
+    assert_eq!(to_host_name(b"README     "), "readme");
+    assert!(name_matches(&parse_fcb("*.ASM")[1..12].try_into().unwrap(), b"HELLO   ASM"));
+    assert!(!name_matches(&parse_fcb("H?LLO.COM")[1..12].try_into().unwrap(), b"HELLO   ASM"));
+}
+
+#[test]
+fn writes_and_reads_files_sequentially() {
+    let directory = temp_directory("sequential");
+    let mut cpm = machine(vec![HostDrive::new(&directory, false)]);
+    bdos(&mut cpm, 26, DMA);
+
+    set_fcb(&mut cpm, "TEST.TXT");
+    assert_eq!(bdos(&mut cpm, 22, FCB), 0);
+    for record in 0..2u8 {
+        cpm.core.memory[DMA as usize..DMA as usize + 128].fill(b'a' + record);
+        assert_eq!(bdos(&mut cpm, 21, FCB), 0);
+    }
+    assert_eq!(bdos(&mut cpm, 16, FCB), 0);
+    let contents = fs::read(directory.join("test.txt")).unwrap();
+    assert_eq!(contents.len(), 256);
+    assert!(contents[128..].iter().all(|byte| *byte == b'b'));
+
+    // a short host file reads back padded with ^Z and then hits the end
+    fs::write(directory.join("Short.Txt"), b"hi").unwrap();
+    set_fcb(&mut cpm, "SHORT.TXT");
+    assert_eq!(bdos(&mut cpm, 15, FCB), 0);
+    assert_eq!(cpm.core.memory[FCB as usize + 15], 1);
+    assert_eq!(bdos(&mut cpm, 20, FCB), 0);
+    assert_eq!(&cpm.core.memory[DMA as usize..DMA as usize + 3], b"hi\x1A");
+    assert_eq!(bdos(&mut cpm, 20, FCB), 1);
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn random_access_and_file_size() {
+    let directory = temp_directory("random");
+    let data: Vec<u8> = (0..128 * 300).map(|i| (i / 128) as u8).collect();
+    fs::write(directory.join("big.dat"), &data).unwrap();
+    let mut cpm = machine(vec![HostDrive::new(&directory, false)]);
+    bdos(&mut cpm, 26, DMA);
+
+    set_fcb(&mut cpm, "BIG.DAT");
+    assert_eq!(bdos(&mut cpm, 15, FCB), 0);
+    assert_eq!(bdos(&mut cpm, 35, FCB), 0);
+    assert_eq!(&cpm.core.memory[FCB as usize + 33..FCB as usize + 36], &[44, 1, 0]);
+
+    cpm.core.memory[FCB as usize + 33..FCB as usize + 36].copy_from_slice(&[0x2C, 0x01, 0]);
+    assert_eq!(bdos(&mut cpm, 33, FCB), 1);
+    cpm.core.memory[FCB as usize + 33..FCB as usize + 36].copy_from_slice(&[200, 0, 0]);
+    assert_eq!(bdos(&mut cpm, 33, FCB), 0);
+    assert_eq!(cpm.core.memory[DMA as usize], 200);
+    // the sequential position follows the random read into extent 1
+    assert_eq!(cpm.core.memory[FCB as usize + 12], 1);
+    assert_eq!(cpm.core.memory[FCB as usize + 32], 72);
+
+    cpm.core.memory[DMA as usize..DMA as usize + 128].fill(0xAA);
+    cpm.core.memory[FCB as usize + 33..FCB as usize + 36].copy_from_slice(&[5, 0, 0]);
+    assert_eq!(bdos(&mut cpm, 34, FCB), 0);
+    let contents = fs::read(directory.join("big.dat")).unwrap();
+    assert!(contents[5 * 128..6 * 128].iter().all(|byte| *byte == 0xAA));
+    assert_eq!(contents.len(), data.len());
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn user_numbers_are_subdirectories() {
+    let directory = temp_directory("users");
+    let mut cpm = machine(vec![HostDrive::new(&directory, false)]);
+    bdos(&mut cpm, 26, DMA);
+
+    cpm.core.e = 3;
+    bdos(&mut cpm, 32, 0x0003);
+    set_fcb(&mut cpm, "PROG.ASM");
+    assert_eq!(bdos(&mut cpm, 22, FCB), 0);
+    assert!(directory.join("3").join("prog.asm").exists());
+
+    set_fcb(&mut cpm, "*.*");
+    assert_eq!(bdos(&mut cpm, 17, FCB), 0);
+    assert_eq!(cpm.core.memory[DMA as usize], 3);
+    assert_eq!(&cpm.core.memory[DMA as usize + 1..DMA as usize + 12], b"PROG    ASM");
+    assert_eq!(bdos(&mut cpm, 18, FCB), 0xFF);
+    assert_eq!(bdos(&mut cpm, 32, 0x00FF), 3);
+
+    bdos(&mut cpm, 32, 0x0000);
+    set_fcb(&mut cpm, "*.*");
+    assert_eq!(bdos(&mut cpm, 17, FCB), 0xFF);
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn read_only_drives_and_files_refuse_writes() {
+    let read_only = temp_directory("read_only");
+    let writable = temp_directory("writable");
+    fs::write(read_only.join("keep.txt"), b"keep").unwrap();
+    fs::write(writable.join("locked.txt"), b"locked").unwrap();
+    let mut permissions = fs::metadata(writable.join("locked.txt")).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(writable.join("locked.txt"), permissions).unwrap();
+
+    let mut cpm = machine(vec![HostDrive::new(&read_only, true), HostDrive::new(&writable, false)]);
+    bdos(&mut cpm, 26, DMA);
+
+    set_fcb(&mut cpm, "NEW.TXT");
+    assert_eq!(bdos(&mut cpm, 22, FCB), 0xFF);
+    set_fcb(&mut cpm, "KEEP.TXT");
+    assert_eq!(bdos(&mut cpm, 19, FCB), 0xFF);
+    assert!(read_only.join("keep.txt").exists());
+
+    // B: through the FCB drive byte, the locked file shows the read only attribute
+    set_fcb(&mut cpm, "B:LOCKED.TXT");
+    assert_eq!(bdos(&mut cpm, 15, FCB), 0);
+    assert_eq!(bdos(&mut cpm, 21, FCB), 0xFF);
+    assert_eq!(bdos(&mut cpm, 17, FCB), 0);
+    assert_eq!(cpm.core.memory[DMA as usize + 9], b'T' | 0x80);
+
+    assert_eq!(bdos(&mut cpm, 24, 0), 0b11);
+    assert_eq!(bdos(&mut cpm, 29, 0), 0b01);
+    cpm.core.e = 1;
+    assert_eq!(bdos(&mut cpm, 14, 0x0001), 0);
+    assert_eq!(bdos(&mut cpm, 25, 0), 1);
+    cpm.core.e = 2;
+    assert_eq!(bdos(&mut cpm, 14, 0x0002), 0xFF);
+
+    let mut permissions = fs::metadata(writable.join("locked.txt")).unwrap().permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(writable.join("locked.txt"), permissions).unwrap();
+    fs::remove_dir_all(&read_only).unwrap();
+    fs::remove_dir_all(&writable).unwrap();
+}
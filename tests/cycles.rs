@@ -0,0 +1,37 @@
+use i8080_emulator::{I8080Core, StepInstructionResult, CYCLE_TABLE};
+
+#[test]
+fn counts_cycles_of_a_known_program() {
+    // LXI SP,2000h; MVI A,1; ORA A; CZ 0010h; CNZ 0010h; HLT with RZ; RNZ at 0010h
+    let mut core = I8080Core::new();
+    core.memory[..10].copy_from_slice(&[0x31, 0x00, 0x20, 0x3E, 0x01, 0xB7, 0xCC, 0x10, 0x00, 0xC4]);
+    core.memory[10..13].copy_from_slice(&[0x10, 0x00, 0x76]);
+    core.memory[0x10..0x12].copy_from_slice(&[0xC8, 0xC0]);
+
+    // (opcode, taken) in the order they execute, Z is clear after ORA A so CZ and RZ fall through
+    let executed = [
+        (0x31, false),
+        (0x3E, false),
+        (0xB7, false),
+        (0xCC, false),
+        (0xC4, true),
+        (0xC8, false),
+        (0xC0, true),
+        (0x76, false),
+    ];
+    for (opcode, taken) in executed {
+        assert_eq!(core.memory[core.program_counter as usize], opcode);
+        let before = core.cycles;
+        let result = core.i8080_step();
+        // a taken conditional CALL or RET costs 6 more than its CYCLE_TABLE entry
+        let expected = CYCLE_TABLE[opcode as usize] as u64 + if taken { 6 } else { 0 };
+        assert_eq!(core.cycles - before, expected, "opcode {:02X}", opcode);
+        assert!(!result.is_failure());
+    }
+    assert_eq!(core.cycles, 10 + 7 + 4 + 11 + 17 + 5 + 11 + 7);
+    assert!(core.halted);
+
+    // the halted core keeps burning 4 cycles a step
+    assert!(core.i8080_step() == StepInstructionResult::Halt);
+    assert_eq!(core.cycles, 76);
+}
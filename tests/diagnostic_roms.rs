@@ -0,0 +1,155 @@
+use i8080_emulator::altair::SerialBackend;
+use i8080_emulator::cpm::Cpm;
+use i8080_emulator::{LoadRomResult, StepInstructionResult, UndocumentedOpcodes};
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+/*
+ * The CP/M CPU diagnostics, run when I8080_TEST_ROMS names a directory holding TST8080.COM,
+ * CPUTEST.COM, 8080PRE.COM and 8080EXM.COM. Any that aren't there are skipped. 8080EXM runs a few
+ * billion instructions so use cargo test --release for it.
+ */
+const ROM_DIRECTORY: &str = "I8080_TEST_ROMS";
+
+// The CRCs 8080EXM prints for each of its tests on a real 8080
+const EXERCISER_CRCS: [(&str, &str); 25] = [
+    ("dad <b,d,h,sp>", "14474ba6"),
+    ("aluop nn", "9e922f9e"),
+    ("aluop <b,c,d,e,h,l,m,a>", "cf762c86"),
+    ("<daa,cma,stc,cmc>", "bb3f030c"),
+    ("<inr,dcr> a", "adb6460e"),
+    ("<inr,dcr> b", "83ed1345"),
+    ("<inx,dcx> b", "f79287cd"),
+    ("<inr,dcr> c", "e5f6721b"),
+    ("<inr,dcr> d", "15b5579a"),
+    ("<inx,dcx> d", "7f4e2501"),
+    ("<inr,dcr> e", "cf2ab396"),
+    ("<inr,dcr> h", "12b2952c"),
+    ("<inx,dcx> h", "9f2b23c0"),
+    ("<inr,dcr> l", "ff57d356"),
+    ("<inr,dcr> m", "92e963bd"),
+    ("<inx,dcx> sp", "d5702fab"),
+    ("lhld nnnn", "a9c3d5cb"),
+    ("shld nnnn", "e8864f26"),
+    ("lxi <b,d,h,sp>,nnnn", "fcf46e12"),
+    ("ldax <b,d>", "2b821d5f"),
+    ("mvi <b,c,d,e,h,l,m,a>,nn", "eaa72044"),
+    ("mov <bcdehla>,<bcdehla>", "10b58cee"),
+    ("sta nnnn / lda nnnn", "ed57af72"),
+    ("<rlc,rrc,ral,rar>", "e0d89235"),
+    ("stax <b,d>", "2b0471e9"),
+];
+
+/*
+ * Console that keeps what the program prints where the test can read it after the machine took the box
+ */
+struct CapturedConsole {
+    output: Rc<RefCell<Vec<u8>>>,
+}
+
+impl SerialBackend for CapturedConsole {
+    fn receive(&mut self) -> Option<u8> {
+        None
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        self.output.borrow_mut().push(byte);
+    }
+}
+
+// Instruction budgets, a few times what each diagnostic needs on a working core
+const TST8080_BUDGET: u64 = 1_000_000;
+const CPUTEST_BUDGET: u64 = 200_000_000;
+const PRELIM_BUDGET: u64 = 1_000_000;
+const EXERCISER_BUDGET: u64 = 10_000_000_000;
+
+/*
+ * run_rom - Runs the diagnostic until it warm boots through 0x0000 and returns what it printed, None
+ * when I8080_TEST_ROMS isn't set or the ROM isn't in it. Panics with where it got to and what it had
+ * printed if it is still going after budget instructions
+ */
+fn run_rom(name: &str, budget: u64) -> Option<String> {
+    let Some(directory) = std::env::var_os(ROM_DIRECTORY) else {
+        eprintln!("skipping {}: {} is not set", name, ROM_DIRECTORY);
+        return None;
+    };
+    let path = Path::new(&directory).join(name);
+    if !path.is_file() {
+        eprintln!("skipping {}: not found in {}", name, path.parent().unwrap().display());
+        return None;
+    }
+
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut cpm = Cpm::new(Box::new(CapturedConsole { output: output.clone() }));
+    // the diagnostics never use the undocumented opcodes so landing on one means a bad jump
+    cpm.core.undocumented_opcodes = UndocumentedOpcodes::Trap;
+    assert!(cpm.load_com(&path.to_string_lossy()) == LoadRomResult::Ok, "failed to load {}", name);
+
+    let mut instructions: u64 = 0;
+    loop {
+        if instructions == budget {
+            panic!(
+                "{} still running after {} instructions, PC is 0x{:04X}, output so far:\n{}",
+                name,
+                budget,
+                cpm.core.program_counter,
+                String::from_utf8_lossy(&output.borrow())
+            );
+        }
+        instructions += 1;
+        match cpm.step() {
+            StepInstructionResult::Halt => panic!("{} halted at 0x{:04X}", name, cpm.core.program_counter),
+            StepInstructionResult::Error => panic!("{} step failed at 0x{:04X}", name, cpm.core.program_counter),
+            StepInstructionResult::IllegalOpcode { pc, opcode } => panic!("{} hit illegal opcode 0x{:02X} at 0x{:04X}", name, opcode, pc),
+            StepInstructionResult::UnmappedPort { pc, port } => panic!("{} hit unmapped port 0x{:02X} at 0x{:04X}", name, port, pc),
+            _ => {}
+        }
+        if cpm.core.program_counter == 0 {
+            break;
+        }
+    }
+    let output = String::from_utf8_lossy(&output.borrow()).into_owned();
+    Some(output)
+}
+
+#[test]
+fn tst8080() {
+    if let Some(output) = run_rom("TST8080.COM", TST8080_BUDGET) {
+        assert!(output.contains("CPU IS OPERATIONAL"), "{}", output);
+    }
+}
+
+#[test]
+fn cputest() {
+    if let Some(output) = run_rom("CPUTEST.COM", CPUTEST_BUDGET) {
+        assert!(output.contains("CPU IS 8080/8085"), "{}", output);
+        assert!(output.contains("CPU TESTS OK"), "{}", output);
+    }
+}
+
+#[test]
+fn prelim() {
+    if let Some(output) = run_rom("8080PRE.COM", PRELIM_BUDGET) {
+        assert!(output.contains("8080 Preliminary tests complete"), "{}", output);
+    }
+}
+
+#[test]
+fn exerciser() {
+    let Some(output) = run_rom("8080EXM.COM", EXERCISER_BUDGET) else {
+        return;
+    };
+    // each test prints "name.....  PASS! crc is:xxxxxxxx" or "name.....  ERROR **** crc expected:... found:..."
+    let results: Vec<&str> = output.lines().filter(|line| line.contains("..")).collect();
+    let mut failures = Vec::new();
+    for (name, crc) in EXERCISER_CRCS {
+        match results.iter().find(|line| line.starts_with(&format!("{}.", name))) {
+            Some(line) if line.contains("PASS!") && line.ends_with(&format!("crc is:{}", crc)) => {}
+            Some(line) => failures.push(line.trim().to_string()),
+            None => failures.push(format!("{} never ran", name)),
+        }
+    }
+    assert!(failures.is_empty(), "8080EXM failures:\n{}", failures.join("\n"));
+    assert!(output.contains("Tests complete"), "{}", output);
+}
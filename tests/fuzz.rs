@@ -0,0 +1,63 @@
+use i8080_emulator::fuzz::{fuzz_image, XorShift};
+use i8080_emulator::{CpuModel, I8080Core};
+
+#[test]
+fn operands_and_stack_wrap_at_64k() {
+    for model in [CpuModel::I8080, CpuModel::I8085, CpuModel::Z80] {
+        let mut core = I8080Core::new();
+        core.model = model;
+        // LXI B at the very top takes its operand from 0x0000/0x0001
+        core.memory[0xFFFF] = 0x01;
+        core.memory[0x0000..0x0002].copy_from_slice(&[0x34, 0x12]);
+        core.program_counter = 0xFFFF;
+        core.i8080_step();
+        assert_eq!((core.b, core.c), (0x12, 0x34));
+        assert_eq!(core.program_counter, 0x0002);
+
+        // CALL from 0xFFFE with SP at 0x0001 pushes across the wrap, RET pops it back
+        core.memory[0xFFFE] = 0xCD;
+        core.memory[0xFFFF] = 0x00;
+        core.memory[0x0000] = 0x40;
+        core.memory[0x4000] = 0xC9;
+        core.program_counter = 0xFFFE;
+        core.stack_pointer = 0x0001;
+        core.i8080_step();
+        assert_eq!(core.program_counter, 0x4000);
+        assert_eq!(core.stack_pointer, 0xFFFF);
+        assert_eq!((core.memory[0x0000], core.memory[0xFFFF]), (0x00, 0x01));
+        core.i8080_step();
+        assert_eq!(core.program_counter, 0x0001);
+        assert_eq!(core.stack_pointer, 0x0001);
+
+        // SHLD/LHLD at 0xFFFF store and load the high byte at 0x0000
+        core.memory[0x0100..0x0106].copy_from_slice(&[0x22, 0xFF, 0xFF, 0x2A, 0xFF, 0xFF]);
+        core.program_counter = 0x0100;
+        core.h = 0xAB;
+        core.l = 0xCD;
+        core.i8080_step();
+        assert_eq!((core.memory[0xFFFF], core.memory[0x0000]), (0xCD, 0xAB));
+        core.h = 0;
+        core.l = 0;
+        core.i8080_step();
+        assert_eq!((core.h, core.l), (0xAB, 0xCD));
+    }
+}
+
+#[test]
+fn random_images_never_panic() {
+    for seed in 1..=200 {
+        fuzz_image(seed, 2_000);
+    }
+}
+
+#[test]
+fn fuzz_runs_are_reproducible() {
+    let first = fuzz_image(42, 5_000);
+    let second = fuzz_image(42, 5_000);
+    assert_eq!(first.program_counter, second.program_counter);
+    assert_eq!(first.cycles, second.cycles);
+    assert!(first.memory == second.memory);
+
+    let mut random = XorShift::new(0);
+    assert_ne!(random.next_u64(), random.next_u64());
+}
@@ -0,0 +1,135 @@
+use i8080_emulator::i8085::{RST55_VECTOR, RST65_VECTOR, RST75_VECTOR, TRAP_VECTOR};
+use i8080_emulator::{CpuModel, I8080Core};
+
+fn core_8085(program: &[u8]) -> I8080Core {
+    let mut core = I8080Core::new();
+    core.model = CpuModel::I8085;
+    core.memory[..program.len()].copy_from_slice(program);
+    core.stack_pointer = 0x2000;
+    core
+}
+
+#[test]
+fn rim_and_sim() {
+    // MVI A,0x59 (MSE, reset 7.5, mask 5.5), SIM, MVI A,0xC0 (SOE, SOD), SIM, RIM
+    let mut core = core_8085(&[0x3E, 0x59, 0x30, 0x3E, 0xC0, 0x30, 0x20]);
+    core.i8085_rst75();
+    core.i8085.rst65 = true;
+    core.i8085.sid = true;
+    for _ in 0..3 {
+        core.i8080_step();
+    }
+    assert_eq!(core.i8085.masks, 0x01);
+    assert!(!core.i8085.rst75);
+    assert!(!core.i8085.sod);
+    core.i8080_step();
+    core.i8080_step();
+    assert!(core.i8085.sod);
+    assert_eq!(core.i8085.masks, 0x01);
+
+    core.i8080_step();
+    assert_eq!(core.a, 0x80 | 0x20 | 0x01);
+}
+
+#[test]
+fn rim_and_sim_are_nops_on_the_8080() {
+    let mut core = core_8085(&[0x3E, 0x08, 0x30, 0x20]);
+    core.model = CpuModel::I8080;
+    core.i8080_step();
+    core.i8080_step();
+    core.i8080_step();
+    assert_eq!(core.i8085.masks, 0x07);
+    assert_eq!(core.a, 0x08);
+}
+
+#[test]
+fn interrupt_priorities_and_masks() {
+    // EI, then a run of NOPs
+    let mut core = core_8085(&[0xFB]);
+    core.i8085.rst55 = true;
+    core.i8085.rst65 = true;
+    core.i8085_rst75();
+    core.i8080_step();
+    core.i8080_step();
+    // everything is masked after reset
+    assert_eq!(core.program_counter, 2);
+
+    core.i8085.masks = 0;
+    core.interrupts_enabled = true;
+    core.i8080_step();
+    assert_eq!(core.program_counter, RST75_VECTOR);
+    assert!(!core.i8085.rst75);
+    assert!(!core.interrupts_enabled);
+    assert_eq!(&core.memory[0x1FFE..0x2000], &[0x02, 0x00]);
+
+    core.interrupts_enabled = true;
+    core.i8080_step();
+    assert_eq!(core.program_counter, RST65_VECTOR);
+    core.i8085.rst65 = false;
+    core.interrupts_enabled = true;
+    core.i8080_step();
+    assert_eq!(core.program_counter, RST55_VECTOR);
+}
+
+#[test]
+fn trap_is_non_maskable_and_wakes_hlt() {
+    // HLT
+    let mut core = core_8085(&[0x76]);
+    core.interrupts_enabled = true;
+    core.i8080_step();
+    assert!(core.halted);
+    core.i8085_trap();
+    core.i8080_step();
+    assert!(!core.halted);
+    assert_eq!(core.program_counter, TRAP_VECTOR);
+    assert!(!core.interrupts_enabled);
+
+    // the first RIM after the TRAP reports INTE from before it
+    core.memory[TRAP_VECTOR as usize] = 0x20;
+    core.memory[TRAP_VECTOR as usize + 1] = 0x20;
+    core.i8080_step();
+    assert_eq!(core.a & 0x08, 0x08);
+    core.i8080_step();
+    assert_eq!(core.a & 0x08, 0x00);
+}
+
+#[test]
+fn cycle_timings() {
+    // MOV B,C, INX B, PUSH B, CALL 0x0010, at 0x10: RNZ (taken), then JZ 0x0000 (not taken)
+    let mut core = core_8085(&[0x41, 0x03, 0xC5, 0xCD, 0x10, 0x00, 0xCA, 0x00, 0x00]);
+    core.memory[0x10] = 0xC0;
+    let mut timings = Vec::new();
+    for _ in 0..6 {
+        let before = core.cycles;
+        core.i8080_step();
+        timings.push(core.cycles - before);
+    }
+    assert_eq!(timings, [4, 6, 12, 18, 12, 7]);
+
+    let mut core = core_8085(&[0x41, 0x03, 0xC5]);
+    core.model = CpuModel::I8080;
+    for _ in 0..3 {
+        core.i8080_step();
+    }
+    assert_eq!(core.cycles, 5 + 5 + 11);
+}
+
+#[test]
+fn ana_sets_auxiliary_carry() {
+    // MVI A,0x01, ANI 0x01, MVI B,0x01, ANA B
+    let program = [0x3E, 0x01, 0xE6, 0x01, 0x06, 0x01, 0xA0];
+    let mut core = core_8085(&program);
+    core.i8080_step();
+    core.i8080_step();
+    assert!(core.auxiliary_carry);
+    core.auxiliary_carry = false;
+    core.i8080_step();
+    core.i8080_step();
+    assert!(core.auxiliary_carry);
+
+    let mut core = core_8085(&program);
+    core.model = CpuModel::I8080;
+    core.i8080_step();
+    core.i8080_step();
+    assert!(!core.auxiliary_carry);
+}
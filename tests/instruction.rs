@@ -0,0 +1,79 @@
+use i8080_emulator::instruction::{Cond, IndirectPair, Instruction, Reg, RegPair, StackPair};
+use i8080_emulator::{I8080Core, CYCLE_TABLE};
+
+#[test]
+fn every_opcode_round_trips() {
+    for opcode in 0..=255u8 {
+        let bytes = [opcode, 0x34, 0x12];
+        let instruction = Instruction::decode(&bytes);
+        let length = instruction.length() as usize;
+        assert_eq!(instruction.opcode(), opcode, "{}", instruction);
+        assert_eq!(instruction.encode(), bytes[..length], "{}", instruction);
+        assert_eq!(Instruction::decode(&instruction.encode()), instruction);
+        assert_eq!(instruction.cycles(), CYCLE_TABLE[opcode as usize]);
+    }
+}
+
+#[test]
+fn lengths_match_the_core() {
+    // run every instruction that doesn't jump and check the core moved past exactly its bytes
+    for opcode in 0..=255u8 {
+        let instruction = Instruction::decode(&[opcode, 0x00, 0x40]);
+        match instruction {
+            Instruction::Hlt
+            | Instruction::Pchl
+            | Instruction::Ret
+            | Instruction::Rcc(_)
+            | Instruction::Jmp(_)
+            | Instruction::Jcc(..)
+            | Instruction::Call(_)
+            | Instruction::Ccc(..)
+            | Instruction::Rst(_)
+            | Instruction::UndocumentedJmp(_)
+            | Instruction::UndocumentedRet
+            | Instruction::UndocumentedCall(..) => continue,
+            _ => {}
+        }
+        let mut core = I8080Core::new();
+        core.memory[0x1000..0x1003].copy_from_slice(&[opcode, 0x00, 0x40]);
+        core.program_counter = 0x1000;
+        core.stack_pointer = 0x8000;
+        core.i8080_step();
+        assert_eq!(core.program_counter, 0x1000 + instruction.length(), "{}", instruction);
+    }
+}
+
+#[test]
+fn decoded_operands() {
+    assert_eq!(Instruction::decode(&[0x7E]), Instruction::Mov(Reg::A, Reg::M));
+    assert_eq!(Instruction::decode(&[0x21, 0x34, 0x12]), Instruction::Lxi(RegPair::HL, 0x1234));
+    assert_eq!(Instruction::decode(&[0xEC, 0x00, 0x01]), Instruction::Ccc(Cond::PE, 0x0100));
+    assert_eq!(Instruction::decode(&[0xF5]), Instruction::Push(StackPair::PSW));
+    assert_eq!(Instruction::decode(&[0x1A]), Instruction::Ldax(IndirectPair::DE));
+    assert_eq!(Instruction::decode(&[0xEF]), Instruction::Rst(5));
+    // missing operand bytes read as 0
+    assert_eq!(Instruction::decode(&[0xC3, 0x10]), Instruction::Jmp(0x0010));
+
+    let mut core = I8080Core::new();
+    core.memory[0xFFFF] = 0xCD;
+    core.memory[0x0000..0x0002].copy_from_slice(&[0x00, 0x20]);
+    assert_eq!(core.decode_at(0xFFFF), Instruction::Call(0x2000));
+}
+
+#[test]
+fn mnemonics_and_timings() {
+    assert_eq!(Instruction::Mov(Reg::A, Reg::M).to_string(), "MOV A,M");
+    assert_eq!(Instruction::Lxi(RegPair::SP, 0xF000).to_string(), "LXI SP,0xF000");
+    assert_eq!(Instruction::Jcc(Cond::NZ, 0x0100).to_string(), "JNZ 0x0100");
+    assert_eq!(Instruction::Push(StackPair::PSW).to_string(), "PUSH PSW");
+    assert_eq!(Instruction::Mvi(Reg::B, 0x0A).to_string(), "MVI B,0x0A");
+
+    assert_eq!(Instruction::Ccc(Cond::Z, 0).cycles(), 11);
+    assert_eq!(Instruction::Ccc(Cond::Z, 0).taken_cycles(), 17);
+    assert_eq!(Instruction::Rcc(Cond::C).taken_cycles(), 11);
+    assert_eq!(Instruction::Jcc(Cond::C, 0).taken_cycles(), 10);
+
+    let mut core = I8080Core::new();
+    core.carry = true;
+    assert!(Cond::C.met(&core) && !Cond::NC.met(&core));
+}
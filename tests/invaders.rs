@@ -0,0 +1,128 @@
+use i8080_emulator::invaders::{DipSwitches, InvadersInput, SpaceInvaders, CYCLES_PER_FRAME};
+use i8080_emulator::observer::Observer;
+use i8080_emulator::{I8080Core, LoadRomResult, StepInstructionResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Loads program at 0x0000 and runs it from there until it reaches its HLT
+fn run(machine: &mut SpaceInvaders, program: &[u8]) {
+    assert!(machine.load_rom_bytes(program, 0x0000) == LoadRomResult::Ok);
+    machine.board.core.program_counter = 0x0000;
+    machine.board.core.halted = false;
+    for _ in 0..program.len() {
+        if machine.step() == StepInstructionResult::Halt {
+            return;
+        }
+    }
+    panic!("program never halted, PC is 0x{:04X}", machine.board.core.program_counter);
+}
+
+#[test]
+fn input_ports_and_dip_switches() {
+    // IN 0, MOV B,A, IN 1, MOV C,A, IN 2, HLT
+    let program = [0xDB, 0x00, 0x47, 0xDB, 0x01, 0x4F, 0xDB, 0x02, 0x76];
+    let mut machine = SpaceInvaders::new();
+    run(&mut machine, &program);
+    // the defaults, 3 ships, bonus at 1500 and the coin info shown, only the tied bits are set
+    assert_eq!((machine.board.core.b, machine.board.core.c, machine.board.core.a), (0x0E, 0x08, 0x00));
+
+    let mut machine = SpaceInvaders::new();
+    machine.set_dip_switches(DipSwitches {
+        ships: 5,
+        bonus_life_at_1000: true,
+        show_coin_info: false,
+    });
+    machine.set_input(InvadersInput::Coin, true);
+    machine.set_input(InvadersInput::Player1Fire, true);
+    machine.set_input(InvadersInput::Tilt, true);
+    run(&mut machine, &program);
+    // player 1 fire is wired to bit 4 of both IN 0 and IN 1
+    assert_eq!(machine.board.core.b, 0x1E);
+    assert_eq!(machine.board.core.c, 0x19);
+    // ships - 3 in bits 0-1, tilt in bit 2, bonus in bit 3 and coin info off in bit 7
+    assert_eq!(machine.board.core.a, 0x8E);
+
+    machine.set_input(InvadersInput::Coin, false);
+    run(&mut machine, &program);
+    assert_eq!(machine.board.core.c, 0x18);
+}
+
+#[test]
+fn shift_register_reads_at_every_offset() {
+    // MVI A,0xAB, OUT 4, MVI A,0xCD, OUT 4, HLT
+    let mut machine = SpaceInvaders::new();
+    run(&mut machine, &[0x3E, 0xAB, 0xD3, 0x04, 0x3E, 0xCD, 0xD3, 0x04, 0x76]);
+
+    for offset in 0..8u8 {
+        // MVI A,offset, OUT 2, IN 3, HLT
+        run(&mut machine, &[0x3E, offset, 0xD3, 0x02, 0xDB, 0x03, 0x76]);
+        let expected = (0xCDABu16 << offset >> 8) as u8;
+        assert_eq!(machine.board.core.a, expected, "offset {}", offset);
+    }
+}
+
+#[test]
+fn ram_mirrors_above_0x3fff() {
+    // MVI A,0x5A, STA 0x6000, MVI A,0, LDA 0xA000, HLT
+    let mut machine = SpaceInvaders::new();
+    run(&mut machine, &[0x3E, 0x5A, 0x32, 0x00, 0x60, 0x3E, 0x00, 0x3A, 0x00, 0xA0, 0x76]);
+    assert_eq!(machine.board.core.memory[0x2000], 0x5A);
+    assert_eq!(machine.board.core.a, 0x5A);
+    assert_eq!(machine.board.core.read_memory(0xE000), 0x5A);
+}
+
+#[test]
+fn rom_is_write_protected() {
+    // MVI A,0x77, STA 0x0100, STA 0x4100, STA 0x2100, HLT
+    let mut machine = SpaceInvaders::new();
+    run(&mut machine, &[0x3E, 0x77, 0x32, 0x00, 0x01, 0x32, 0x00, 0x41, 0x32, 0x00, 0x21, 0x76]);
+    assert_eq!(machine.board.core.memory[0x0100], 0x00);
+    assert_eq!(machine.board.core.memory[0x2100], 0x77);
+    // the ROM is still there at its mirror
+    assert_eq!(machine.board.core.read_memory(0x4002), 0x32);
+}
+
+// Records the PC and cycle count whenever an instruction starts at one of the RST vectors
+struct VectorWatch {
+    entries: Rc<RefCell<Vec<(u16, u64)>>>,
+}
+
+impl Observer for VectorWatch {
+    fn before_instruction(&mut self, core: &I8080Core) {
+        if core.program_counter == 0x0008 || core.program_counter == 0x0010 {
+            self.entries.borrow_mut().push((core.program_counter, core.cycles));
+        }
+    }
+}
+
+#[test]
+fn frame_raises_both_interrupts() {
+    // LXI SP,0x2400, EI, JMP 0x0004, then the RST 1 and RST 2 handlers each count into RAM
+    let mut program = vec![0x31, 0x00, 0x24, 0xFB, 0xC3, 0x04, 0x00, 0x00];
+    // 0x0008 LXI H,0x2000, INR M, EI, RET
+    program.extend_from_slice(&[0x21, 0x00, 0x20, 0x34, 0xFB, 0xC9, 0x00, 0x00]);
+    // 0x0010 LXI H,0x2001, INR M, EI, RET
+    program.extend_from_slice(&[0x21, 0x01, 0x20, 0x34, 0xFB, 0xC9]);
+
+    let entries = Rc::new(RefCell::new(Vec::new()));
+    let mut machine = SpaceInvaders::new();
+    assert!(machine.load_rom_bytes(&program, 0x0000) == LoadRomResult::Ok);
+    machine.board.core.observer = Some(Box::new(VectorWatch { entries: entries.clone() }));
+    assert!(machine.run_frame() == StepInstructionResult::Ok);
+    assert_eq!(machine.board.frame_number, 1);
+    // VBLANK is raised as the frame ends so its handler only runs at the start of the next one
+    assert_eq!((machine.board.core.memory[0x2000], machine.board.core.memory[0x2001]), (1, 0));
+    assert!(machine.run_frame() == StepInstructionResult::Ok);
+    assert_eq!((machine.board.core.memory[0x2000], machine.board.core.memory[0x2001]), (2, 1));
+
+    // RST 1 (0xCF) lands at 0x0008 half way through the frame and RST 2 (0xD7) at 0x0010 at the end,
+    // each within an instruction and the 11 cycles of the RST itself
+    let entries = entries.borrow();
+    assert_eq!(entries.len(), 3);
+    let (vector, cycle) = entries[0];
+    assert_eq!(vector, 0x0008);
+    assert!((CYCLES_PER_FRAME / 2..CYCLES_PER_FRAME / 2 + 30).contains(&cycle), "RST 1 at {}", cycle);
+    let (vector, cycle) = entries[1];
+    assert_eq!(vector, 0x0010);
+    assert!((CYCLES_PER_FRAME..CYCLES_PER_FRAME + 30).contains(&cycle), "RST 2 at {}", cycle);
+}
@@ -0,0 +1,212 @@
+use i8080_emulator::invaders::SpaceInvaders;
+use i8080_emulator::io::{IoDevice, PortMap, ShiftRegister, UnmappedPorts};
+use i8080_emulator::{CpuModel, I8080Core, StepInstructionResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Records every OUT and answers IN with how many bytes it has been sent
+struct Recorder {
+    written: Rc<RefCell<Vec<(u8, u8)>>>,
+}
+
+impl IoDevice for Recorder {
+    fn input(&mut self, _port: u8) -> u8 {
+        self.written.borrow().len() as u8
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.written.borrow_mut().push((port, value));
+    }
+}
+
+#[test]
+fn devices_keep_their_own_state() {
+    let written = Rc::new(RefCell::new(Vec::new()));
+    // MVI A,0x41, OUT 0x10, OUT 0x11, IN 0x10
+    let mut core = I8080Core::new();
+    core.memory[..8].copy_from_slice(&[0x3E, 0x41, 0xD3, 0x10, 0xD3, 0x11, 0xDB, 0x10]);
+    core.io = Some(Box::new(Recorder { written: written.clone() }));
+    for _ in 0..4 {
+        core.i8080_step();
+    }
+    assert_eq!(*written.borrow(), [(0x10, 0x41), (0x11, 0x41)]);
+    assert_eq!(core.a, 2);
+
+    // the Z80 port instructions go through the same device
+    let mut core = I8080Core::new();
+    core.model = CpuModel::Z80;
+    core.c = 0x20;
+    core.b = 0x99;
+    // OUT (C),B, IN D,(C)
+    core.memory[..4].copy_from_slice(&[0xED, 0x41, 0xED, 0x50]);
+    core.io = Some(Box::new(Recorder { written: written.clone() }));
+    core.i8080_step();
+    core.i8080_step();
+    assert_eq!(written.borrow()[2], (0x20, 0x99));
+    assert_eq!(core.d, 3);
+}
+
+#[test]
+fn no_device_leaves_a_alone() {
+    // MVI A,0x55, IN 0x01, OUT 0x01
+    let mut core = I8080Core::new();
+    core.memory[..6].copy_from_slice(&[0x3E, 0x55, 0xDB, 0x01, 0xD3, 0x01]);
+    for _ in 0..3 {
+        core.i8080_step();
+    }
+    assert_eq!(core.a, 0x55);
+    assert_eq!(core.io_input(0x01, 0x12), 0x12);
+}
+
+#[test]
+fn shift_register_device() {
+    let mut shifter = ShiftRegister::new(2, 4, 3);
+    shifter.output(4, 0xAB);
+    shifter.output(4, 0xCD);
+    assert_eq!(shifter.value, 0xCDAB);
+    assert_eq!(shifter.input(3), 0xCD);
+    shifter.output(2, 0x0C);
+    assert_eq!(shifter.offset, 4);
+    assert_eq!(shifter.input(3), 0xDA);
+    assert_eq!(shifter.input(5), 0);
+
+    // wired up the way the Midway board does it, only its three ports reach it
+    let shifter = Rc::new(RefCell::new(shifter));
+    let mut ports = PortMap::new(UnmappedPorts::Float);
+    let id = ports.add(Box::new(shifter.clone()));
+    ports.map_input(3..=3, id);
+    ports.map_output(2..=2, id);
+    ports.map_output(4..=4, id);
+    ports.output(1, 0x00);
+    ports.output(5, 0x00);
+    assert_eq!(shifter.borrow().value, 0xCDAB);
+    assert_eq!(ports.input(2), 0xFF);
+    ports.output(4, 0x12);
+    assert_eq!(shifter.borrow().value, 0x12CD);
+    assert_eq!(ports.input(3), 0x2C);
+
+    // the Space Invaders board drives the same device from the program
+    let mut machine = SpaceInvaders::new();
+    // MVI A,0xFF, OUT 4, MVI A,0x01, OUT 2, IN 3
+    let program = [0x3E, 0xFF, 0xD3, 0x04, 0x3E, 0x01, 0xD3, 0x02, 0xDB, 0x03];
+    machine.board.core.memory[..program.len()].copy_from_slice(&program);
+    for _ in 0..5 {
+        machine.step();
+    }
+    assert_eq!(machine.board.shifter.as_ref().unwrap().borrow().value, 0xFF00);
+    assert_eq!(machine.board.core.a, 0xFE);
+}
+
+// Always reads the same byte, handy for seeing which device answered
+struct Constant(u8);
+
+impl IoDevice for Constant {
+    fn input(&mut self, _port: u8) -> u8 {
+        self.0
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+}
+
+#[test]
+fn port_map_routes_by_port() {
+    let written = Rc::new(RefCell::new(Vec::new()));
+    let mut ports = PortMap::new(UnmappedPorts::Float);
+    ports.map(0x10..=0x13, Box::new(Constant(0x42)));
+    // a read only and a write only device sharing port 0x20
+    let reader = ports.add(Box::new(Constant(0x99)));
+    let recorder = ports.add(Box::new(Recorder { written: written.clone() }));
+    ports.map_input(0x20..=0x20, reader);
+    ports.map_output(0x20..=0x20, recorder);
+
+    assert_eq!(ports.input(0x10), 0x42);
+    assert_eq!(ports.input(0x13), 0x42);
+    assert_eq!(ports.input(0x14), 0xFF);
+    assert_eq!(ports.input(0x20), 0x99);
+    ports.output(0x20, 0x01);
+    ports.output(0x21, 0x02);
+    assert_eq!(*written.borrow(), [(0x20, 0x01)]);
+    assert_eq!(ports.device(recorder).input(0), 1);
+
+    ports.unmap(0x10..=0x11);
+    assert_eq!(ports.input(0x11), 0xFF);
+    assert_eq!(ports.input(0x12), 0x42);
+}
+
+#[test]
+fn open_bus_reads_the_last_byte() {
+    let mut ports = PortMap::new(UnmappedPorts::OpenBus);
+    ports.map(0x01..=0x01, Box::new(Constant(0x5A)));
+    assert_eq!(ports.input(0x01), 0x5A);
+    assert_eq!(ports.input(0x80), 0x5A);
+    ports.output(0x81, 0xC3);
+    assert_eq!(ports.input(0x80), 0xC3);
+    assert_eq!(ports.trapped_port(), None);
+}
+
+#[test]
+fn unmapped_ports_trap() {
+    let mut ports = PortMap::new(UnmappedPorts::Trap);
+    ports.map(0x01..=0x01, Box::new(Constant(0x5A)));
+    // IN 0x01, OUT 0x02, IN 0x03
+    let mut core = I8080Core::new();
+    core.memory[..6].copy_from_slice(&[0xDB, 0x01, 0xD3, 0x02, 0xDB, 0x03]);
+    core.io = Some(Box::new(ports));
+    assert!(core.i8080_step() == StepInstructionResult::Ok);
+    assert_eq!(core.a, 0x5A);
+    let result = core.i8080_step();
+    assert!(result == StepInstructionResult::UnmappedPort { pc: 0x0002, port: 0x02 });
+    assert!(result.is_failure());
+    assert_eq!(core.program_counter, 0x0004);
+    assert!(core.i8080_step() == StepInstructionResult::UnmappedPort { pc: 0x0004, port: 0x03 });
+    assert_eq!(core.a, 0xFF);
+
+    // the Z80 port instructions trap the same way
+    let mut ports = PortMap::new(UnmappedPorts::Trap);
+    ports.map(0x01..=0x01, Box::new(Constant(0x5A)));
+    let mut core = I8080Core::new();
+    core.model = CpuModel::Z80;
+    core.c = 0x40;
+    // IN A,(C)
+    core.memory[..2].copy_from_slice(&[0xED, 0x78]);
+    core.io = Some(Box::new(ports));
+    assert!(core.i8080_step() == StepInstructionResult::UnmappedPort { pc: 0x0000, port: 0x40 });
+}
+
+// Remembers the cycle count of every access it sees
+#[derive(Default)]
+struct Stamper {
+    stamps: Vec<(u8, u64)>,
+    cycles: u64,
+}
+
+impl IoDevice for Stamper {
+    fn input(&mut self, port: u8) -> u8 {
+        self.stamps.push((port, self.cycles));
+        0
+    }
+
+    fn output(&mut self, port: u8, _value: u8) {
+        self.stamps.push((port, self.cycles));
+    }
+
+    fn clock(&mut self, cycles: u64) {
+        self.cycles = cycles;
+    }
+}
+
+#[test]
+fn shared_devices_see_the_cycle_count() {
+    // NOP, OUT 0x10, IN 0x11 with the device kept by the test while the core owns a handle to it
+    let stamper = Rc::new(RefCell::new(Stamper::default()));
+    let mut ports = PortMap::new(UnmappedPorts::Float);
+    ports.map(0x10..=0x11, Box::new(stamper.clone()));
+    let mut core = I8080Core::new();
+    core.memory[..5].copy_from_slice(&[0x00, 0xD3, 0x10, 0xDB, 0x11]);
+    core.io = Some(Box::new(ports));
+    for _ in 0..3 {
+        core.i8080_step();
+    }
+    // stamped with the count at the start of the instruction, NOP is 4 and OUT 10
+    assert_eq!(stamper.borrow().stamps, [(0x10, 4), (0x11, 14)]);
+}
@@ -0,0 +1,55 @@
+use i8080_emulator::json::{Json, MAX_DEPTH};
+
+#[test]
+fn parses_values() {
+    let json = Json::parse(r#" { "name": "3c 00\"01\u00e9\ud83d\ude00", "list": [1, -2.5, 1e3, true, false, null, []], "empty": {} } "#).unwrap();
+    assert_eq!(json.get("name").and_then(Json::as_str), Some("3c 00\"01\u{e9}\u{1F600}"));
+    let list = json.get("list").and_then(Json::as_array).unwrap();
+    assert_eq!(list[0].as_u64(), Some(1));
+    assert_eq!(list[1].as_f64(), Some(-2.5));
+    assert_eq!(list[1].as_u64(), None);
+    assert_eq!(list[2].as_u64(), Some(1000));
+    assert_eq!(list[3].as_bool(), Some(true));
+    assert_eq!(list[5], Json::Null);
+    assert_eq!(list[6], Json::Array(Vec::new()));
+    assert_eq!(json.get("empty"), Some(&Json::Object(Vec::new())));
+    assert_eq!(json.get("missing"), None);
+}
+
+#[test]
+fn rejects_bad_json() {
+    let too_deep = "[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1);
+    let too_deep_objects = "{\"a\":".repeat(MAX_DEPTH) + "[]" + &"}".repeat(MAX_DEPTH);
+    let texts = [
+        "",
+        "[1,",
+        "[1 2]",
+        "{\"a\" 1}",
+        "{1: 2}",
+        "\"open",
+        "tru",
+        "[1] x",
+        "\"\\q\"",
+        // a high surrogate needs a low one straight after it, a low one can't stand alone
+        r#""\ud83d""#,
+        r#""\ud83dx""#,
+        r#""\ud83d\u0041""#,
+        r#""\ud83d\ud83d""#,
+        r#""\ude00""#,
+        // exactly four hex digits
+        r#""\u+041""#,
+        r#""\u-041""#,
+        r#""\u 041""#,
+        r#""\u04""#,
+        &too_deep,
+        &too_deep_objects,
+    ];
+    for text in texts {
+        let error = Json::parse(text).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{}", text);
+    }
+
+    // right at the limit is still fine
+    let deepest = "[".repeat(MAX_DEPTH) + &"]".repeat(MAX_DEPTH);
+    assert!(Json::parse(&deepest).is_ok());
+}
@@ -0,0 +1,209 @@
+use i8080_emulator::invaders::video::Orientation;
+use i8080_emulator::midway::{
+    find_machine, BitSource, Button, InputPort, LatchWrite, MachineDescription, MidwayBoard, RomFile, ScheduledInterrupt,
+    ShifterPorts,
+};
+use i8080_emulator::{LoadRomResult, PowerOnFill, StepInstructionResult};
+use std::fs;
+
+// A made up variant with everything in different places from Space Invaders: 4K of ROM, 32K
+// mirroring, inputs on 1 and 6, the shifter on 7/8/9 and the sound latches on 4 and 0x20
+static TEST_BOARD: MachineDescription = MachineDescription {
+    name: "test",
+    roms: &[
+        RomFile { name: "test.1", address: 0x0000, size: 0x0800, crc32: None },
+        RomFile { name: "test.2", address: 0x0800, size: 0x0800, crc32: None },
+    ],
+    address_mask: 0x7FFF,
+    rom_end: 0x1000,
+    upper_rom: None,
+    inputs: &[
+        InputPort {
+            port: 1,
+            bits: [
+                BitSource::Low,
+                BitSource::High,
+                BitSource::Button(Button::Coin),
+                BitSource::ButtonActiveLow(Button::Player1Start),
+                BitSource::Dip(0),
+                BitSource::Dip(7),
+                BitSource::Button(Button::Player2Up),
+                BitSource::Low,
+            ],
+        },
+        InputPort {
+            port: 6,
+            bits: [BitSource::High; 8],
+        },
+    ],
+    default_dip_switches: 0x01,
+    shifter: Some(ShifterPorts { amount: 7, data: 9, result: 8 }),
+    sound_ports: &[4, 0x20],
+    cycles_per_frame: 1000,
+    interrupts: &[ScheduledInterrupt { cycle: 400, opcode: 0xCF }],
+    orientation: Orientation::Upright,
+};
+
+static NO_SHIFTER: MachineDescription = MachineDescription {
+    shifter: None,
+    ..TEST_BOARD
+};
+
+// Loads program at 0x0000 and runs it from there until it reaches its HLT
+fn run(board: &mut MidwayBoard, program: &[u8]) {
+    assert!(board.load_rom_bytes(program, 0x0000) == LoadRomResult::Ok);
+    board.core.program_counter = 0x0000;
+    board.core.halted = false;
+    for _ in 0..program.len() {
+        if board.step() == StepInstructionResult::Halt {
+            return;
+        }
+    }
+    panic!("program never halted, PC is 0x{:04X}", board.core.program_counter);
+}
+
+#[test]
+fn memory_map_follows_the_description() {
+    let mut board = MidwayBoard::new(&TEST_BOARD);
+    assert!(board.load_rom_bytes(&[0; 0x10], 0x0FF0) == LoadRomResult::Ok);
+    assert!(board.load_rom_bytes(&[0; 0x20], 0x0FF0) == LoadRomResult::Error);
+
+    // MVI A,0x5A, STA 0x0800, STA 0x1800, MVI A,0, LDA 0x9800, HLT
+    run(&mut board, &[0x3E, 0x5A, 0x32, 0x00, 0x08, 0x32, 0x00, 0x18, 0x3E, 0x00, 0x3A, 0x00, 0x98, 0x76]);
+    assert_eq!(board.core.memory[0x0800], 0x00);
+    assert_eq!(board.core.memory[0x1800], 0x5A);
+    // 0x9800 is 0x1800 again with the mask at 32K, and 0x4000 is not a mirror of 0x0000 any more
+    assert_eq!(board.core.a, 0x5A);
+    assert_eq!(board.core.read_memory(0x8000), 0x3E);
+    assert_eq!(board.core.read_memory(0x4000), 0x00);
+}
+
+#[test]
+fn input_ports_follow_the_wiring() {
+    // IN 1, MOV B,A, IN 6, MOV C,A, IN 2, HLT
+    let program = [0xDB, 0x01, 0x47, 0xDB, 0x06, 0x4F, 0xDB, 0x02, 0x76];
+    let mut board = MidwayBoard::new(&TEST_BOARD);
+    run(&mut board, &program);
+    // the tied high bit, the active low start button and the default DIP switch 0
+    assert_eq!(board.core.b, 0x1A);
+    assert_eq!(board.core.c, 0xFF);
+    // nothing is wired to IN 2 here so it floats
+    assert_eq!(board.core.a, 0xFF);
+
+    board.set_button(Button::Coin, true);
+    board.set_button(Button::Player1Start, true);
+    board.set_button(Button::Player2Up, true);
+    // not wired on this board
+    board.set_button(Button::Player2Fire, true);
+    board.set_dip_switches(0x80);
+    run(&mut board, &program);
+    assert_eq!(board.core.b, 0x66);
+    assert!(board.inputs.borrow().button_pressed(Button::Player2Fire));
+}
+
+#[test]
+fn shifter_and_sound_latches_on_their_ports() {
+    let mut board = MidwayBoard::new(&TEST_BOARD);
+    // MVI A,0x12, OUT 9, MVI A,0x34, OUT 9, MVI A,4, OUT 7, IN 8, HLT
+    run(&mut board, &[0x3E, 0x12, 0xD3, 0x09, 0x3E, 0x34, 0xD3, 0x09, 0x3E, 0x04, 0xD3, 0x07, 0xDB, 0x08, 0x76]);
+    assert_eq!(board.core.a, 0x41);
+    assert_eq!(board.shifter.as_ref().unwrap().borrow().value, 0x3412);
+    assert!(board.take_latch_writes().is_empty());
+
+    // MVI A,0x11, OUT 4, MVI A,0x22, OUT 0x20, OUT 5, HLT
+    let mut board = MidwayBoard::new(&TEST_BOARD);
+    run(&mut board, &[0x3E, 0x11, 0xD3, 0x04, 0x3E, 0x22, 0xD3, 0x20, 0xD3, 0x05, 0x76]);
+    // each write is stamped with the cycle its OUT started on, OUT 5 isn't a sound port here
+    assert_eq!(
+        board.take_latch_writes(),
+        [
+            LatchWrite { port: 4, value: 0x11, cycle: 7 },
+            LatchWrite { port: 0x20, value: 0x22, cycle: 24 },
+        ]
+    );
+    assert!(board.take_latch_writes().is_empty());
+}
+
+#[test]
+fn board_without_a_shifter() {
+    let mut board = MidwayBoard::new(&NO_SHIFTER);
+    assert!(board.shifter.is_none());
+    // MVI A,0x12, OUT 9, OUT 7, IN 8, HLT
+    run(&mut board, &[0x3E, 0x12, 0xD3, 0x09, 0xD3, 0x07, 0xDB, 0x08, 0x76]);
+    assert_eq!(board.core.a, 0xFF);
+}
+
+#[test]
+fn frames_raise_the_scheduled_interrupts() {
+    // LXI SP,0x2000, EI, HLT, JMP 0x0004, then at 0x0008 LXI H,0x1800, INR M, EI, RET
+    let program = [0x31, 0x00, 0x20, 0xFB, 0x76, 0xC3, 0x04, 0x00, 0x21, 0x00, 0x18, 0x34, 0xFB, 0xC9];
+    let mut board = MidwayBoard::new(&TEST_BOARD);
+    assert!(board.load_rom_bytes(&program, 0x0000) == LoadRomResult::Ok);
+    for _ in 0..3 {
+        assert!(board.run_frame() == StepInstructionResult::Ok);
+    }
+    assert_eq!(board.frame_number, 3);
+    assert_eq!(board.core.cycles, 3000);
+    assert_eq!(board.core.memory[0x1800], 3);
+}
+
+#[test]
+fn rom_set_checks_every_file() {
+    let directory = std::env::temp_dir().join(format!("i8080_midway_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.to_str().unwrap();
+
+    let mut board = MidwayBoard::new(&TEST_BOARD);
+    fs::write(directory.join("test.1"), [0xAA; 0x0800]).unwrap();
+    assert!(board.load_rom_set(path) == LoadRomResult::NotFound);
+    fs::write(directory.join("test.2"), [0xBB; 0x0400]).unwrap();
+    assert!(board.load_rom_set(path) == LoadRomResult::Error);
+    fs::write(directory.join("test.2"), [0xBB; 0x0800]).unwrap();
+    assert!(board.load_rom_set(path) == LoadRomResult::Ok);
+    assert_eq!((board.core.memory[0x07FF], board.core.memory[0x0800]), (0xAA, 0xBB));
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(find_machine("invaders").unwrap().name, "invaders");
+    assert!(find_machine("test").is_none());
+}
+
+#[test]
+fn lunar_rescue_has_a_second_rom_bank() {
+    let description = find_machine("lrescue").unwrap();
+    let crcs: Vec<(&str, u16, Option<u32>)> = description.roms.iter().map(|rom| (rom.name, rom.address, rom.crc32)).collect();
+    assert_eq!(
+        crcs,
+        [
+            ("lrescue.1", 0x0000, Some(0x2BBC4778)),
+            ("lrescue.2", 0x0800, Some(0x49E79706)),
+            ("lrescue.3", 0x1000, Some(0x1AC969BE)),
+            ("lrescue.4", 0x1800, Some(0x782FEE3C)),
+            ("lrescue.5", 0x4000, Some(0x58FDE8BC)),
+            ("lrescue.6", 0x4800, Some(0xBFB0F65D)),
+        ]
+    );
+    assert!(description.orientation == Orientation::RotatedCounterClockwise);
+
+    let mut board = MidwayBoard::new(description);
+    assert!(board.load_rom_bytes(&[0xC9; 0x0800], 0x4800) == LoadRomResult::Ok);
+    // the bank is 0x4000-0x4FFF and nothing may straddle its end or the gap below it
+    assert!(board.load_rom_bytes(&[0; 0x10], 0x4FF8) == LoadRomResult::Error);
+    assert!(board.load_rom_bytes(&[0; 0x10], 0x3FF8) == LoadRomResult::Error);
+
+    // power on clears RAM but leaves both ROM areas alone
+    board.core.power_on(PowerOnFill::Pattern(0x00));
+    assert!(board.core.memory[0x4800..0x5000].iter().all(|byte| *byte == 0xC9));
+
+    // MVI A,0x5A, STA 0x4800, STA 0x5000, STA 0x6000, MVI A,4, OUT 2, MVI A,0x80, OUT 4, OUT 4, IN 3, HLT
+    let program = [
+        0x3E, 0x5A, 0x32, 0x00, 0x48, 0x32, 0x00, 0x50, 0x32, 0x00, 0x60, 0x3E, 0x04, 0xD3, 0x02, 0x3E, 0x80, 0xD3,
+        0x04, 0xD3, 0x04, 0xDB, 0x03, 0x76,
+    ];
+    run(&mut board, &program);
+    assert_eq!(board.core.memory[0x4800], 0xC9);
+    assert_eq!((board.core.memory[0x5000], board.core.memory[0x6000]), (0x5A, 0x5A));
+    // 0xC800 is 0x4800 again with the mask at 32K
+    assert_eq!(board.core.read_memory(0xC800), 0xC9);
+    // the Space Invaders shifter on 2/4/3, 0x8080 shifted left 4 reads back 0x08
+    assert_eq!(board.core.a, 0x08);
+}
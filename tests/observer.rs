@@ -0,0 +1,138 @@
+use i8080_emulator::io::{PortMap, UnmappedPorts};
+use i8080_emulator::observer::Observer;
+use i8080_emulator::{CpuModel, I8080Core, StepInstructionResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Default)]
+struct Events {
+    instructions: Vec<(u16, u16)>,
+    reads: Vec<(u16, u8)>,
+    writes: Vec<(u16, u8)>,
+    ports: Vec<(bool, u8, u8)>,
+}
+
+// Logs everything into a log shared with the test
+struct Tracer {
+    events: Rc<RefCell<Events>>,
+    pc: u16,
+}
+
+impl Observer for Tracer {
+    fn before_instruction(&mut self, core: &I8080Core) {
+        self.pc = core.program_counter;
+    }
+
+    fn after_instruction(&mut self, core: &I8080Core, result: &StepInstructionResult) {
+        assert!(*result == StepInstructionResult::Ok);
+        self.events.borrow_mut().instructions.push((self.pc, core.program_counter));
+    }
+
+    fn memory_read(&mut self, address: u16, value: u8) {
+        self.events.borrow_mut().reads.push((address, value));
+    }
+
+    fn memory_write(&mut self, address: u16, value: u8) {
+        self.events.borrow_mut().writes.push((address, value));
+    }
+
+    fn port_input(&mut self, port: u8, value: u8) {
+        self.events.borrow_mut().ports.push((true, port, value));
+    }
+
+    fn port_output(&mut self, port: u8, value: u8) {
+        self.events.borrow_mut().ports.push((false, port, value));
+    }
+}
+
+fn traced(model: CpuModel, program: &[u8]) -> (I8080Core, Rc<RefCell<Events>>) {
+    let events = Rc::new(RefCell::new(Events::default()));
+    let mut core = I8080Core::new();
+    core.model = model;
+    core.memory[..program.len()].copy_from_slice(program);
+    core.stack_pointer = 0x2000;
+    core.io = Some(Box::new(PortMap::new(UnmappedPorts::Float)));
+    core.observer = Some(Box::new(Tracer { events: events.clone(), pc: 0 }));
+    (core, events)
+}
+
+#[test]
+fn sees_every_bus_cycle() {
+    // MVI M,0x12 with HL = 0x1000, PUSH B, IN 0x07, OUT 0x08
+    let (mut core, events) = traced(CpuModel::I8080, &[0x36, 0x12, 0xC5, 0xDB, 0x07, 0xD3, 0x08]);
+    core.h = 0x10;
+    core.b = 0xAB;
+    core.c = 0xCD;
+    for _ in 0..4 {
+        core.i8080_step();
+    }
+    let events = events.borrow();
+    assert_eq!(events.instructions, [(0, 2), (2, 3), (3, 5), (5, 7)]);
+    assert_eq!(events.reads, [(0, 0x36), (1, 0x12), (2, 0xC5), (3, 0xDB), (4, 0x07), (5, 0xD3), (6, 0x08)]);
+    assert_eq!(events.writes, [(0x1000, 0x12), (0x1FFF, 0xAB), (0x1FFE, 0xCD)]);
+    assert_eq!(events.ports, [(true, 0x07, 0xFF), (false, 0x08, 0xFF)]);
+}
+
+#[test]
+fn sees_z80_instructions() {
+    // LD (IX+2),0x34 with IX = 0x3000
+    let (mut core, events) = traced(CpuModel::Z80, &[0xDD, 0x36, 0x02, 0x34]);
+    core.z80.ix = 0x3000;
+    core.i8080_step();
+    let events = events.borrow();
+    assert_eq!(events.instructions, [(0, 4)]);
+    assert_eq!(events.reads.len(), 4);
+    assert_eq!(events.writes, [(0x3002, 0x34)]);
+}
+
+// Records the PC of the first instruction that wrote to a watched address
+struct Watchpoint {
+    address: u16,
+    pc: u16,
+    hit: Rc<RefCell<Option<u16>>>,
+}
+
+impl Observer for Watchpoint {
+    fn before_instruction(&mut self, core: &I8080Core) {
+        self.pc = core.program_counter;
+    }
+
+    fn memory_write(&mut self, address: u16, _value: u8) {
+        let mut hit = self.hit.borrow_mut();
+        if address == self.address && hit.is_none() {
+            *hit = Some(self.pc);
+        }
+    }
+}
+
+#[test]
+fn watchpoint() {
+    let hit = Rc::new(RefCell::new(None));
+    // LXI H,0x2000, INR M x3, INX H, MOV M,A
+    let mut core = I8080Core::new();
+    core.memory[..8].copy_from_slice(&[0x21, 0x00, 0x20, 0x34, 0x34, 0x34, 0x23, 0x77]);
+    core.observer = Some(Box::new(Watchpoint { address: 0x2001, pc: 0, hit: hit.clone() }));
+    while hit.borrow().is_none() {
+        core.i8080_step();
+    }
+    assert_eq!(*hit.borrow(), Some(0x0007));
+    assert_eq!(core.memory[0x2000], 3);
+}
+
+#[test]
+fn sees_in_with_no_device() {
+    // MVI A,0x55, IN 0x07, then on the Z80 IN B,(C)
+    let (mut core, events) = traced(CpuModel::I8080, &[0x3E, 0x55, 0xDB, 0x07]);
+    core.io = None;
+    core.i8080_step();
+    core.i8080_step();
+    assert_eq!(core.a, 0x55);
+    assert_eq!(events.borrow().ports, [(true, 0x07, 0x55)]);
+
+    let (mut core, events) = traced(CpuModel::Z80, &[0xED, 0x40]);
+    core.io = None;
+    core.c = 0x09;
+    core.i8080_step();
+    assert_eq!(core.b, 0xFF);
+    assert_eq!(events.borrow().ports, [(true, 0x09, 0xFF)]);
+}
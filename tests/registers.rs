@@ -0,0 +1,56 @@
+use i8080_emulator::registers::{Reg16, Reg8};
+use i8080_emulator::{CpuModel, I8080Core};
+
+#[test]
+fn pairs_and_named_registers() {
+    let mut core = I8080Core::new();
+    core.set_bc(0x1234);
+    core.set_de(0x5678);
+    core.set_hl(0x9ABC);
+    assert_eq!((core.b, core.c, core.d, core.e, core.h, core.l), (0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC));
+    assert_eq!((core.bc(), core.de(), core.hl()), (0x1234, 0x5678, 0x9ABC));
+
+    Reg16::SP.set(&mut core, 0xF000);
+    Reg16::PC.set(&mut core, 0x0100);
+    assert_eq!((core.stack_pointer, core.program_counter), (0xF000, 0x0100));
+    Reg8::L.set(&mut core, 0x01);
+    assert_eq!(Reg16::HL.get(&core), 0x9A01);
+    Reg16::DE.set(&mut core, 0xBEEF);
+    assert_eq!((Reg8::D.get(&core), Reg8::E.get(&core)), (0xBE, 0xEF));
+}
+
+#[test]
+fn flags_byte_matches_push_and_pop_psw() {
+    // PUSH PSW, POP PSW
+    let mut core = I8080Core::new();
+    core.memory[..2].copy_from_slice(&[0xF5, 0xF1]);
+    core.stack_pointer = 0x2000;
+    core.a = 0x42;
+    core.sign = true;
+    core.auxiliary_carry = true;
+    core.carry = true;
+    assert_eq!(core.flags_byte(), 0x93);
+    assert_eq!(core.psw(), 0x4293);
+    core.i8080_step();
+    assert_eq!(core.memory[0x1FFE..0x2000], [0x93, 0x42]);
+
+    // POP PSW ignores the fixed bits on the way back in
+    core.memory[0x1FFE] = 0xFF ^ 0x80;
+    core.i8080_step();
+    assert_eq!(core.flags_byte(), 0x57);
+    assert!(!core.sign && core.zero && core.auxiliary_carry && core.parity && core.carry);
+
+    Reg16::PSW.set(&mut core, 0x0000);
+    assert_eq!(core.a, 0);
+    assert_eq!(Reg8::F.get(&core), 0x02);
+}
+
+#[test]
+fn flags_byte_is_f_in_z80_mode() {
+    let mut core = I8080Core::new();
+    core.model = CpuModel::Z80;
+    core.set_flags_byte(0x02);
+    assert!(core.z80.subtract);
+    assert_eq!(core.flags_byte(), core.z80_flags());
+    assert_eq!(core.flags_byte(), 0x02);
+}
@@ -0,0 +1,85 @@
+use i8080_emulator::{CpuModel, I8080Core, PowerOnFill};
+
+#[test]
+fn reset_keeps_registers_and_memory() {
+    // EI, HLT
+    let mut core = I8080Core::new();
+    core.memory[..2].copy_from_slice(&[0xFB, 0x76]);
+    core.memory[0x4000] = 0x5A;
+    core.a = 0x12;
+    core.set_hl(0x3456);
+    core.stack_pointer = 0x8000;
+    core.carry = true;
+    core.i8080_step();
+    core.i8080_step();
+    assert!(core.halted && core.interrupts_enabled);
+
+    core.reset();
+    assert_eq!(core.program_counter, 0);
+    assert!(!core.halted && !core.interrupts_enabled);
+    assert_eq!((core.a, core.hl(), core.stack_pointer), (0x12, 0x3456, 0x8000));
+    assert!(core.carry);
+    assert_eq!(core.memory[0x4000], 0x5A);
+}
+
+#[test]
+fn reset_per_model() {
+    let mut core = I8080Core::new();
+    core.model = CpuModel::I8085;
+    core.i8085.masks = 0;
+    core.i8085.sod = true;
+    core.i8085_rst75();
+    core.i8085.rst55 = true;
+    core.reset();
+    assert_eq!(core.i8085.masks, 0x07);
+    assert!(!core.i8085.sod && !core.i8085.rst75);
+    assert!(core.i8085.rst55);
+
+    let mut core = I8080Core::new();
+    core.model = CpuModel::Z80;
+    core.z80.interrupt_mode = 2;
+    core.z80.i = 0x40;
+    core.z80.iff2 = true;
+    core.z80.ix = 0x1234;
+    core.reset();
+    assert_eq!((core.z80.interrupt_mode, core.z80.i), (0, 0));
+    assert!(!core.z80.iff2);
+    assert_eq!(core.z80.ix, 0x1234);
+}
+
+#[test]
+fn power_on_fills_ram_and_registers() {
+    let mut core = I8080Core::new();
+    core.memory[..4].copy_from_slice(&[0xC3, 0x00, 0x10, 0x00]);
+    core.rom_end = 0x0004;
+    core.program_counter = 0x1234;
+    core.power_on(PowerOnFill::Pattern(0xE5));
+    assert_eq!(core.memory[..4], [0xC3, 0x00, 0x10, 0x00]);
+    assert!(core.memory[4..].iter().all(|byte| *byte == 0xE5));
+    assert_eq!((core.a, core.bc(), core.stack_pointer), (0xE5, 0xE5E5, 0xE5E5));
+    assert_eq!(core.program_counter, 0);
+
+    // the same seed always powers on the same way, a different one doesn't
+    let mut first = I8080Core::new();
+    let mut second = I8080Core::new();
+    first.power_on(PowerOnFill::Random(7));
+    second.power_on(PowerOnFill::Random(7));
+    assert!(first.memory == second.memory);
+    assert_eq!((first.a, first.psw(), first.hl()), (second.a, second.psw(), second.hl()));
+    second.power_on(PowerOnFill::Random(8));
+    assert!(first.memory != second.memory);
+
+    first.power_on(PowerOnFill::Zero);
+    assert!(first.memory.iter().all(|byte| *byte == 0));
+    assert_eq!(first.psw(), 0x0002);
+}
+
+#[test]
+fn power_on_with_everything_mirrored_as_rom() {
+    let mut core = I8080Core::new();
+    core.address_mask = 0x0FFF;
+    core.rom_end = 0x2000;
+    core.power_on(PowerOnFill::Pattern(0xFF));
+    assert_eq!(core.memory[0x0800], 0);
+    assert_eq!(core.a, 0xFF);
+}
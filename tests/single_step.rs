@@ -0,0 +1,71 @@
+use i8080_emulator::single_step::{parse_tests, run_tests};
+use i8080_emulator::CpuModel;
+
+// INR A rolling over, PUSH B, IN 0x10 and OUT 0x20 written the way the suites write them
+const CASES: &str = r#"[
+  { "name": "3c 0000",
+    "initial": { "pc": 4096, "sp": 0, "a": 255, "b": 0, "c": 0, "d": 0, "e": 0, "f": 3, "h": 0, "l": 0,
+                 "ram": [[4096, 60]] },
+    "final":   { "pc": 4097, "sp": 0, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 87, "h": 0, "l": 0,
+                 "ram": [[4096, 60]] },
+    "cycles": [[4096, 60, "r"], [4096, 60, "r"], [4096, 60, "r"], [4096, 60, "r"], [4096, 60, "r"]] },
+  { "name": "c5 0000",
+    "initial": { "pc": 256, "sp": 8192, "a": 0, "b": 18, "c": 52, "d": 0, "e": 0, "f": 2, "h": 0, "l": 0,
+                 "ram": [[256, 197], [8190, 0], [8191, 0]] },
+    "final":   { "pc": 257, "sp": 8190, "a": 0, "b": 18, "c": 52, "d": 0, "e": 0, "f": 2, "h": 0, "l": 0,
+                 "ram": [[256, 197], [8190, 52], [8191, 18]] },
+    "cycles": [[0, 0, ""], [0, 0, ""], [0, 0, ""], [0, 0, ""], [0, 0, ""], [0, 0, ""],
+               [0, 0, ""], [0, 0, ""], [0, 0, ""], [0, 0, ""], [0, 0, ""]] },
+  { "name": "db 0000",
+    "initial": { "pc": 0, "sp": 0, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 0, "l": 0,
+                 "ram": [[0, 219], [1, 16]] },
+    "final":   { "pc": 2, "sp": 0, "a": 171, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 0, "l": 0,
+                 "ram": [] },
+    "ports": [[16, 171, "r"]] },
+  { "name": "d3 0000",
+    "initial": { "pc": 0, "sp": 0, "a": 85, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 0, "l": 0,
+                 "ram": [[0, 211], [1, 32]] },
+    "final":   { "pc": 2, "sp": 0, "a": 85, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 0, "l": 0,
+                 "ram": [] },
+    "ports": [[32, 85, "w"]] }
+]"#;
+
+#[test]
+fn passing_cases() {
+    let tests = parse_tests(CASES).unwrap();
+    assert_eq!(tests.len(), 4);
+    assert_eq!(tests[0].cycles, Some(5));
+    assert_eq!(tests[2].cycles, None);
+    assert_eq!(tests[1].expected.ram[1], (8190, 52));
+
+    let report = run_tests("mixed", &tests, CpuModel::I8080);
+    assert_eq!(report.failures, Vec::<String>::new());
+    assert_eq!((report.passed, report.failed), (4, 0));
+    assert_eq!(report.pass_rate(), 100.0);
+}
+
+#[test]
+fn failures_point_at_the_difference() {
+    // the expected results are wrong on purpose: A, the pushed byte, the cycle count and the port
+    let broken = CASES
+        .replace(r#""a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 87"#, r#""a": 1, "b": 0, "c": 0, "d": 0, "e": 0, "f": 87"#)
+        .replace("[8190, 52]", "[8190, 53]")
+        .replace(r#"[[4096, 60, "r"], [4096, 60, "r"], "#, r#"[[4096, 60, "r"], "#)
+        .replace(r#"[[32, 85, "w"]]"#, r#"[[33, 85, "w"]]"#);
+    let tests = parse_tests(&broken).unwrap();
+    let report = run_tests("broken", &tests, CpuModel::I8080);
+    assert_eq!((report.passed, report.failed), (1, 3));
+    assert_eq!(report.pass_rate(), 25.0);
+    assert_eq!(report.failures.len(), 3);
+    assert_eq!(report.failures[0], "3c 0000: a expected 0x01 got 0x00, cycles expected 4 got 5");
+    assert_eq!(report.failures[1], "c5 0000: ram[0x1FFE] expected 0x35 got 0x34");
+    assert_eq!(report.failures[2], "d3 0000: OUT expected 0x55 to port 0x21 got 0x55 to port 0x20");
+}
+
+#[test]
+fn bad_files_are_errors() {
+    assert!(parse_tests("{}").is_err());
+    let missing_register = CASES.replacen(r#""sp": 0, "#, "", 1);
+    let error = parse_tests(&missing_register).unwrap_err();
+    assert_eq!(error.to_string(), "3c 0000: missing or bad \"sp\"");
+}
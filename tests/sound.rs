@@ -0,0 +1,151 @@
+use i8080_emulator::invaders::sound::{decode_wav, encode_wav, resample, SampleMixer, Sound, SoundDecoder, SoundEvent};
+use i8080_emulator::io::IoDevice;
+use std::io;
+
+fn event(sound: Sound, started: bool, cycle: u64) -> SoundEvent {
+    SoundEvent { sound, started, cycle }
+}
+
+#[test]
+fn decoder_edges_on_port_3() {
+    let mut decoder = SoundDecoder::new();
+    decoder.write(3, 0x03, 10);
+    decoder.write(3, 0x03, 20);
+    decoder.write(3, 0x02, 30);
+    // the amplifier bit is not a sound
+    decoder.write(3, 0x22, 40);
+    decoder.write(3, 0x24, 50);
+    assert_eq!(
+        decoder.take_events(),
+        [
+            event(Sound::Ufo, true, 10),
+            event(Sound::Shot, true, 10),
+            event(Sound::Ufo, false, 30),
+            event(Sound::Shot, false, 50),
+            event(Sound::PlayerDeath, true, 50),
+        ]
+    );
+    assert!(decoder.amplifier_enabled());
+    assert!(decoder.take_events().is_empty());
+}
+
+#[test]
+fn decoder_edges_on_port_5() {
+    let mut decoder = SoundDecoder::new();
+    decoder.write(5, 0x01, 100);
+    decoder.write(5, 0x12, 200);
+    // cocktail flip and other ports make no sound
+    decoder.write(5, 0x32, 300);
+    decoder.write(4, 0xFF, 400);
+    decoder.write(5, 0x00, 500);
+    assert_eq!(
+        decoder.take_events(),
+        [
+            event(Sound::Fleet1, true, 100),
+            event(Sound::Fleet1, false, 200),
+            event(Sound::Fleet2, true, 200),
+            event(Sound::UfoHit, true, 200),
+            event(Sound::Fleet2, false, 500),
+            event(Sound::UfoHit, false, 500),
+        ]
+    );
+    assert_eq!((decoder.port3, decoder.port5), (0x00, 0x00));
+}
+
+#[test]
+fn decoder_as_a_device_stamps_the_clock() {
+    let mut decoder = SoundDecoder::new();
+    decoder.clock(1234);
+    decoder.output(3, 0x08);
+    decoder.clock(5678);
+    decoder.output(5, 0x08);
+    assert_eq!(decoder.input(3), 0);
+    assert_eq!(
+        decoder.take_events(),
+        [event(Sound::InvaderHit, true, 1234), event(Sound::Fleet4, true, 5678)]
+    );
+}
+
+#[test]
+fn wav_round_trip() {
+    let audio = [0, 1, -1, 1000, -1000, i16::MAX, i16::MIN];
+    let wav = encode_wav(&audio, 22050);
+    assert_eq!(wav.len(), 44 + audio.len() * 2);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize, wav.len() - 8);
+    assert_eq!(decode_wav(&wav).unwrap(), (22050, audio.to_vec()));
+    assert_eq!(decode_wav(&encode_wav(&[], 8000)).unwrap(), (8000, Vec::new()));
+}
+
+#[test]
+fn resample_lengths() {
+    let audio: Vec<i16> = (0..100).collect();
+    assert_eq!(resample(&audio, 22050, 22050), audio);
+    assert_eq!(resample(&audio, 11025, 44100).len(), 400);
+    assert_eq!(resample(&audio, 44100, 22050).len(), 50);
+    assert_eq!(resample(&audio, 44100, 8000).len(), 18);
+    assert!(resample(&[], 11025, 44100).is_empty());
+
+    // nearest sample, every input sample is repeated when upsampling by a whole factor
+    assert_eq!(resample(&[1, 2, 3], 1, 2), [1, 1, 2, 2, 3, 3]);
+    assert_eq!(resample(&[1, 2, 3, 4], 2, 1), [1, 3]);
+}
+
+#[test]
+fn rejects_malformed_riff() {
+    let wav = encode_wav(&[1, 2, 3], 8000);
+    let rejected = |data: &[u8]| decode_wav(data).unwrap_err().kind() == io::ErrorKind::InvalidData;
+
+    assert!(rejected(&[]));
+    assert!(rejected(&wav[..11]));
+    let mut bad_magic = wav.clone();
+    bad_magic[0..4].copy_from_slice(b"RIFX");
+    assert!(rejected(&bad_magic));
+    let mut not_wave = wav.clone();
+    not_wave[8..12].copy_from_slice(b"AVI ");
+    assert!(rejected(&not_wave));
+    // header and fmt but no data chunk
+    assert!(rejected(&wav[..36]));
+
+    // data before fmt
+    let mut data_first = wav[..12].to_vec();
+    data_first.extend_from_slice(&wav[36..]);
+    data_first.extend_from_slice(&wav[12..36]);
+    assert!(rejected(&data_first));
+
+    // 24 bit and non PCM formats
+    let mut wide = wav.clone();
+    wide[34] = 24;
+    assert!(rejected(&wide));
+    let mut float = wav.clone();
+    float[20] = 3;
+    assert!(rejected(&float));
+    let mut no_channels = wav.clone();
+    no_channels[22] = 0;
+    assert!(rejected(&no_channels));
+}
+
+#[test]
+fn mixer_rejects_a_zero_clock() {
+    assert_eq!(SampleMixer::new(44100, 0).err().unwrap().kind(), io::ErrorKind::InvalidInput);
+    assert!(SampleMixer::new(0, 2_000_000).is_err());
+}
+
+#[test]
+fn mixer_places_samples_at_their_events() {
+    // one frame per cycle keeps the arithmetic out of the way
+    let mut mixer = SampleMixer::new(1000, 1000).unwrap();
+    mixer.set_sample(Sound::Shot, vec![100, 200]);
+    mixer.set_sample(Sound::Ufo, vec![1, 2]);
+
+    let events = [
+        event(Sound::Ufo, true, 1),
+        event(Sound::Shot, true, 2),
+        // a one shot sound plays out even though its bit drops straight away
+        event(Sound::Shot, false, 3),
+        event(Sound::Ufo, false, 6),
+        // no sample, no sound
+        event(Sound::InvaderHit, true, 7),
+    ];
+    assert_eq!(mixer.mix(&events, 9), [0, 1, 102, 201, 2, 1, 0, 0, 0]);
+}
@@ -0,0 +1,94 @@
+use i8080_emulator::altair::{BufferSerial, SerialBackend};
+use i8080_emulator::terminal::{write_ansi, Terminal, TerminalAction, TerminalBackend, TerminalDecoder, TerminalType};
+
+#[test]
+fn adm3a_cursor_addressing_and_clear() {
+    let mut terminal = Terminal::new(TerminalType::Adm3a);
+    terminal.write(b"junk\x1a");
+    assert_eq!(terminal.screen.text(), "");
+
+    // ESC = row col, then up, right and home
+    terminal.write(b"\x1b=%(HELLO");
+    assert_eq!(terminal.screen.line(5), "        HELLO");
+    terminal.write(b"\x0b\x0c*\x1e#");
+    assert_eq!(terminal.screen.line(4), "              *");
+    assert_eq!(terminal.screen.line(0), "#");
+
+    // ESC T clears the rest of the line
+    terminal.write(b"\x1b=%*\x1bT");
+    assert_eq!(terminal.screen.line(5), "        HE");
+}
+
+#[test]
+fn adm3a_wraps_and_scrolls() {
+    let mut terminal = Terminal::new(TerminalType::Adm3a);
+    terminal.write(b"\x1b=7 ");
+    terminal.write(&[b'x'; 81]);
+    // row 23 filled then the 81st character wrapped onto a new bottom line
+    assert_eq!(terminal.screen.line(22), "x".repeat(80));
+    assert_eq!(terminal.screen.line(23), "x");
+    assert_eq!((terminal.screen.cursor_row, terminal.screen.cursor_column), (23, 1));
+}
+
+#[test]
+fn vt52_escape_sequences() {
+    let mut terminal = Terminal::new(TerminalType::Vt52);
+    terminal.write(b"first\r\nsecond\r\nthird");
+    terminal.write(b"\x1bA\x1bA\x1bD\x1bK");
+    assert_eq!(terminal.screen.line(0), "firs");
+
+    terminal.write(b"\x1bY\x21\x22AB\x1bC\x1bCC\x1bH\x1bJ");
+    assert_eq!(terminal.screen.text(), "");
+
+    // the VT52 doesn't wrap, the last column keeps being overwritten
+    terminal.write(b"\x1bY\x20\x6eabc");
+    assert_eq!(terminal.screen.line(0), format!("{}ac", " ".repeat(78)));
+
+    // reverse line feed on the top line scrolls the screen down
+    terminal.write(b"\x1bH\x1bItop");
+    assert_eq!(terminal.screen.line(0), "top");
+    assert_eq!(terminal.screen.line(1), format!("{}ac", " ".repeat(78)));
+}
+
+#[test]
+fn decoder_respects_terminal_type() {
+    let mut adm3a = TerminalDecoder::new(TerminalType::Adm3a);
+    let mut vt52 = TerminalDecoder::new(TerminalType::Vt52);
+    assert_eq!(adm3a.decode(0x1A), Some(TerminalAction::ClearScreen));
+    assert_eq!(vt52.decode(0x1A), None);
+    assert_eq!(vt52.decode(0x1B), None);
+    assert_eq!(vt52.decode(b'H'), Some(TerminalAction::Home));
+    assert_eq!(adm3a.decode(0x1B), None);
+    assert_eq!(adm3a.decode(b'H'), None);
+    assert_eq!(adm3a.decode(b'H' | 0x80), Some(TerminalAction::Print(b'H')));
+    assert_eq!(TerminalType::from_name("VT52"), Some(TerminalType::Vt52));
+    assert_eq!(TerminalType::from_name("vt100"), None);
+}
+
+#[test]
+fn translates_to_ansi() {
+    let mut ansi = Vec::new();
+    write_ansi(TerminalAction::MoveTo { row: 4, column: 9 }, &mut ansi);
+    write_ansi(TerminalAction::ClearToEndOfLine, &mut ansi);
+    write_ansi(TerminalAction::Print(b'x'), &mut ansi);
+    write_ansi(TerminalAction::LineFeed, &mut ansi);
+    assert_eq!(ansi, b"\x1b[5;10H\x1b[Kx\x1bD");
+
+    // the backend does the same to everything a program writes, input goes straight through
+    struct Shared(std::rc::Rc<std::cell::RefCell<BufferSerial>>);
+    impl SerialBackend for Shared {
+        fn receive(&mut self) -> Option<u8> {
+            self.0.borrow_mut().receive()
+        }
+        fn transmit(&mut self, byte: u8) {
+            self.0.borrow_mut().transmit(byte)
+        }
+    }
+    let buffer = std::rc::Rc::new(std::cell::RefCell::new(BufferSerial::new(b"k")));
+    let mut backend = TerminalBackend::new(TerminalType::Adm3a, Box::new(Shared(buffer.clone())));
+    for byte in b"\x1a\x1b=!!ok" {
+        backend.transmit(*byte);
+    }
+    assert_eq!(backend.receive(), Some(b'k'));
+    assert_eq!(buffer.borrow().output, b"\x1b[H\x1b[2J\x1b[2;2Hok");
+}
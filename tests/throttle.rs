@@ -0,0 +1,38 @@
+use i8080_emulator::Throttle;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// at 10 kHz every 100 cycles is 10ms, small enough to sleep through in a test
+const CLOCK_HZ: u64 = 10_000;
+
+#[test]
+fn paces_to_emulated_time() {
+    let mut throttle = Throttle::new(CLOCK_HZ);
+    let began = Instant::now();
+    // the first call only anchors the schedule
+    throttle.pace(0);
+    assert!(began.elapsed() < Duration::from_millis(20));
+
+    throttle.pace(500);
+    let elapsed = began.elapsed();
+    assert!(elapsed >= Duration::from_millis(50), "ran ahead, {:?} for 50ms of cycles", elapsed);
+    assert!(elapsed < Duration::from_millis(500), "slept {:?} for 50ms of cycles", elapsed);
+}
+
+#[test]
+fn reanchors_after_a_stall() {
+    let mut throttle = Throttle::new(CLOCK_HZ);
+    throttle.pace(0);
+    // stall for longer than the 100ms the throttle lets execution fall behind
+    thread::sleep(Duration::from_millis(250));
+
+    // 10ms of cycles is now far behind, the schedule restarts here without sleeping
+    let stalled = Instant::now();
+    throttle.pace(100);
+    assert!(stalled.elapsed() < Duration::from_millis(20));
+
+    // had it kept the old anchor the next 50ms would be run flat out to catch up
+    let resumed = Instant::now();
+    throttle.pace(600);
+    assert!(resumed.elapsed() >= Duration::from_millis(50), "ran fast to catch up, {:?}", resumed.elapsed());
+}
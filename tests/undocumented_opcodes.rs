@@ -0,0 +1,63 @@
+use i8080_emulator::{CpuModel, I8080Core, StepInstructionResult, UndocumentedOpcodes, CYCLE_TABLE};
+
+fn core(program: &[u8]) -> I8080Core {
+    let mut core = I8080Core::new();
+    core.memory[..program.len()].copy_from_slice(program);
+    core.stack_pointer = 0x2000;
+    core
+}
+
+#[test]
+fn executes_silicon_aliases() {
+    // *JMP 0x0010, at 0x10: *CALL 0x0020 three ways, at 0x20: *RET, *NOP
+    let mut core = core(&[0xCB, 0x10, 0x00]);
+    core.memory[0x10..0x19].copy_from_slice(&[0xDD, 0x20, 0x00, 0xED, 0x20, 0x00, 0xFD, 0x20, 0x00]);
+    core.memory[0x20..0x22].copy_from_slice(&[0xD9, 0x38]);
+
+    assert!(core.i8080_step() == StepInstructionResult::Ok);
+    assert_eq!(core.program_counter, 0x0010);
+    for return_address in [0x13, 0x16, 0x19] {
+        core.i8080_step();
+        assert_eq!(core.program_counter, 0x0020);
+        assert_eq!(core.memory[0x1FFE], return_address);
+        core.i8080_step();
+        assert_eq!(core.program_counter, return_address as u16);
+        if return_address != 0x19 {
+            continue;
+        }
+        core.program_counter = 0x0021;
+        assert!(core.i8080_step() == StepInstructionResult::NoOperation);
+        assert_eq!(core.program_counter, 0x0022);
+    }
+    assert_eq!(core.cycles, 10 + 3 * (17 + 10) + 4);
+    assert_eq!(CYCLE_TABLE[0xFD], CYCLE_TABLE[0xCD]);
+}
+
+#[test]
+fn trap_policy_stops_on_undocumented_opcodes() {
+    for opcode in [0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38, 0xCB, 0xD9, 0xDD, 0xED, 0xFD] {
+        let mut core = core(&[0x00, opcode]);
+        core.undocumented_opcodes = UndocumentedOpcodes::Trap;
+        core.i8080_step();
+        let cycles = core.cycles;
+        assert!(core.i8080_step() == StepInstructionResult::IllegalOpcode { pc: 1, opcode });
+        assert_eq!(core.program_counter, 1);
+        assert_eq!(core.cycles, cycles);
+        assert!(core.i8080_run_cycles(1000).is_failure());
+    }
+}
+
+#[test]
+fn other_models_keep_their_own_instructions() {
+    // RIM is still RIM on the 8085 under the trap policy, the rest are NOPs there
+    let mut core = core(&[0x20, 0xFD]);
+    core.model = CpuModel::I8085;
+    assert!(core.i8080_step() == StepInstructionResult::Ok);
+    assert!(core.i8080_step() == StepInstructionResult::NoOperation);
+    assert_eq!(core.program_counter, 2);
+
+    core.program_counter = 0;
+    core.undocumented_opcodes = UndocumentedOpcodes::Trap;
+    assert!(core.i8080_step() == StepInstructionResult::Ok);
+    assert!(core.i8080_step() == StepInstructionResult::IllegalOpcode { pc: 1, opcode: 0xFD });
+}
@@ -0,0 +1,108 @@
+use i8080_emulator::crc32;
+use i8080_emulator::invaders::video::{Framebuffer, Overlay, BLACK, GREEN, RED, SCREEN_HEIGHT, SCREEN_WIDTH, WHITE};
+
+const VIDEO_RAM_SIZE: usize = 0x1C00;
+
+// Lights the raster bit that ends up at x, y of the rotated screen
+fn light(video_ram: &mut [u8], x: usize, y: usize) {
+    let raster_x = SCREEN_HEIGHT - 1 - y;
+    video_ram[x * 32 + raster_x / 8] |= 1 << (raster_x % 8);
+}
+
+#[test]
+fn raster_is_rotated_counter_clockwise() {
+    let mut video_ram = vec![0; VIDEO_RAM_SIZE];
+    // first byte bit 0 is the leftmost pixel of the first raster line, the bottom left corner
+    video_ram[0] = 0x01;
+    // line 10, byte 3 bit 5 is raster x 29
+    video_ram[10 * 32 + 3] = 0x20;
+    // the last bit of the last line is the top right corner
+    video_ram[VIDEO_RAM_SIZE - 1] = 0x80;
+
+    let frame = Framebuffer::render(&video_ram, None);
+    assert_eq!((frame.width, frame.height), (SCREEN_WIDTH, SCREEN_HEIGHT));
+    assert_eq!(frame.pixel(0, 255), WHITE);
+    assert_eq!(frame.pixel(10, 255 - 29), WHITE);
+    assert_eq!(frame.pixel(223, 0), WHITE);
+    let lit = frame.pixels.chunks_exact(4).filter(|pixel| *pixel == WHITE).count();
+    assert_eq!(lit, 3);
+    assert_eq!(frame.pixel(0, 0), BLACK);
+}
+
+#[test]
+fn overlay_bands() {
+    let mut video_ram = vec![0; VIDEO_RAM_SIZE];
+    let points = [
+        ((100, 10), WHITE),
+        ((100, 32), RED),
+        ((100, 63), RED),
+        ((100, 64), WHITE),
+        ((0, 184), GREEN),
+        ((223, 239), GREEN),
+        ((16, 250), GREEN),
+        ((133, 250), GREEN),
+        ((10, 250), WHITE),
+        ((134, 250), WHITE),
+    ];
+    for ((x, y), _) in points {
+        light(&mut video_ram, x, y);
+    }
+
+    let frame = Framebuffer::render(&video_ram, Some(&Overlay::cabinet()));
+    for ((x, y), color) in points {
+        assert_eq!(frame.pixel(x, y), color, "pixel {},{}", x, y);
+    }
+    // unlit pixels stay black under the gels
+    assert_eq!(frame.pixel(50, 40), BLACK);
+}
+
+#[test]
+fn ppm_header_and_size() {
+    let frame = Framebuffer::render(&vec![0xFF; VIDEO_RAM_SIZE], None);
+    let ppm = frame.to_ppm();
+    let header = b"P6\n224 256\n255\n";
+    assert_eq!(&ppm[..header.len()], header);
+    assert_eq!(ppm.len(), header.len() + SCREEN_WIDTH * SCREEN_HEIGHT * 3);
+    assert!(ppm[header.len()..].iter().all(|byte| *byte == 0xFF));
+}
+
+#[test]
+fn png_of_a_single_red_pixel() {
+    let frame = Framebuffer {
+        width: 1,
+        height: 1,
+        pixels: RED.to_vec(),
+    };
+    let png = frame.to_png();
+    assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+
+    // walk the chunks checking every CRC
+    let mut chunks = Vec::new();
+    let mut position = 8;
+    while position < png.len() {
+        let length = u32::from_be_bytes(png[position..position + 4].try_into().unwrap()) as usize;
+        let kind = &png[position + 4..position + 8];
+        let data = &png[position + 8..position + 8 + length];
+        let crc = u32::from_be_bytes(png[position + 8 + length..position + 12 + length].try_into().unwrap());
+        assert_eq!(crc, crc32(&png[position + 4..position + 8 + length]));
+        chunks.push((kind.to_vec(), data.to_vec(), crc));
+        position += 12 + length;
+    }
+    assert_eq!(position, png.len());
+    let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _, _)| kind.as_slice()).collect();
+    assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+
+    // 1x1, 8 bits per channel RGBA, the IHDR and IEND CRCs every such PNG has
+    let (_, header, crc) = &chunks[0];
+    assert_eq!(header, &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+    assert_eq!(*crc, 0x1F15C489);
+    assert_eq!(chunks[2].2, 0xAE426082);
+
+    // zlib header, one final stored block of the filter byte and the pixel, then the Adler-32
+    let (_, idat, _) = &chunks[1];
+    let raw = [0x00, 0xFF, 0x20, 0x20, 0xFF];
+    assert_eq!(&idat[..7], &[0x78, 0x01, 0x01, 0x05, 0x00, 0xFA, 0xFF]);
+    assert_eq!(&idat[7..12], &raw);
+    // a = 1 + the bytes, b = the sum of every running a
+    assert_eq!(&idat[12..], &0x05A0_023Fu32.to_be_bytes());
+}
@@ -0,0 +1,161 @@
+use i8080_emulator::z80::NMI_VECTOR;
+use i8080_emulator::{CpuModel, I8080Core, StepInstructionResult};
+
+fn z80(program: &[u8]) -> I8080Core {
+    let mut core = I8080Core::new();
+    core.model = CpuModel::Z80;
+    core.memory[..program.len()].copy_from_slice(program);
+    core.stack_pointer = 0x2000;
+    core
+}
+
+fn run(core: &mut I8080Core, steps: usize) {
+    for _ in 0..steps {
+        assert!(core.i8080_step() == StepInstructionResult::Ok);
+    }
+}
+
+#[test]
+fn index_registers() {
+    let program = [
+        0xDD, 0x21, 0x00, 0x10, // LD IX,0x1000
+        0xDD, 0x36, 0x05, 0x7F, // LD (IX+5),0x7F
+        0xDD, 0x34, 0x05, // INC (IX+5)
+        0xFD, 0x21, 0x10, 0x10, // LD IY,0x1010
+        0xFD, 0x7E, 0xF5, // LD A,(IY-11)
+        0xDD, 0x26, 0x42, // LD IXH,0x42
+        0xDD, 0xE5, // PUSH IX
+        0xE1, // POP HL
+    ];
+    let mut core = z80(&program);
+    run(&mut core, 3);
+    assert_eq!(core.memory[0x1005], 0x80);
+    // INC into 0x80 overflows
+    assert!(core.parity && core.sign && core.auxiliary_carry);
+    run(&mut core, 5);
+    assert_eq!(core.a, 0x80);
+    assert_eq!(core.z80.ix, 0x4200);
+    assert_eq!((core.h, core.l), (0x42, 0x00));
+    assert_eq!(core.cycles, 14 + 19 + 23 + 14 + 19 + 11 + 15 + 10);
+}
+
+#[test]
+fn alternate_registers() {
+    // LD BC,0x1234, SCF, EX AF,AF', EXX, LD BC,0x5678, EXX, EX AF,AF'
+    let mut core = z80(&[0x01, 0x34, 0x12, 0x37, 0x08, 0xD9, 0x01, 0x78, 0x56, 0xD9, 0x08]);
+    core.a = 0x99;
+    run(&mut core, 4);
+    assert_eq!(core.a, 0);
+    assert!(!core.carry);
+    assert_eq!(core.z80.bc_alternate, 0x1234);
+    run(&mut core, 3);
+    assert_eq!((core.b, core.c), (0x12, 0x34));
+    assert_eq!(core.z80.bc_alternate, 0x5678);
+    assert_eq!(core.a, 0x99);
+    assert!(core.carry);
+}
+
+#[test]
+fn relative_jumps_and_djnz() {
+    // LD B,3, loop: INC A, DJNZ loop, JR +2, HALT, HALT, JR NZ,-2 is skipped as Z is set by CP 3
+    let program = [0x06, 0x03, 0x3C, 0x10, 0xFD, 0x18, 0x02, 0x76, 0x76, 0xFE, 0x03, 0x20, 0xFE, 0x28, 0x00];
+    let mut core = z80(&program);
+    run(&mut core, 1 + 3 * 2 + 1 + 1 + 1);
+    assert_eq!(core.a, 3);
+    assert_eq!(core.program_counter, 0x000D);
+    run(&mut core, 1);
+    assert_eq!(core.program_counter, 0x000F);
+    assert_eq!(core.cycles, 7 + 3 * 4 + 13 + 13 + 8 + 12 + 7 + 7 + 12);
+}
+
+#[test]
+fn cb_and_ed_instructions() {
+    let program = [
+        0xCB, 0x7F, // BIT 7,A
+        0xCB, 0x38, // SRL B
+        0xED, 0x44, // NEG
+        0xED, 0x52, // SBC HL,DE
+        0xED, 0xB0, // LDIR
+    ];
+    let mut core = z80(&program);
+    core.a = 0x01;
+    core.b = 0x03;
+    core.h = 0x40;
+    core.e = 0x01;
+    run(&mut core, 1);
+    assert!(core.zero && core.auxiliary_carry);
+    run(&mut core, 1);
+    assert!(core.b == 1 && core.carry);
+    run(&mut core, 1);
+    assert_eq!(core.a, 0xFF);
+    assert!(core.carry && core.z80.subtract && core.sign);
+    // 0x4000 - 1 - carry
+    run(&mut core, 1);
+    assert_eq!((core.h, core.l), (0x3F, 0xFE));
+    assert!(!core.parity && !core.carry);
+
+    // copy 3 bytes from 0x3000 to 0x3100, one iteration per step
+    core.memory[0x3000..0x3003].copy_from_slice(b"Z80");
+    core.h = 0x30;
+    core.l = 0x00;
+    core.d = 0x31;
+    core.e = 0x00;
+    core.b = 0;
+    core.c = 3;
+    let before = core.cycles;
+    run(&mut core, 3);
+    assert_eq!(&core.memory[0x3100..0x3103], b"Z80");
+    assert_eq!(core.program_counter, program.len() as u16);
+    assert!(!core.parity);
+    assert_eq!(core.cycles - before, 21 + 21 + 16);
+}
+
+#[test]
+fn interrupt_modes() {
+    // EI, IM 2, then NOPs
+    let mut core = z80(&[0xFB, 0xED, 0x5E]);
+    core.z80.i = 0x40;
+    core.memory[0x4010..0x4012].copy_from_slice(&[0x34, 0x12]);
+    run(&mut core, 1);
+    assert!(!core.i8080_interrupt(0x10));
+    run(&mut core, 1);
+    assert!(core.i8080_interrupt(0x10));
+    assert_eq!(core.program_counter, 0x1234);
+    assert!(!core.interrupts_enabled && !core.z80.iff2);
+
+    // IM 1 ignores the data bus, IM 0 only takes an RST
+    core.z80.interrupt_mode = 1;
+    core.interrupts_enabled = true;
+    assert!(core.z80_interrupt(0xFF));
+    assert_eq!(core.program_counter, 0x0038);
+    core.z80.interrupt_mode = 0;
+    core.interrupts_enabled = true;
+    assert!(!core.z80_interrupt(0x00));
+    assert!(core.z80_interrupt(0xD7));
+    assert_eq!(core.program_counter, 0x0010);
+
+    // NMI ignores IFF1 and RETN brings it back from IFF2
+    core.interrupts_enabled = true;
+    core.z80.iff2 = true;
+    core.memory[NMI_VECTOR as usize..NMI_VECTOR as usize + 2].copy_from_slice(&[0xED, 0x45]);
+    core.z80_nmi();
+    assert!(!core.interrupts_enabled);
+    assert_eq!(core.program_counter, NMI_VECTOR);
+    run(&mut core, 1);
+    assert_eq!(core.program_counter, 0x0010);
+    assert!(core.interrupts_enabled);
+}
+
+#[test]
+fn arithmetic_flags() {
+    // LD A,0x7F, ADD A,1, DAA on 0x15 + 0x27, PUSH AF
+    let mut core = z80(&[0x3E, 0x7F, 0xC6, 0x01, 0x3E, 0x15, 0xC6, 0x27, 0x27, 0xF5]);
+    run(&mut core, 2);
+    assert_eq!(core.a, 0x80);
+    assert!(core.parity && core.sign && core.auxiliary_carry && !core.z80.subtract);
+    run(&mut core, 3);
+    assert_eq!(core.a, 0x42);
+    run(&mut core, 1);
+    assert_eq!(core.memory[0x1FFE], core.z80_flags());
+    assert_eq!(core.z80_flags() & 0x02, 0);
+}